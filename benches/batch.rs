@@ -0,0 +1,48 @@
+//! Compares element-by-element updates with batch updates for each key source.
+//!
+//! Run with `cargo bench --bench batch`. Bench builds have no debug assertions, so the batch
+//! updates take the unchecked path.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use zobristhash_set::{FxKeys, KeySource, SplitMixKeys, WordKeys, ZobristHashSet};
+
+const ELEMENTS: u64 = 1 << 16;
+const ROUNDS: u32 = 200;
+
+fn time(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn compare<K: KeySource<u64> + Copy>(name: &str, keys: K, elements: &[u64]) {
+    let looped = time(|| {
+        let mut set = ZobristHashSet::with_keys(keys);
+        for element in elements {
+            set.add(black_box(element));
+        }
+        black_box(set.hash());
+    });
+    let batched = time(|| {
+        let mut set = ZobristHashSet::with_keys(keys);
+        set.add_batch(black_box(elements));
+        black_box(set.hash());
+    });
+    let per_element = |duration: Duration| duration.as_secs_f64() * 1e9 / elements.len() as f64;
+    println!(
+        "{name:>12}: add {:6.2} ns/element, add_batch {:6.2} ns/element",
+        per_element(looped),
+        per_element(batched)
+    );
+}
+
+fn main() {
+    let elements: Vec<u64> = (0..ELEMENTS).collect();
+    compare("FxKeys", FxKeys, &elements);
+    compare("SplitMixKeys", SplitMixKeys, &elements);
+    compare("WordKeys", WordKeys, &elements);
+}
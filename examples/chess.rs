@@ -71,7 +71,7 @@ impl ChessBoard {
     }
 
     pub fn hash(&self) -> u64 {
-        self.zobrist.into()
+        self.zobrist.hash()
     }
 }
 
@@ -0,0 +1,39 @@
+/* C API of the zobristhash_set crate, built with the `ffi` feature. */
+
+#ifndef ZOBRISTHASH_SET_H
+#define ZOBRISTHASH_SET_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A Zobrist hash of a set of byte strings, equal on every platform. */
+typedef struct ZobristSet ZobristSet;
+
+/* A transposition table storing one uint64_t per position. */
+typedef struct ZobristTable ZobristTable;
+
+/* Constructors return NULL only if they panic; running out of memory aborts the process. The
+ * add and remove functions return false, leaving the set unchanged, when the debug checker
+ * rejects the update. */
+
+ZobristSet *zobrist_new(void);
+void zobrist_free(ZobristSet *set);
+bool zobrist_add_bytes(ZobristSet *set, const uint8_t *bytes, size_t len);
+bool zobrist_remove_bytes(ZobristSet *set, const uint8_t *bytes, size_t len);
+uint64_t zobrist_value(const ZobristSet *set);
+
+ZobristTable *zobrist_tt_new(size_t entries);
+void zobrist_tt_free(ZobristTable *table);
+void zobrist_tt_store(ZobristTable *table, uint64_t hash, uint64_t value);
+bool zobrist_tt_probe(const ZobristTable *table, uint64_t hash, uint64_t *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
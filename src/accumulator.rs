@@ -0,0 +1,265 @@
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Incrementally maintained state derived from the elements of a set, such as a material
+/// balance or piece-square sums.
+///
+/// A [`ZobristWithAccumulators`] calls these methods after every update of its hash. Tuples of
+/// accumulators are accumulators too, so several can be driven by one set.
+pub trait Accumulator<E> {
+    /// Called after `element` was added.
+    fn on_add(&mut self, element: &E);
+
+    /// Called after `element` was removed.
+    fn on_remove(&mut self, element: &E);
+}
+
+/// The accumulator that ignores every update.
+impl<E> Accumulator<E> for () {
+    fn on_add(&mut self, _element: &E) {}
+
+    fn on_remove(&mut self, _element: &E) {}
+}
+
+macro_rules! tuple_accumulator {
+    ($($name:ident $index:tt),+) => {
+        impl<E, $($name: Accumulator<E>),+> Accumulator<E> for ($($name,)+) {
+            fn on_add(&mut self, element: &E) {
+                $(self.$index.on_add(element);)+
+            }
+
+            fn on_remove(&mut self, element: &E) {
+                $(self.$index.on_remove(element);)+
+            }
+        }
+    };
+}
+
+tuple_accumulator!(A 0);
+tuple_accumulator!(A 0, B 1);
+tuple_accumulator!(A 0, B 1, C 2);
+tuple_accumulator!(A 0, B 1, C 2, D 3);
+
+/// A running total of a score each element contributes, such as its material value.
+///
+/// The score of an element is added when it is added and subtracted when it is removed, so
+/// [`score`](Self::score) is always the sum over the present elements.
+///
+/// ```rust
+/// use zobristhash_set::{ScoreAccumulator, ZobristWithAccumulators};
+///
+/// let value = |(piece, _): &(&str, u8)| match *piece {
+///     "queen" => 900,
+///     "rook" => 500,
+///     _ => 100,
+/// };
+/// let mut position = ZobristWithAccumulators::new(ScoreAccumulator::new(value));
+/// position.add(&("queen", 3));
+/// position.add(&("rook", 0));
+/// position.remove(&("rook", 0));
+/// assert_eq!(position.score(), 900);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ScoreAccumulator<F> {
+    score: i32,
+    value: F,
+}
+
+impl<F> ScoreAccumulator<F> {
+    /// Creates a zero score, with `value` giving the score of each element.
+    pub fn new(value: F) -> Self {
+        Self { score: 0, value }
+    }
+
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+}
+
+impl<E, F: Fn(&E) -> i32> Accumulator<E> for ScoreAccumulator<F> {
+    fn on_add(&mut self, element: &E) {
+        self.score += (self.value)(element);
+    }
+
+    fn on_remove(&mut self, element: &E) {
+        self.score -= (self.value)(element);
+    }
+}
+
+/// A Zobrist hash that updates user-supplied [`Accumulator`]s in lockstep with the hash.
+///
+/// Incremental evaluation and hashing then share one update path: a move is applied once, and
+/// the evaluation terms can never disagree with the hashed position.
+///
+/// ```rust
+/// use zobristhash_set::{Accumulator, ZobristWithAccumulators};
+///
+/// #[derive(Default)]
+/// struct Material(i32);
+///
+/// impl Accumulator<(&str, u8)> for Material {
+///     fn on_add(&mut self, (piece, _): &(&str, u8)) {
+///         self.0 += if *piece == "queen" { 9 } else { 1 };
+///     }
+///
+///     fn on_remove(&mut self, (piece, _): &(&str, u8)) {
+///         self.0 -= if *piece == "queen" { 9 } else { 1 };
+///     }
+/// }
+///
+/// #[derive(Default)]
+/// struct Count(usize);
+///
+/// impl<E> Accumulator<E> for Count {
+///     fn on_add(&mut self, _: &E) {
+///         self.0 += 1;
+///     }
+///
+///     fn on_remove(&mut self, _: &E) {
+///         self.0 -= 1;
+///     }
+/// }
+///
+/// let mut position = ZobristWithAccumulators::new((Material::default(), Count::default()));
+/// position.add(&("queen", 3));
+/// position.add(&("pawn", 12));
+/// position.remove(&("pawn", 12));
+///
+/// let (material, count) = position.accumulators();
+/// assert_eq!((material.0, count.0), (9, 1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ZobristWithAccumulators<E, A, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    accumulators: A,
+}
+
+impl<E, A> ZobristWithAccumulators<E, A> {
+    /// Creates an empty set driving `accumulators`.
+    pub fn new(accumulators: A) -> Self {
+        Self::with_keys(FxKeys, accumulators)
+    }
+}
+
+impl<E, A, K> ZobristWithAccumulators<E, A, K> {
+    /// Creates an empty set that derives element keys from `keys` and drives `accumulators`.
+    pub fn with_keys(keys: K, accumulators: A) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            accumulators,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    pub fn accumulators(&self) -> &A {
+        &self.accumulators
+    }
+
+    /// Returns the accumulators, for resetting or reconfiguring them.
+    ///
+    /// Changes made through it are not reflected in the hash.
+    pub fn accumulators_mut(&mut self) -> &mut A {
+        &mut self.accumulators
+    }
+
+    /// Returns the set and the accumulators.
+    pub fn into_parts(self) -> (ZobristHashSet<E, K>, A) {
+        (self.set, self.accumulators)
+    }
+}
+
+impl<E, F, K> ZobristWithAccumulators<E, ScoreAccumulator<F>, K> {
+    /// Returns the score of the present elements.
+    pub fn score(&self) -> i32 {
+        self.accumulators.score
+    }
+}
+
+impl<E, A, K> From<ZobristWithAccumulators<E, A, K>> for u64 {
+    fn from(set: ZobristWithAccumulators<E, A, K>) -> u64 {
+        set.set.hash
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, A: Accumulator<E>, K: KeySource<E>>
+    ZobristWithAccumulators<E, A, K>
+{
+    /// Adds `key`, updates the accumulators and returns its 64-bit key.
+    pub fn add(&mut self, key: &E) -> u64 {
+        let element_key = self.set.add(key);
+        self.accumulators.on_add(key);
+        element_key
+    }
+
+    /// Removes `key`, updates the accumulators and returns its 64-bit key.
+    pub fn remove(&mut self, key: &E) -> u64 {
+        let element_key = self.set.remove(key);
+        self.accumulators.on_remove(key);
+        element_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Sum(i64);
+
+    impl Accumulator<i64> for Sum {
+        fn on_add(&mut self, element: &i64) {
+            self.0 += element;
+        }
+
+        fn on_remove(&mut self, element: &i64) {
+            self.0 -= element;
+        }
+    }
+
+    #[derive(Default)]
+    struct Log(Vec<(bool, i64)>);
+
+    impl Accumulator<i64> for Log {
+        fn on_add(&mut self, element: &i64) {
+            self.0.push((true, *element));
+        }
+
+        fn on_remove(&mut self, element: &i64) {
+            self.0.push((false, *element));
+        }
+    }
+
+    #[test]
+    fn accumulators_follow_the_hash() {
+        let mut set = ZobristWithAccumulators::new((Sum::default(), Log::default()));
+        set.add(&5);
+        set.add(&7);
+        set.remove(&5);
+        let (sum, log) = set.accumulators();
+        assert_eq!(sum.0, 7);
+        assert_eq!(log.0, [(true, 5), (true, 7), (false, 5)]);
+
+        let mut plain = ZobristHashSet::empty();
+        plain.add(&7i64);
+        assert_eq!(set.hash(), plain.hash());
+
+        let mut unit = ZobristWithAccumulators::new(());
+        unit.add(&7i64);
+        assert_eq!(u64::from(unit), plain.hash());
+    }
+
+    #[test]
+    fn scores_sum_present_elements() {
+        let mut scored = ZobristWithAccumulators::new(ScoreAccumulator::new(|x: &i64| *x as i32));
+        for x in 0..10 {
+            scored.add(&x);
+        }
+        scored.remove(&4);
+        assert_eq!(scored.score(), 41);
+        assert_eq!(scored.accumulators().score(), 41);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::ops::retain_valid;
+use crate::{Op, ZobristHashSet};
+use std::hash::Hash;
+
+#[cfg(feature = "proptest")]
+use proptest::collection::{vec, SizeRange};
+#[cfg(feature = "proptest")]
+use proptest::prelude::*;
+
+/// Returns a strategy for single operations on elements drawn from `element`.
+#[cfg(feature = "proptest")]
+pub fn op_strategy<S>(element: S) -> impl Strategy<Value = Op<S::Value>> + Clone
+where
+    S: Strategy + Clone,
+    S::Value: Clone,
+{
+    prop_oneof![
+        4 => element.clone().prop_map(Op::Add),
+        2 => element.clone().prop_map(Op::Remove),
+        1 => Just(Op::Clear),
+        3 => element.prop_map(Op::Toggle),
+    ]
+}
+
+/// Returns a strategy for operation sequences that [`ZobristHashSet::apply_ops`] accepts.
+///
+/// Sequences never add a present element or remove an absent one, so they may come out shorter
+/// than `len` asks for.
+///
+/// ```rust
+/// use proptest::prelude::*;
+/// use std::collections::HashSet;
+/// use zobristhash_set::{apply_reference_ops, ops_strategy, ZobristHashSet};
+///
+/// proptest!(|(ops in ops_strategy(0..8u8, 0..32))| {
+///     let mut hash = ZobristHashSet::empty();
+///     hash.apply_ops(&ops);
+///     let mut reference = HashSet::new();
+///     apply_reference_ops(&mut reference, &ops);
+///     prop_assert_eq!(hash.verify(&reference), Ok(()));
+/// });
+/// ```
+#[cfg(feature = "proptest")]
+pub fn ops_strategy<S>(
+    element: S,
+    len: impl Into<SizeRange>,
+) -> impl Strategy<Value = Vec<Op<S::Value>>>
+where
+    S: Strategy + Clone,
+    S::Value: Hash + Eq + Clone,
+{
+    vec(op_strategy(element), len).prop_map(retain_valid)
+}
+
+#[cfg(feature = "proptest")]
+impl<E: Arbitrary + Clone + 'static> Arbitrary for Op<E>
+where
+    E::Strategy: 'static,
+{
+    type Parameters = E::Parameters;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        op_strategy(any_with::<E>(args).boxed()).boxed()
+    }
+}
+
+/// Generates the set reached by a random sequence of operations.
+#[cfg(feature = "proptest")]
+impl<E> Arbitrary for ZobristHashSet<E>
+where
+    E: Arbitrary + Hash + Eq + Clone + std::fmt::Debug + 'static,
+    E::Strategy: 'static,
+{
+    type Parameters = E::Parameters;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        ops_strategy(any_with::<E>(args).boxed(), 0..64)
+            .prop_map(|ops| {
+                let mut set = ZobristHashSet::empty();
+                set.apply_ops(&ops);
+                set
+            })
+            .boxed()
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<E: quickcheck::Arbitrary> quickcheck::Arbitrary for Op<E> {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let element = <E as quickcheck::Arbitrary>::arbitrary;
+        match g.choose(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap() {
+            0..=3 => Op::Add(element(g)),
+            4..=5 => Op::Remove(element(g)),
+            6 => Op::Clear,
+            _ => Op::Toggle(element(g)),
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        match self {
+            Op::Add(element) => Box::new(element.shrink().map(Op::Add)),
+            Op::Remove(element) => Box::new(element.shrink().map(Op::Remove)),
+            Op::Clear => quickcheck::empty_shrinker(),
+            Op::Toggle(element) => Box::new(element.shrink().map(Op::Toggle)),
+        }
+    }
+}
+
+/// Generates the set reached by a random sequence of operations, dropping the ones the checker
+/// would reject.
+#[cfg(feature = "quickcheck")]
+impl<E> quickcheck::Arbitrary for ZobristHashSet<E>
+where
+    E: quickcheck::Arbitrary + Hash + Eq + std::fmt::Debug,
+{
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let ops = retain_valid(<Vec<Op<E>> as quickcheck::Arbitrary>::arbitrary(g));
+        let mut set = ZobristHashSet::empty();
+        set.apply_ops(&ops);
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_reference_ops;
+    use std::collections::HashSet;
+
+    #[cfg(feature = "proptest")]
+    proptest! {
+        #[test]
+        fn generated_ops_match_the_reference(ops in ops_strategy(0..16u8, 0..64)) {
+            let mut hash = ZobristHashSet::empty();
+            hash.apply_ops(&ops);
+            let mut reference = HashSet::new();
+            apply_reference_ops(&mut reference, &ops);
+            prop_assert_eq!(hash.verify(&reference), Ok(()));
+        }
+
+        #[test]
+        fn toggling_twice_restores_generated_sets(set in any::<ZobristHashSet<u8>>(), x: u8) {
+            let before = set.hash();
+            let mut toggled = set;
+            toggled.apply_ops(&[Op::Toggle(x), Op::Toggle(x)]);
+            prop_assert_eq!(toggled.hash(), before);
+        }
+    }
+
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn quickcheck_ops_are_valid() {
+        fn valid(ops: Vec<Op<u8>>) -> bool {
+            let ops = retain_valid(ops);
+            let mut hash = ZobristHashSet::empty();
+            hash.apply_ops(&ops);
+            let mut reference = HashSet::new();
+            apply_reference_ops(&mut reference, &ops);
+            hash.verify(&reference).is_ok()
+        }
+        quickcheck::quickcheck(valid as fn(Vec<Op<u8>>) -> bool);
+    }
+}
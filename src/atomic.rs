@@ -0,0 +1,150 @@
+use crate::{FxKeys, KeySource};
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+use rustc_hash::FxHashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+use std::sync::Mutex;
+
+/// A Zobrist hash that several threads can update at once.
+///
+/// XOR is commutative, so concurrent updates can be applied in any order with
+/// [`AtomicU64::fetch_xor`] and no lock. Updates use relaxed ordering: the value is final once
+/// the updating threads have been joined or otherwise synchronized with. In checked builds the
+/// elements are tracked behind a mutex, which serializes updates.
+///
+/// ```rust
+/// use zobristhash_set::{AtomicZobristHashSet, ZobristHashSet};
+///
+/// let shared = AtomicZobristHashSet::new();
+/// std::thread::scope(|scope| {
+///     for row in 0..4u8 {
+///         let shared = &shared;
+///         scope.spawn(move || {
+///             for column in 0..8u8 {
+///                 shared.add(&(row, column));
+///             }
+///         });
+///     }
+/// });
+///
+/// let mut expected = ZobristHashSet::empty();
+/// for row in 0..4u8 {
+///     for column in 0..8u8 {
+///         expected.add(&(row, column));
+///     }
+/// }
+/// assert_eq!(shared.hash(), u64::from(expected));
+/// ```
+#[derive(Debug)]
+pub struct AtomicZobristHashSet<E, K = FxKeys> {
+    hash: AtomicU64,
+    keys: K,
+    _data: std::marker::PhantomData<fn(&E)>,
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    checker: Mutex<FxHashSet<E>>,
+}
+
+impl<E> AtomicZobristHashSet<E> {
+    pub fn new() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E> Default for AtomicZobristHashSet<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, K> AtomicZobristHashSet<E, K> {
+    /// Creates an empty set that derives element keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        Self {
+            hash: AtomicU64::new(0),
+            keys,
+            _data: std::marker::PhantomData,
+            #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+            checker: Mutex::default(),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash.load(Ordering::Relaxed)
+    }
+}
+
+impl<E, K> From<AtomicZobristHashSet<E, K>> for u64 {
+    fn from(hash: AtomicZobristHashSet<E, K>) -> u64 {
+        hash.hash.into_inner()
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> AtomicZobristHashSet<E, K> {
+    pub fn add(&self, key: &E) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        {
+            let mut checker = self.checker.lock().unwrap_or_else(|e| e.into_inner());
+            assert!(
+                checker.insert(key.clone()),
+                "duplicate add of {key:?}: the element is already among the {} tracked elements",
+                checker.len()
+            );
+        }
+        self.hash.fetch_xor(self.keys.key(key), Ordering::Relaxed);
+    }
+
+    pub fn remove(&self, key: &E) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        {
+            let mut checker = self.checker.lock().unwrap_or_else(|e| e.into_inner());
+            assert!(
+                checker.remove(key),
+                "missing remove of {key:?}: the element is not among the {} tracked elements",
+                checker.len()
+            );
+        }
+        self.hash.fetch_xor(self.keys.key(key), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZobristHashSet;
+
+    #[test]
+    fn concurrent_updates_match_sequential_ones() {
+        let shared = AtomicZobristHashSet::new();
+        std::thread::scope(|scope| {
+            for thread in 0..8u32 {
+                let shared = &shared;
+                scope.spawn(move || {
+                    for i in 0..500 {
+                        shared.add(&(thread * 1000 + i));
+                    }
+                    for i in 0..250 {
+                        shared.remove(&(thread * 1000 + i));
+                    }
+                });
+            }
+        });
+        let mut expected = ZobristHashSet::empty();
+        for thread in 0..8u32 {
+            for i in 250..500 {
+                expected.add(&(thread * 1000 + i));
+            }
+        }
+        assert_eq!(u64::from(shared), u64::from(expected));
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate add of 1: the element is already among the 1 tracked")]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn double_add_debug() {
+        let set = AtomicZobristHashSet::new();
+        set.add(&1);
+        set.add(&1);
+    }
+}
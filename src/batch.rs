@@ -0,0 +1,87 @@
+use crate::{KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> ZobristHashSet<E, K> {
+    /// Adds every element of `elements`.
+    ///
+    /// The result is the same as calling [`add`](Self::add) for each element. Without the debug
+    /// checker the keys are combined by [`KeySource::xor_keys`] and the hash is updated once;
+    /// [`WordKeys`](crate::WordKeys) derives four keys at a time there with AVX2. In checked
+    /// builds the elements are added one by one. `cargo bench --bench batch` compares the key
+    /// sources.
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// let squares: Vec<u64> = (0..64).collect();
+    /// let mut batched = ZobristHashSet::empty();
+    /// batched.add_batch(&squares);
+    ///
+    /// let mut looped = ZobristHashSet::empty();
+    /// for square in &squares {
+    ///     looped.add(square);
+    /// }
+    /// assert_eq!(u64::from(batched), u64::from(looped));
+    /// ```
+    pub fn add_batch(&mut self, elements: &[E]) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("add_batch", len = elements.len()).entered();
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        for element in elements {
+            self.add(element);
+        }
+        #[cfg(not(all(debug_assertions, feature = "check_set_behavior")))]
+        {
+            self.hash ^= self.keys.xor_keys(elements);
+            self.count_updates(elements.len() as i64);
+            #[cfg(feature = "metrics")]
+            crate::telemetry::adds().increment(elements.len() as u64);
+            #[cfg(feature = "tracing")]
+            tracing::trace!(op = "add_batch", hash = self.hash);
+        }
+    }
+
+    /// Removes every element of `elements`, like [`add_batch`](Self::add_batch).
+    pub fn remove_batch(&mut self, elements: &[E]) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("remove_batch", len = elements.len()).entered();
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        for element in elements {
+            self.remove(element);
+        }
+        #[cfg(not(all(debug_assertions, feature = "check_set_behavior")))]
+        {
+            self.hash ^= self.keys.xor_keys(elements);
+            self.count_updates(-(elements.len() as i64));
+            #[cfg(feature = "metrics")]
+            crate::telemetry::removes().increment(elements.len() as u64);
+            #[cfg(feature = "tracing")]
+            tracing::trace!(op = "remove_batch", hash = self.hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_match_single_updates() {
+        for len in [0usize, 1, 7, 8, 9, 100] {
+            let elements: Vec<(u32, u8)> = (0..len as u32).map(|i| (i, (i % 7) as u8)).collect();
+            let mut batched = ZobristHashSet::empty();
+            batched.add_batch(&elements);
+            let mut looped = ZobristHashSet::empty();
+            for element in &elements {
+                looped.add(element);
+            }
+            assert_eq!(batched.hash, looped.hash);
+            batched.remove_batch(&elements[..len / 2]);
+            for element in &elements[..len / 2] {
+                looped.remove(element);
+            }
+            assert_eq!(batched.hash, looped.hash);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Bulk updates for sets of `(tag, square)` elements, with squares given as a 64-bit bitboard.
+impl<T: Hash + Eq + Clone + Debug, K: KeySource<(T, u8)>> ZobristHashSet<(T, u8), K> {
+    /// Adds `(tag, square)` for every square whose bit is set in `bitboard`.
+    ///
+    /// Without the debug checker the keys of the squares are folded together and the hash is
+    /// updated once. In checked builds every square is added, and checked, on its own.
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// let mut by_bits = ZobristHashSet::empty();
+    /// by_bits.add_bits('P', 0x0000_0000_0000_ff00);
+    ///
+    /// let mut by_square = ZobristHashSet::empty();
+    /// for square in 8..16 {
+    ///     by_square.add(&('P', square));
+    /// }
+    /// assert_eq!(u64::from(by_bits), u64::from(by_square));
+    /// ```
+    pub fn add_bits(&mut self, tag: T, bitboard: u64) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        for square in squares(bitboard) {
+            self.add(&(tag.clone(), square));
+        }
+        #[cfg(not(all(debug_assertions, feature = "check_set_behavior")))]
+        {
+            self.hash ^= self.xor_bits(tag, bitboard);
+            self.count_updates(bitboard.count_ones().into());
+            #[cfg(feature = "metrics")]
+            crate::telemetry::adds().increment(bitboard.count_ones().into());
+            #[cfg(feature = "tracing")]
+            tracing::trace!(op = "add_bits", hash = self.hash);
+        }
+    }
+
+    /// Removes `(tag, square)` for every square whose bit is set in `bitboard`, like
+    /// [`add_bits`](Self::add_bits).
+    pub fn remove_bits(&mut self, tag: T, bitboard: u64) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        for square in squares(bitboard) {
+            self.remove(&(tag.clone(), square));
+        }
+        #[cfg(not(all(debug_assertions, feature = "check_set_behavior")))]
+        {
+            self.hash ^= self.xor_bits(tag, bitboard);
+            self.count_updates(-i64::from(bitboard.count_ones()));
+            #[cfg(feature = "metrics")]
+            crate::telemetry::removes().increment(bitboard.count_ones().into());
+            #[cfg(feature = "tracing")]
+            tracing::trace!(op = "remove_bits", hash = self.hash);
+        }
+    }
+
+    #[cfg(not(all(debug_assertions, feature = "check_set_behavior")))]
+    fn xor_bits(&self, tag: T, bitboard: u64) -> u64 {
+        let mut element = (tag, 0);
+        squares(bitboard).fold(0, |hash, square| {
+            element.1 = square;
+            hash ^ self.keys.key(&element)
+        })
+    }
+}
+
+fn squares(mut bitboard: u64) -> impl Iterator<Item = u8> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+        let square = bitboard.trailing_zeros() as u8;
+        bitboard &= bitboard - 1;
+        Some(square)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_of_bitboard() {
+        let collected: Vec<u8> = squares(0x8000_0000_0000_0005).collect();
+        assert_eq!(collected, vec![0, 2, 63]);
+        assert_eq!(squares(0).count(), 0);
+    }
+
+    #[test]
+    fn add_and_remove_bits() {
+        let mut hash = ZobristHashSet::empty();
+        hash.add_bits(1u8, u64::MAX);
+        hash.remove_bits(1u8, 0xffff_ffff_0000_0000);
+        hash.remove_bits(1u8, 0x0000_0000_ffff_ffff);
+        assert_eq!(hash.hash, 0);
+
+        hash.add_bits(2u8, 0b1010);
+        let mut expected = ZobristHashSet::empty();
+        expected.add(&(2u8, 1u8));
+        expected.add(&(2u8, 3u8));
+        assert_eq!(hash.hash, expected.hash);
+    }
+}
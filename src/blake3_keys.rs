@@ -0,0 +1,78 @@
+use crate::keys::KeySource;
+use std::hash::{Hash, Hasher};
+
+/// A key source that derives each element's key with keyed BLAKE3.
+///
+/// `FxHash` keys are public knowledge, so an adversary who chooses the elements can construct
+/// sets whose hashes collide. With a secret 256-bit key, the element keys are unpredictable to
+/// anyone who does not know it, while hashing stays an XOR of 64-bit keys. Generate the key
+/// from a cryptographically secure source and keep it for as long as hashes must stay
+/// comparable.
+///
+/// ```rust
+/// use zobristhash_set::{Blake3Keys, ZobristHashSet};
+///
+/// let keys = Blake3Keys::new([7; 32]);
+/// let mut hash = ZobristHashSet::with_keys(keys);
+/// hash.add(&"e2e4");
+/// hash.add(&"e7e5");
+/// hash.remove(&"e2e4");
+///
+/// let mut expected = ZobristHashSet::with_keys(keys);
+/// expected.add(&"e7e5");
+/// assert_eq!(u64::from(hash), u64::from(expected));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Blake3Keys {
+    key: [u8; 32],
+}
+
+impl Blake3Keys {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+}
+
+impl std::fmt::Debug for Blake3Keys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Blake3Keys").finish_non_exhaustive()
+    }
+}
+
+impl<E: Hash> KeySource<E> for Blake3Keys {
+    fn key(&self, element: &E) -> u64 {
+        let mut hasher = Blake3Hasher(blake3::Hasher::new_keyed(&self.key));
+        element.hash(&mut hasher);
+        let mut key = [0; 8];
+        hasher.0.finalize_xof().fill(&mut key);
+        u64::from_le_bytes(key)
+    }
+}
+
+/// Feeds the bytes written by a [`Hash`] implementation into BLAKE3.
+struct Blake3Hasher(blake3::Hasher);
+
+impl Hasher for Blake3Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unreachable!("the key is read with `finalize_xof`")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_depend_on_the_secret() {
+        let a = Blake3Keys::new([1; 32]);
+        let b = Blake3Keys::new([2; 32]);
+        assert_eq!(a.key(&"x"), a.key(&"x"));
+        assert_ne!(a.key(&"x"), a.key(&"y"));
+        assert_ne!(a.key(&"x"), b.key(&"x"));
+        assert_eq!(format!("{a:?}"), "Blake3Keys { .. }");
+    }
+}
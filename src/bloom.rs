@@ -0,0 +1,115 @@
+use crate::keys::mix64;
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash paired with a Bloom filter over the same elements, answering approximate
+/// membership queries.
+///
+/// [`likely_contains`](Self::likely_contains) never misses an added element, and reports an
+/// element that was never added with about the false-positive rate the filter was sized for.
+/// A Bloom filter cannot forget: [`remove`](Self::remove) updates the hash, but the filter keeps
+/// answering `true` for the removed element.
+///
+/// ```rust
+/// use zobristhash_set::BloomZobristSet;
+///
+/// let mut expanded = BloomZobristSet::with_capacity(1000, 0.01);
+/// expanded.add(&"e2e4");
+/// assert!(expanded.likely_contains(&"e2e4"));
+///
+/// expanded.remove(&"e2e4");
+/// assert_eq!(expanded.hash(), 0);
+/// assert!(expanded.likely_contains(&"e2e4"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct BloomZobristSet<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    bits: Vec<u64>,
+    bit_count: u64,
+    probes: u32,
+}
+
+impl<E> BloomZobristSet<E> {
+    /// Creates an empty set whose filter holds `expected` elements at the given false-positive
+    /// rate.
+    pub fn with_capacity(expected: usize, false_positive_rate: f64) -> Self {
+        Self::with_keys(FxKeys, expected, false_positive_rate)
+    }
+}
+
+impl<E, K> BloomZobristSet<E, K> {
+    /// Like [`with_capacity`](BloomZobristSet::with_capacity), deriving keys from `keys`.
+    pub fn with_keys(keys: K, expected: usize, false_positive_rate: f64) -> Self {
+        assert!(
+            false_positive_rate > 0.0 && false_positive_rate < 1.0,
+            "false-positive rate must be in (0, 1)"
+        );
+        // The standard optimum: m = -n ln p / ln² 2 bits and k = m/n ln 2 probes.
+        let ln2 = std::f64::consts::LN_2;
+        let n = expected.max(1) as f64;
+        let bit_count = (-n * false_positive_rate.ln() / (ln2 * ln2))
+            .ceil()
+            .max(64.0) as u64;
+        let probes = ((bit_count as f64 / n) * ln2).round().clamp(1.0, 32.0) as u32;
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            bits: vec![0; bit_count.div_ceil(64) as usize],
+            bit_count,
+            probes,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the size of the filter in bytes.
+    pub fn filter_bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+}
+
+/// Yields the filter bits of an element with key `key`, by double hashing.
+fn positions(key: u64, bit_count: u64, probes: u32) -> impl Iterator<Item = u64> {
+    let step = mix64(key) | 1;
+    (0..probes as u64).map(move |i| key.wrapping_add(i.wrapping_mul(step)) % bit_count)
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> BloomZobristSet<E, K> {
+    pub fn add(&mut self, key: &E) {
+        let element_key = self.set.add(key);
+        for bit in positions(element_key, self.bit_count, self.probes) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        self.set.remove(key);
+    }
+
+    /// Returns `true` if `element` may have been added, and `false` if it certainly was not.
+    pub fn likely_contains(&self, element: &E) -> bool {
+        positions(self.set.keys.key(element), self.bit_count, self.probes)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn false_positive_rate_is_close_to_target() {
+        let mut set = BloomZobristSet::with_capacity(5_000, 0.01);
+        for i in 0..5_000u32 {
+            set.add(&i);
+        }
+        assert!((0..5_000u32).all(|i| set.likely_contains(&i)));
+        let false_positives = (5_000..105_000u32)
+            .filter(|i| set.likely_contains(i))
+            .count();
+        assert!(false_positives < 2_000, "{false_positives}");
+        assert!(set.filter_bytes() < 5_000 * 2);
+    }
+}
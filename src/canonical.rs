@@ -0,0 +1,201 @@
+use crate::{FxKeys, KeySource, ZobristError, ZobristHashSet};
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+/// Maps an element to the canonical representative of the elements it is equivalent to.
+///
+/// Used by [`CanonicalZobristHashSet`] before hashing. Closures taking `&E` implement it, so an
+/// adapter can capture per-set configuration such as the board size.
+pub trait KeyAdapter<E> {
+    type Canonical;
+
+    fn canonicalize(&self, element: &E) -> Self::Canonical;
+}
+
+impl<E, T, F: Fn(&E) -> T> KeyAdapter<E> for F {
+    type Canonical = T;
+
+    fn canonicalize(&self, element: &E) -> T {
+        self(element)
+    }
+}
+
+/// A Zobrist hash that canonicalizes each element with a [`KeyAdapter`] before hashing it.
+///
+/// Logically equal elements, such as coordinates in different notations or states that differ
+/// only in an irrelevant detail, then always contribute the same key. The adapter belongs to the
+/// set, so sets of the same element type can canonicalize differently.
+///
+/// The set underneath holds the canonical forms, and so does its debug checker: adding two
+/// representations of the same element is reported as a duplicate add, instead of letting their
+/// keys cancel out, and an element can be removed in any of its representations.
+///
+/// ```rust
+/// use zobristhash_set::{CanonicalZobristHashSet, ZobristHashSet};
+///
+/// // Squares given as (file, rank) or as an index in 0..64 hash the same.
+/// #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+/// enum Square {
+///     Coordinates(u8, u8),
+///     Index(u8),
+/// }
+///
+/// let index = |square: &Square| match *square {
+///     Square::Coordinates(file, rank) => rank * 8 + file,
+///     Square::Index(index) => index,
+/// };
+///
+/// let mut a = CanonicalZobristHashSet::new(index);
+/// a.add(&Square::Coordinates(4, 0));
+/// let mut b = CanonicalZobristHashSet::new(index);
+/// b.add(&Square::Index(4));
+/// assert_eq!(a.hash(), b.hash());
+///
+/// a.remove(&Square::Index(4));
+/// assert_eq!(a.hash(), ZobristHashSet::<u8>::empty().hash());
+/// ```
+pub struct CanonicalZobristHashSet<E, A: KeyAdapter<E>, K = FxKeys> {
+    adapter: A,
+    set: ZobristHashSet<A::Canonical, K>,
+    _marker: PhantomData<fn(&E)>,
+}
+
+impl<E, A: KeyAdapter<E> + Clone, K: Clone> Clone for CanonicalZobristHashSet<E, A, K>
+where
+    A::Canonical: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            adapter: self.adapter.clone(),
+            set: self.set.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E, A: KeyAdapter<E> + core::fmt::Debug, K: core::fmt::Debug> core::fmt::Debug
+    for CanonicalZobristHashSet<E, A, K>
+where
+    A::Canonical: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CanonicalZobristHashSet")
+            .field("adapter", &self.adapter)
+            .field("set", &self.set)
+            .finish()
+    }
+}
+
+impl<E, A: KeyAdapter<E>> CanonicalZobristHashSet<E, A> {
+    /// Creates an empty set canonicalizing elements with `adapter`.
+    pub fn new(adapter: A) -> Self {
+        Self::with_keys(adapter, FxKeys)
+    }
+}
+
+impl<E, A: KeyAdapter<E>, K> CanonicalZobristHashSet<E, A, K> {
+    /// Creates an empty set canonicalizing elements with `adapter` and deriving the keys of the
+    /// canonical forms from `keys`.
+    pub fn with_keys(adapter: A, keys: K) -> Self {
+        Self {
+            adapter,
+            set: ZobristHashSet::with_keys(keys),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the adapter.
+    pub fn adapter(&self) -> &A {
+        &self.adapter
+    }
+
+    /// Returns the set of canonical forms.
+    pub fn into_inner(self) -> ZobristHashSet<A::Canonical, K> {
+        self.set
+    }
+}
+
+impl<E, A: KeyAdapter<E>, K> From<CanonicalZobristHashSet<E, A, K>> for u64 {
+    fn from(set: CanonicalZobristHashSet<E, A, K>) -> u64 {
+        set.set.hash
+    }
+}
+
+impl<E, A, K> CanonicalZobristHashSet<E, A, K>
+where
+    A: KeyAdapter<E>,
+    A::Canonical: Hash + Eq + Clone + Debug,
+    K: KeySource<A::Canonical>,
+{
+    /// Adds the canonical form of `key` and returns its 64-bit key.
+    pub fn add(&mut self, key: &E) -> u64 {
+        self.set.add(&self.adapter.canonicalize(key))
+    }
+
+    /// Removes the canonical form of `key` and returns its 64-bit key.
+    pub fn remove(&mut self, key: &E) -> u64 {
+        self.set.remove(&self.adapter.canonicalize(key))
+    }
+
+    /// Adds the canonical form of `key` like [`ZobristHashSet::try_add`].
+    pub fn try_add(&mut self, key: &E) -> Result<u64, ZobristError> {
+        self.set.try_add(&self.adapter.canonicalize(key))
+    }
+
+    /// Removes the canonical form of `key` like [`ZobristHashSet::try_remove`].
+    pub fn try_remove(&mut self, key: &E) -> Result<u64, ZobristError> {
+        self.set.try_remove(&self.adapter.canonicalize(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SplitMixKeys;
+
+    /// Maps a cell of a `size` by `size` board to its orbit under the horizontal mirror.
+    struct Mirror {
+        size: i32,
+    }
+
+    impl KeyAdapter<(i32, i32)> for Mirror {
+        type Canonical = (i32, i32);
+
+        fn canonicalize(&self, &(x, y): &(i32, i32)) -> (i32, i32) {
+            (x.min(self.size - 1 - x), y)
+        }
+    }
+
+    #[test]
+    fn equivalent_elements_share_keys() {
+        let mut small = CanonicalZobristHashSet::with_keys(Mirror { size: 4 }, SplitMixKeys);
+        let mut mirrored = CanonicalZobristHashSet::with_keys(Mirror { size: 4 }, SplitMixKeys);
+        let mut large = CanonicalZobristHashSet::with_keys(Mirror { size: 8 }, SplitMixKeys);
+        small.add(&(0, 2));
+        mirrored.add(&(3, 2));
+        large.add(&(3, 2));
+        assert_eq!(small.hash(), mirrored.hash());
+        assert_ne!(small.hash(), large.hash());
+
+        mirrored.remove(&(0, 2));
+        assert_eq!(mirrored.hash(), 0);
+
+        let mut identity = CanonicalZobristHashSet::new(|x: &u32| *x);
+        identity.add(&7);
+        assert_eq!(identity.hash(), FxKeys.key(&7u32));
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate add of (0, 2)")]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn equivalent_elements_are_tracked_as_one() {
+        let mut set = CanonicalZobristHashSet::new(Mirror { size: 4 });
+        set.add(&(0, 2));
+        set.add(&(3, 2));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{FxKeys, KeySource, TrackedZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash that validates every update in all build profiles.
+///
+/// [`ZobristHashSet`](crate::ZobristHashSet) only checks for duplicate additions and unmatched
+/// removals in debug builds with the `check_set_behavior` feature, so tests run in release mode
+/// skip the validation. `CheckedZobristHashSet` has the same `add`/`remove` API but always
+/// stores its elements and panics on misuse, at the cost of a hash set lookup per update.
+///
+/// ```rust
+/// use zobristhash_set::{CheckedZobristHashSet, ZobristHashSet};
+///
+/// let mut checked = CheckedZobristHashSet::empty();
+/// checked.add(&"e4");
+/// checked.add(&"d4");
+/// checked.remove(&"e4");
+///
+/// let mut plain = ZobristHashSet::empty();
+/// plain.add(&"d4");
+/// assert_eq!(checked.hash(), u64::from(plain));
+/// ```
+///
+/// ```should_panic
+/// use zobristhash_set::CheckedZobristHashSet;
+///
+/// let mut checked = CheckedZobristHashSet::empty();
+/// checked.remove(&"e4"); // panics, even in release builds
+/// ```
+#[derive(Clone, Debug)]
+pub struct CheckedZobristHashSet<E, K = FxKeys> {
+    elements: TrackedZobristHashSet<E, K>,
+}
+
+impl<E> CheckedZobristHashSet<E> {
+    pub fn empty() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E> Default for CheckedZobristHashSet<E> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<E, K> CheckedZobristHashSet<E, K> {
+    /// Creates an empty set that derives element keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        Self {
+            elements: TrackedZobristHashSet::with_keys(keys),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.elements.hash()
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns the stored elements.
+    pub fn elements(&self) -> &TrackedZobristHashSet<E, K> {
+        &self.elements
+    }
+}
+
+impl<E, K> From<CheckedZobristHashSet<E, K>> for u64 {
+    fn from(set: CheckedZobristHashSet<E, K>) -> u64 {
+        set.hash()
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> CheckedZobristHashSet<E, K> {
+    /// Adds `key` and returns its 64-bit key.
+    ///
+    /// Panics if `key` is already present.
+    pub fn add(&mut self, key: &E) -> u64 {
+        assert!(
+            self.elements.insert(key.clone()),
+            "duplicate add of {key:?}: the element is already among the {} tracked elements",
+            self.elements.len()
+        );
+        self.elements.keys.key(key)
+    }
+
+    /// Removes `key` and returns its 64-bit key.
+    ///
+    /// Panics if `key` is not present.
+    pub fn remove(&mut self, key: &E) -> u64 {
+        assert!(
+            self.elements.remove(key),
+            "missing remove of {key:?}: the element is not among the {} tracked elements",
+            self.elements.len()
+        );
+        self.elements.keys.key(key)
+    }
+
+    pub fn contains(&self, key: &E) -> bool {
+        self.elements.contains(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_keys_and_tracks_elements() {
+        let mut set = CheckedZobristHashSet::empty();
+        let key = set.add(&5u32);
+        assert_eq!(key, set.hash());
+        assert!(set.contains(&5));
+        assert_eq!(set.remove(&5), key);
+        assert!(set.is_empty());
+        assert_eq!(set.hash(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate add of 1: the element is already among the 1 tracked")]
+    fn double_add_panics_in_every_profile() {
+        let mut set = CheckedZobristHashSet::empty();
+        set.add(&1u32);
+        set.add(&1u32);
+    }
+}
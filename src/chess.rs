@@ -0,0 +1,7 @@
+//! Chess-specific helpers.
+
+#[cfg(feature = "chess")]
+mod chess_board;
+pub mod polyglot;
+#[cfg(feature = "shakmaty")]
+mod shakmaty;
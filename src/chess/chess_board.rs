@@ -0,0 +1,206 @@
+use super::polyglot::{CastlingRight, Color, PolyglotHash, PolyglotKey, PolyglotKeys, Role};
+use ::chess::{Board, CastleRights, ChessMove, File, Piece, Square, ALL_COLORS, ALL_SQUARES};
+
+/// Hashing of [`chess`](::chess) crate boards with Polyglot keys.
+impl PolyglotHash {
+    /// Computes the hash of `board` from scratch.
+    ///
+    /// ```rust
+    /// use zobristhash_set::chess::polyglot::PolyglotHash;
+    ///
+    /// let hash = PolyglotHash::from_board(&chess::Board::default());
+    /// assert_eq!(u64::from(hash), 0x463b96181691fc9c);
+    /// ```
+    pub fn from_board(board: &Board) -> Self {
+        let mut hash = PolyglotHash::with_keys(PolyglotKeys);
+        for square in ALL_SQUARES {
+            if let (Some(piece), Some(color)) = (board.piece_on(square), board.color_on(square)) {
+                hash.add(&piece_key(color, piece, square));
+            }
+        }
+        for color in ALL_COLORS {
+            add_castle_rights(&mut hash, color, board.castle_rights(color));
+        }
+        // `Board::en_passant` is only set when an enemy pawn can capture, matching Polyglot.
+        if let Some(square) = board.en_passant() {
+            hash.add(&PolyglotKey::EnPassantFile(
+                square.get_file().to_index() as u8
+            ));
+        }
+        if board.side_to_move() == ::chess::Color::White {
+            hash.add(&PolyglotKey::WhiteToMove);
+        }
+        hash
+    }
+
+    /// Updates the hash for `mv` being made on `board`, the board before the move.
+    ///
+    /// ```rust
+    /// use chess::{Board, ChessMove, Square};
+    /// use zobristhash_set::chess::polyglot::PolyglotHash;
+    ///
+    /// let board = Board::default();
+    /// let mv = ChessMove::new(Square::E2, Square::E4, None);
+    ///
+    /// let mut hash = PolyglotHash::from_board(&board);
+    /// hash.make_move(&board, mv);
+    /// assert_eq!(u64::from(hash), 0x823c9b50fd114196);
+    /// ```
+    pub fn make_move(&mut self, board: &Board, mv: ChessMove) {
+        let us = board.side_to_move();
+        let source = mv.get_source();
+        let dest = mv.get_dest();
+        let moved = board
+            .piece_on(source)
+            .expect("the source square of a move must be occupied");
+
+        self.remove(&piece_key(us, moved, source));
+        if let Some(captured) = board.piece_on(dest) {
+            self.remove(&piece_key(!us, captured, dest));
+        }
+        self.add(&piece_key(us, mv.get_promotion().unwrap_or(moved), dest));
+
+        let file_distance = source
+            .get_file()
+            .to_index()
+            .abs_diff(dest.get_file().to_index());
+        if moved == Piece::King && file_distance == 2 {
+            let (rook_from, rook_to) = if dest.get_file() == File::G {
+                (File::H, File::F)
+            } else {
+                (File::A, File::D)
+            };
+            let rank = source.get_rank();
+            self.remove(&piece_key(
+                us,
+                Piece::Rook,
+                Square::make_square(rank, rook_from),
+            ));
+            self.add(&piece_key(
+                us,
+                Piece::Rook,
+                Square::make_square(rank, rook_to),
+            ));
+        }
+        if moved == Piece::Pawn && file_distance == 1 && board.piece_on(dest).is_none() {
+            let captured = Square::make_square(source.get_rank(), dest.get_file());
+            self.remove(&piece_key(!us, Piece::Pawn, captured));
+        }
+
+        let ours = board.castle_rights(us);
+        let theirs = board.castle_rights(!us);
+        remove_castle_rights(self, us, ours);
+        remove_castle_rights(self, !us, theirs);
+        add_castle_rights(
+            self,
+            us,
+            ours.remove(CastleRights::square_to_castle_rights(us, source)),
+        );
+        add_castle_rights(
+            self,
+            !us,
+            theirs.remove(CastleRights::square_to_castle_rights(!us, dest)),
+        );
+
+        if let Some(square) = board.en_passant() {
+            self.remove(&PolyglotKey::EnPassantFile(
+                square.get_file().to_index() as u8
+            ));
+        }
+        let rank_distance = source
+            .get_rank()
+            .to_index()
+            .abs_diff(dest.get_rank().to_index());
+        if moved == Piece::Pawn && rank_distance == 2 {
+            let their_pawns = board.pieces(Piece::Pawn) & board.color_combined(!us);
+            let can_capture = [dest.left(), dest.right()]
+                .into_iter()
+                .flatten()
+                .any(|square| {
+                    their_pawns & ::chess::BitBoard::from_square(square) != ::chess::EMPTY
+                });
+            if can_capture {
+                self.add(&PolyglotKey::EnPassantFile(dest.get_file().to_index() as u8));
+            }
+        }
+
+        if us == ::chess::Color::White {
+            self.remove(&PolyglotKey::WhiteToMove);
+        } else {
+            self.add(&PolyglotKey::WhiteToMove);
+        }
+    }
+}
+
+fn piece_key(color: ::chess::Color, piece: Piece, square: Square) -> PolyglotKey {
+    let color = match color {
+        ::chess::Color::White => Color::White,
+        ::chess::Color::Black => Color::Black,
+    };
+    let role = match piece {
+        Piece::Pawn => Role::Pawn,
+        Piece::Knight => Role::Knight,
+        Piece::Bishop => Role::Bishop,
+        Piece::Rook => Role::Rook,
+        Piece::Queen => Role::Queen,
+        Piece::King => Role::King,
+    };
+    PolyglotKey::Piece {
+        piece: (color, role),
+        square: square.to_index() as u8,
+    }
+}
+
+fn castling_keys(color: ::chess::Color, rights: CastleRights) -> impl Iterator<Item = PolyglotKey> {
+    let (kingside, queenside) = match color {
+        ::chess::Color::White => (CastlingRight::WhiteKingside, CastlingRight::WhiteQueenside),
+        ::chess::Color::Black => (CastlingRight::BlackKingside, CastlingRight::BlackQueenside),
+    };
+    [
+        rights.has_kingside().then_some(kingside),
+        rights.has_queenside().then_some(queenside),
+    ]
+    .into_iter()
+    .flatten()
+    .map(PolyglotKey::Castling)
+}
+
+fn add_castle_rights(hash: &mut PolyglotHash, color: ::chess::Color, rights: CastleRights) {
+    for key in castling_keys(color, rights) {
+        hash.add(&key);
+    }
+}
+
+fn remove_castle_rights(hash: &mut PolyglotHash, color: ::chess::Color, rights: CastleRights) {
+    for key in castling_keys(color, rights) {
+        hash.remove(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::polyglot;
+    use ::chess::MoveGen;
+    use rand::seq::IteratorRandom;
+
+    #[test]
+    fn incremental_matches_from_scratch() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let mut board = Board::default();
+            let mut hash = PolyglotHash::from_board(&board);
+            for _ in 0..200 {
+                let Some(mv) = MoveGen::new_legal(&board).choose(&mut rng) else {
+                    break;
+                };
+                hash.make_move(&board, mv);
+                board = board.make_move_new(mv);
+
+                assert_eq!(hash.hash, PolyglotHash::from_board(&board).hash);
+                let from_fen = polyglot::from_fen(&board.to_string()).unwrap();
+                assert_eq!(hash.hash, from_fen.hash);
+            }
+        }
+    }
+}
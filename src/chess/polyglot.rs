@@ -0,0 +1,290 @@
+//! Keys compatible with the [Polyglot](http://hgm.nubati.net/book_format.html) opening book format.
+//!
+//! A [`PolyglotHash`] built from the elements of a position has exactly the value Polyglot
+//! assigns to it, so it can be used to look positions up in existing `.bin` books and compared
+//! with hashes produced by other engines.
+//!
+//! ```rust
+//! use zobristhash_set::chess::polyglot::{self, Color, PolyglotHash, PolyglotKey, Role};
+//!
+//! let mut hash = polyglot::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+//!     .unwrap();
+//! assert_eq!(hash.hash(), 0x463b96181691fc9c);
+//!
+//! // 1. e4
+//! let pawn = (Color::White, Role::Pawn);
+//! hash.remove(&PolyglotKey::Piece { piece: pawn, square: 12 });
+//! hash.add(&PolyglotKey::Piece { piece: pawn, square: 28 });
+//! hash.remove(&PolyglotKey::WhiteToMove);
+//! assert_eq!(hash.hash(), 0x823c9b50fd114196);
+//! ```
+
+mod random;
+
+use crate::{FiniteDomain, KeySource, ZobristHashSet};
+use random::RANDOM64;
+use std::hash::{Hash, Hasher};
+
+/// Side of a piece.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+/// Kind of a piece.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+/// One of the four castling rights.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CastlingRight {
+    WhiteKingside,
+    WhiteQueenside,
+    BlackKingside,
+    BlackQueenside,
+}
+
+/// An element of a Polyglot position hash.
+///
+/// Squares are numbered from `0` (a1) to `63` (h8), file first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolyglotKey {
+    Piece {
+        piece: (Color, Role),
+        square: u8,
+    },
+    Castling(CastlingRight),
+    /// The file (`0` for a, `7` for h) of an en passant square.
+    ///
+    /// Polyglot only includes it when a pawn of the side to move can actually capture en passant.
+    EnPassantFile(u8),
+    WhiteToMove,
+}
+
+impl FiniteDomain for PolyglotKey {
+    const SIZE: usize = 781;
+
+    fn index(&self) -> usize {
+        match *self {
+            PolyglotKey::Piece {
+                piece: (color, role),
+                square,
+            } => {
+                let kind = 2 * role as usize + usize::from(color == Color::White);
+                64 * kind + square as usize
+            }
+            PolyglotKey::Castling(right) => 768 + right as usize,
+            PolyglotKey::EnPassantFile(file) => 772 + file as usize,
+            PolyglotKey::WhiteToMove => 780,
+        }
+    }
+}
+
+// Hashing the dense index keeps distinct elements from colliding in `FxHash`, which the derived
+// implementation does not guarantee for these small field values.
+impl Hash for PolyglotKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index().hash(state);
+    }
+}
+
+/// The key source holding Polyglot's `Random64` table.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PolyglotKeys;
+
+impl KeySource<PolyglotKey> for PolyglotKeys {
+    fn key(&self, element: &PolyglotKey) -> u64 {
+        RANDOM64[element.index()]
+    }
+}
+
+/// A set of [`PolyglotKey`]s hashed with Polyglot's keys.
+pub type PolyglotHash = ZobristHashSet<PolyglotKey, PolyglotKeys>;
+
+/// Computes the Polyglot hash of the position described by `fen`.
+///
+/// Only the first four fields (placement, side to move, castling rights and en passant square)
+/// are used. Returns `None` if they are malformed.
+pub fn from_fen(fen: &str) -> Option<PolyglotHash> {
+    let mut fields = fen.split_whitespace();
+    let placement = fields.next()?;
+    let side = fields.next()?;
+    let castling = fields.next()?;
+    let en_passant = fields.next()?;
+
+    let mut hash = PolyglotHash::with_keys(PolyglotKeys);
+    let mut board = [None; 64];
+
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return None;
+    }
+    for (i, rank) in ranks.iter().enumerate() {
+        let rank_index = 7 - i as u8;
+        let mut file = 0u8;
+        for c in rank.chars() {
+            if let Some(skip) = c.to_digit(10) {
+                file = file
+                    .checked_add(skip as u8)
+                    .filter(|&file| skip > 0 && file <= 8)?;
+                continue;
+            }
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let role = match c.to_ascii_lowercase() {
+                'p' => Role::Pawn,
+                'n' => Role::Knight,
+                'b' => Role::Bishop,
+                'r' => Role::Rook,
+                'q' => Role::Queen,
+                'k' => Role::King,
+                _ => return None,
+            };
+            if file >= 8 {
+                return None;
+            }
+            let square = 8 * rank_index + file;
+            board[square as usize] = Some((color, role));
+            hash.add(&PolyglotKey::Piece {
+                piece: (color, role),
+                square,
+            });
+            file += 1;
+        }
+        if file != 8 {
+            return None;
+        }
+    }
+
+    let white_to_move = match side {
+        "w" => true,
+        "b" => false,
+        _ => return None,
+    };
+    if white_to_move {
+        hash.add(&PolyglotKey::WhiteToMove);
+    }
+
+    if castling != "-" {
+        let mut seen = Vec::with_capacity(4);
+        for c in castling.chars() {
+            if seen.contains(&c) {
+                return None;
+            }
+            seen.push(c);
+            let right = match c {
+                'K' => CastlingRight::WhiteKingside,
+                'Q' => CastlingRight::WhiteQueenside,
+                'k' => CastlingRight::BlackKingside,
+                'q' => CastlingRight::BlackQueenside,
+                _ => return None,
+            };
+            hash.add(&PolyglotKey::Castling(right));
+        }
+    }
+
+    if en_passant != "-" {
+        let bytes = en_passant.as_bytes();
+        if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) || !b"36".contains(&bytes[1]) {
+            return None;
+        }
+        let file = bytes[0] - b'a';
+        // The capturing pawn stands on the fifth rank from its own side.
+        let (pawn, rank) = if white_to_move {
+            ((Color::White, Role::Pawn), 4u8)
+        } else {
+            ((Color::Black, Role::Pawn), 3u8)
+        };
+        let can_capture = [file.checked_sub(1), Some(file + 1).filter(|&f| f < 8)]
+            .into_iter()
+            .flatten()
+            .any(|f| board[(8 * rank + f) as usize] == Some(pawn));
+        if can_capture {
+            hash.add(&PolyglotKey::EnPassantFile(file));
+        }
+    }
+
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(fen: &str) -> u64 {
+        from_fen(fen).unwrap().into()
+    }
+
+    #[test]
+    fn reference_positions() {
+        // Test vectors from the Polyglot book format specification.
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0x463b96181691fc9c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                0x823c9b50fd114196,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                0x0756b94461c50fb0,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+                0x662fafb965db29d4,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                0x22a48b5a8e47ff78,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+                0x652a607ca3f242c1,
+            ),
+            (
+                "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+                0x00fdd303c946bdd9,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+                0x3c8123ea7b067637,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+                0x5c3f9b829b279560,
+            ),
+        ];
+        for (fen, expected) in cases {
+            assert_eq!(hash(fen), expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn malformed_fen() {
+        assert!(from_fen("").is_none());
+        assert!(from_fen("8/8/8/8/8/8/8 w - -").is_none());
+        assert!(from_fen("8/8/8/8/8/8/8/9 w - -").is_none());
+        assert!(from_fen("8/8/8/8/8/8/8/8 x - -").is_none());
+        assert!(from_fen("8/8/8/8/8/8/8/7x w - -").is_none());
+        assert!(from_fen("99999999999999999999999999999/8/8/8/8/8/8/8 w - -").is_none());
+        assert!(from_fen("0k7/8/8/8/8/8/8/8 w - -").is_none());
+        assert!(from_fen("4k3/8/8/8/8/8/8/4K2R w KK -").is_none());
+        assert!(from_fen("4k3/8/8/8/8/8/8/4K3 w - e9").is_none());
+        assert!(from_fen("4k3/8/8/8/8/8/8/4K3 w - exyz").is_none());
+        assert!(from_fen("4k3/8/8/8/8/8/8/4K3 w - e4").is_none());
+        assert!(from_fen("4k3/8/8/8/8/8/8/4K3 w - e6").is_some());
+    }
+}
@@ -0,0 +1,187 @@
+use super::polyglot::{CastlingRight, Color, PolyglotHash, PolyglotKey, PolyglotKeys, Role};
+use ::shakmaty::{attacks, CastlingSide, Chess, EnPassantMode, Move, Position, Square};
+
+/// Hashing of [`shakmaty`] positions with Polyglot keys.
+impl PolyglotHash {
+    /// Computes the hash of `pos` from scratch.
+    ///
+    /// ```rust
+    /// use shakmaty::Chess;
+    /// use zobristhash_set::chess::polyglot::PolyglotHash;
+    ///
+    /// let hash = PolyglotHash::from_position(&Chess::default());
+    /// assert_eq!(u64::from(hash), 0x463b96181691fc9c);
+    /// ```
+    pub fn from_position(pos: &Chess) -> Self {
+        let mut hash = PolyglotHash::with_keys(PolyglotKeys);
+        for (square, piece) in pos.board().iter() {
+            hash.add(&piece_key(piece.color, piece.role, square));
+        }
+        for (color, side) in CASTLING {
+            if pos.castles().has(color, side) {
+                hash.add(&castling_key(color, side));
+            }
+        }
+        if let Some(square) = pos.ep_square(EnPassantMode::PseudoLegal) {
+            hash.add(&PolyglotKey::EnPassantFile(square.file().to_u32() as u8));
+        }
+        if pos.turn() == ::shakmaty::Color::White {
+            hash.add(&PolyglotKey::WhiteToMove);
+        }
+        hash
+    }
+
+    /// Updates the hash for `mv` being played in `pos`, the position before the move.
+    ///
+    /// Captures, promotions, castling, en passant and the resulting changes to castling rights
+    /// and the en passant square are all accounted for.
+    ///
+    /// ```rust
+    /// use shakmaty::{uci::UciMove, Chess, Position};
+    /// use zobristhash_set::chess::polyglot::PolyglotHash;
+    ///
+    /// let pos = Chess::default();
+    /// let mv = "e2e4".parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+    ///
+    /// let mut hash = PolyglotHash::from_position(&pos);
+    /// hash.play(&pos, mv);
+    /// assert_eq!(u64::from(hash), 0x823c9b50fd114196);
+    /// ```
+    pub fn play(&mut self, pos: &Chess, mv: Move) {
+        let us = pos.turn();
+        match mv {
+            Move::Normal {
+                role,
+                from,
+                capture,
+                to,
+                promotion,
+            } => {
+                self.remove(&piece_key(us, role, from));
+                if let Some(captured) = capture {
+                    self.remove(&piece_key(!us, captured, to));
+                }
+                self.add(&piece_key(us, promotion.unwrap_or(role), to));
+            }
+            Move::EnPassant { from, to } => {
+                self.remove(&piece_key(us, ::shakmaty::Role::Pawn, from));
+                let captured = Square::from_coords(to.file(), from.rank());
+                self.remove(&piece_key(!us, ::shakmaty::Role::Pawn, captured));
+                self.add(&piece_key(us, ::shakmaty::Role::Pawn, to));
+            }
+            Move::Castle { king, rook } => {
+                let side = CastlingSide::from_king_side(king < rook);
+                self.remove(&piece_key(us, ::shakmaty::Role::King, king));
+                self.remove(&piece_key(us, ::shakmaty::Role::Rook, rook));
+                self.add(&piece_key(us, ::shakmaty::Role::King, side.king_to(us)));
+                self.add(&piece_key(us, ::shakmaty::Role::Rook, side.rook_to(us)));
+            }
+            Move::Put { .. } => unreachable!("drops are not legal in standard chess"),
+        }
+
+        let mut castles = pos.castles().clone();
+        if mv.role() == ::shakmaty::Role::King {
+            castles.discard_color(us);
+        }
+        if let Some(from) = mv.from() {
+            castles.discard_rook(from);
+        }
+        castles.discard_rook(mv.to());
+        for (color, side) in CASTLING {
+            if pos.castles().has(color, side) && !castles.has(color, side) {
+                self.remove(&castling_key(color, side));
+            }
+        }
+
+        if let Some(square) = pos.ep_square(EnPassantMode::PseudoLegal) {
+            self.remove(&PolyglotKey::EnPassantFile(square.file().to_u32() as u8));
+        }
+        if let Move::Normal {
+            role: ::shakmaty::Role::Pawn,
+            from,
+            to,
+            ..
+        } = mv
+        {
+            if from.rank().distance(to.rank()) == 2 {
+                let ep_square = Square::new((from.to_u32() + to.to_u32()) / 2);
+                let their_pawns = pos.board().by_piece(::shakmaty::Piece {
+                    color: !us,
+                    role: ::shakmaty::Role::Pawn,
+                });
+                if (attacks::pawn_attacks(us, ep_square) & their_pawns).any() {
+                    self.add(&PolyglotKey::EnPassantFile(from.file().to_u32() as u8));
+                }
+            }
+        }
+
+        if us == ::shakmaty::Color::White {
+            self.remove(&PolyglotKey::WhiteToMove);
+        } else {
+            self.add(&PolyglotKey::WhiteToMove);
+        }
+    }
+}
+
+const CASTLING: [(::shakmaty::Color, CastlingSide); 4] = [
+    (::shakmaty::Color::White, CastlingSide::KingSide),
+    (::shakmaty::Color::White, CastlingSide::QueenSide),
+    (::shakmaty::Color::Black, CastlingSide::KingSide),
+    (::shakmaty::Color::Black, CastlingSide::QueenSide),
+];
+
+fn piece_key(color: ::shakmaty::Color, role: ::shakmaty::Role, square: Square) -> PolyglotKey {
+    let color = match color {
+        ::shakmaty::Color::White => Color::White,
+        ::shakmaty::Color::Black => Color::Black,
+    };
+    let role = match role {
+        ::shakmaty::Role::Pawn => Role::Pawn,
+        ::shakmaty::Role::Knight => Role::Knight,
+        ::shakmaty::Role::Bishop => Role::Bishop,
+        ::shakmaty::Role::Rook => Role::Rook,
+        ::shakmaty::Role::Queen => Role::Queen,
+        ::shakmaty::Role::King => Role::King,
+    };
+    PolyglotKey::Piece {
+        piece: (color, role),
+        square: square.to_u32() as u8,
+    }
+}
+
+fn castling_key(color: ::shakmaty::Color, side: CastlingSide) -> PolyglotKey {
+    PolyglotKey::Castling(match (color, side) {
+        (::shakmaty::Color::White, CastlingSide::KingSide) => CastlingRight::WhiteKingside,
+        (::shakmaty::Color::White, CastlingSide::QueenSide) => CastlingRight::WhiteQueenside,
+        (::shakmaty::Color::Black, CastlingSide::KingSide) => CastlingRight::BlackKingside,
+        (::shakmaty::Color::Black, CastlingSide::QueenSide) => CastlingRight::BlackQueenside,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::shakmaty::zobrist::Zobrist64;
+    use rand::seq::SliceRandom;
+
+    #[test]
+    fn incremental_matches_from_scratch() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let mut pos = Chess::default();
+            let mut hash = PolyglotHash::from_position(&pos);
+            for _ in 0..200 {
+                let moves = pos.legal_moves();
+                let Some(&mv) = moves.choose(&mut rng) else {
+                    break;
+                };
+                hash.play(&pos, mv);
+                pos.play_unchecked(mv);
+
+                assert_eq!(hash.hash, PolyglotHash::from_position(&pos).hash);
+                let reference: Zobrist64 = pos.zobrist_hash(EnPassantMode::PseudoLegal);
+                assert_eq!(hash.hash, reference.0);
+            }
+        }
+    }
+}
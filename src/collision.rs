@@ -0,0 +1,105 @@
+use crate::KeySource;
+use rustc_hash::FxHashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+/// A key source that detects distinct elements sharing a key, in debug builds.
+///
+/// Two elements with the same 64-bit key are indistinguishable to the hash, so positions that
+/// differ only in them collide, and a transposition table silently returns entries for the wrong
+/// position. In debug builds this wrapper remembers one element for every key it has produced
+/// and panics, naming both elements, when a different element produces the same key. The
+/// registry grows with the number of distinct elements seen and is shared by clones. In release
+/// builds keys are passed through unchecked.
+///
+/// ```rust
+/// use zobristhash_set::{CollisionCheckedKeys, FxKeys, ZobristHashSet};
+///
+/// // In a test suite, hash every element the engine can produce once to vet the encoding.
+/// let mut hash = ZobristHashSet::with_keys(CollisionCheckedKeys::new(FxKeys));
+/// for square in 0..64u8 {
+///     for piece in ["pawn", "knight", "bishop", "rook", "queen", "king"] {
+///         hash.add(&(square, piece));
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CollisionCheckedKeys<S, E> {
+    source: S,
+    registry: Arc<Mutex<FxHashMap<u64, E>>>,
+}
+
+impl<S, E> CollisionCheckedKeys<S, E> {
+    /// Wraps `source` with an empty registry.
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            registry: Arc::default(),
+        }
+    }
+
+    /// Returns the wrapped key source.
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+}
+
+impl<S: Default, E> Default for CollisionCheckedKeys<S, E> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+impl<S: KeySource<E>, E: Eq + Clone + Debug> KeySource<E> for CollisionCheckedKeys<S, E> {
+    fn key(&self, element: &E) -> u64 {
+        let key = self.source.key(element);
+        if cfg!(debug_assertions) {
+            let mut registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+            let known = registry.entry(key).or_insert_with(|| element.clone());
+            assert!(
+                known == element,
+                "elements {known:?} and {element:?} share the key {key:#018x}"
+            );
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FxKeys, ZobristHashSet};
+
+    #[test]
+    fn distinct_keys_pass_through() {
+        let keys = CollisionCheckedKeys::new(FxKeys);
+        let mut hash = ZobristHashSet::with_keys(keys.clone());
+        for i in 0..100u32 {
+            hash.add(&i);
+            assert_eq!(keys.key(&i), FxKeys.key(&i));
+        }
+        for i in 0..100u32 {
+            hash.remove(&i);
+        }
+        assert_eq!(hash.hash(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "elements 1 and 3 share the key")]
+    #[cfg(debug_assertions)]
+    fn colliding_elements_are_reported_debug() {
+        struct Parity;
+
+        impl KeySource<u32> for Parity {
+            fn key(&self, element: &u32) -> u64 {
+                FxKeys.key(&(element % 2))
+            }
+        }
+
+        let keys = CollisionCheckedKeys::new(Parity);
+        keys.key(&1);
+        keys.key(&2);
+        keys.key(&1);
+        keys.key(&3);
+    }
+}
@@ -0,0 +1,157 @@
+/// How element keys are folded into a set's 64-bit value.
+///
+/// `combine` and `uncombine` must be commutative and associative in their key arguments and undo
+/// each other, so the value only depends on which elements are present.
+pub trait Combiner {
+    /// The value of the empty set.
+    const IDENTITY: u64;
+
+    /// Whether combining the same key twice restores the previous value.
+    ///
+    /// Self-inverse combiners describe sets: adding an element that is already present is a
+    /// mistake, and the checker reports it. Other combiners describe multisets.
+    const SELF_INVERSE: bool;
+
+    fn combine(hash: u64, key: u64) -> u64;
+
+    fn uncombine(hash: u64, key: u64) -> u64;
+}
+
+/// The classic Zobrist combiner: bitwise XOR.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Xor;
+
+impl Combiner for Xor {
+    const IDENTITY: u64 = 0;
+    const SELF_INVERSE: bool = true;
+
+    fn combine(hash: u64, key: u64) -> u64 {
+        hash ^ key
+    }
+
+    fn uncombine(hash: u64, key: u64) -> u64 {
+        hash ^ key
+    }
+}
+
+/// Addition modulo 2<sup>64</sup>, which reflects element multiplicities.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrappingAdd;
+
+impl Combiner for WrappingAdd {
+    const IDENTITY: u64 = 0;
+    const SELF_INVERSE: bool = false;
+
+    fn combine(hash: u64, key: u64) -> u64 {
+        hash.wrapping_add(key)
+    }
+
+    fn uncombine(hash: u64, key: u64) -> u64 {
+        hash.wrapping_sub(key)
+    }
+}
+
+/// Carry-less multiplication in GF(2<sup>64</sup>), which reflects element multiplicities.
+///
+/// The field is defined by the polynomial x<sup>64</sup> + x<sup>4</sup> + x<sup>3</sup> + x + 1.
+/// A zero key would absorb every other key, so it is replaced by a fixed non-zero constant.
+/// Removing an element multiplies by the inverse of its key, which is considerably slower than
+/// adding it.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GfMul;
+
+impl Combiner for GfMul {
+    const IDENTITY: u64 = 1;
+    const SELF_INVERSE: bool = false;
+
+    fn combine(hash: u64, key: u64) -> u64 {
+        gf_mul(hash, nonzero(key))
+    }
+
+    fn uncombine(hash: u64, key: u64) -> u64 {
+        gf_mul(hash, gf_inv(nonzero(key)))
+    }
+}
+
+fn nonzero(key: u64) -> u64 {
+    if key == 0 {
+        0x9E37_79B9_7F4A_7C15
+    } else {
+        key
+    }
+}
+
+pub(crate) fn gf_mul(mut a: u64, mut b: u64) -> u64 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        b >>= 1;
+        let carry = a >> 63;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1B;
+        }
+    }
+    product
+}
+
+/// Computes `a^(2^64 - 2)`, the multiplicative inverse of a non-zero `a`.
+pub(crate) fn gf_inv(a: u64) -> u64 {
+    let mut result = 1;
+    let mut base = a;
+    let mut exponent = u64::MAX - 1;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn gf_inverse() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let a = rng.gen::<u64>() | 1;
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn gf_mul_is_commutative_and_associative() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let (a, b, c) = (rng.gen(), rng.gen(), rng.gen());
+            assert_eq!(gf_mul(a, b), gf_mul(b, a));
+            assert_eq!(gf_mul(gf_mul(a, b), c), gf_mul(a, gf_mul(b, c)));
+        }
+    }
+
+    fn roundtrip<C: Combiner>() {
+        let keys = [0u64, 1, 42, u64::MAX, 0x1234_5678_9ABC_DEF0];
+        let mut hash = C::IDENTITY;
+        for &key in &keys {
+            hash = C::combine(hash, key);
+        }
+        for &key in keys.iter().rev() {
+            hash = C::uncombine(hash, key);
+        }
+        assert_eq!(hash, C::IDENTITY);
+    }
+
+    #[test]
+    fn combiners_roundtrip() {
+        roundtrip::<Xor>();
+        roundtrip::<WrappingAdd>();
+        roundtrip::<GfMul>();
+    }
+}
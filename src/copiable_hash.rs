@@ -1,24 +1,154 @@
+use crate::keys::mix64;
+use crate::DEFAULT_CHECK_CAPACITY;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
 use rustc_hash::FxHasher;
+#[cfg(feature = "std")]
 use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
 
-const DEBUG_MAP_HASH_SIZE: usize = 1024 * 8;
+const MIN_SLOTS: usize = 16;
 
-/// A hash that can be copied and compared for equality.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct CopiableHash<E> {
-    data: [Option<u64>; DEBUG_MAP_HASH_SIZE],
+/// The number of operations kept in a [`Journal`].
+const JOURNAL_LEN: usize = 32;
+
+/// A hash that can be cloned and checked for membership.
+///
+/// It tracks at most `N` elements in a linear-probing table on the heap that grows as elements
+/// are added, so it only takes a few words inside the set that owns it and every operation
+/// touches a handful of slots. Repeated elements share a slot and a count.
+///
+/// Clones share the table until one of them is updated, so cloning costs the same at any size,
+/// and a snapshot that is restored without further updates never copies it.
+///
+/// Elements added with [`insert`](Self::insert) are stored along with their hash and compared
+/// with `Eq`, so distinct elements whose hashes collide are told apart. Elements added by hash
+/// alone match any element with the same hash.
+#[derive(Debug)]
+pub(crate) struct CopiableHash<E, const N: usize = DEFAULT_CHECK_CAPACITY> {
+    table: Option<Arc<Table<E>>>,
+    journal: Journal<E>,
+}
+
+/// The slots of a [`CopiableHash`], shared between its clones.
+#[derive(Clone, Debug)]
+struct Table<E> {
+    slots: Vec<Option<Slot<E>>>,
+    occupied: usize,
     len: usize,
-    _marker: std::marker::PhantomData<E>,
 }
 
-impl<E> Default for CopiableHash<E> {
+/// The most recent updates of a set, printed when the checker panics.
+///
+/// Entries hold the updated element itself and are only formatted when the journal is printed,
+/// so recording an update costs a clone of the element.
+#[derive(Clone, Debug)]
+pub(crate) struct Journal<E> {
+    entries: VecDeque<Entry<E>>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry<E> {
+    op: &'static str,
+    element: Recorded<E>,
+    hash: u64,
+}
+
+/// The element of a journal [`Entry`].
+#[derive(Clone, Debug)]
+enum Recorded<E> {
+    /// An element, along with the `Debug` implementation that prints it.
+    Element(E, fn(&E, &mut fmt::Formatter<'_>) -> fmt::Result),
+    /// An element known only by its precomputed key.
+    Key(u64),
+}
+
+impl<E> Default for Journal<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Journal<E> {
+    pub const fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Records that `op` was applied to `element`, leaving the set with `hash`.
+    pub fn record(&mut self, op: &'static str, element: &E, hash: u64)
+    where
+        E: Clone + Debug,
+    {
+        let element = Recorded::Element(element.clone(), E::fmt);
+        self.push(Entry { op, element, hash });
+    }
+
+    /// Records that `op` was applied to the element with the precomputed `key`.
+    pub fn record_key(&mut self, op: &'static str, key: u64, hash: u64) {
+        let element = Recorded::Key(key);
+        self.push(Entry { op, element, hash });
+    }
+
+    fn push(&mut self, entry: Entry<E>) {
+        if self.entries.len() == JOURNAL_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+impl<E> Display for Entry<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.op)?;
+        match &self.element {
+            Recorded::Element(element, format) => format(element, f)?,
+            Recorded::Key(key) => write!(f, "key {key:#018x}")?,
+        }
+        write!(f, " -> {:#018x}", self.hash)
+    }
+}
+
+impl<E> Display for Journal<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.entries.is_empty() {
+            return write!(f, "no earlier operations");
+        }
+        write!(f, "last {} operations, oldest first:", self.entries.len())?;
+        for entry in &self.entries {
+            write!(f, "\n  {entry}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Slot<E> {
+    hash: u64,
+    key: Option<E>,
+    count: usize,
+}
+
+impl<E: Clone, const N: usize> Clone for CopiableHash<E, N> {
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+impl<E, const N: usize> Default for CopiableHash<E, N> {
     fn default() -> Self {
         Self::empty()
     }
 }
 
-impl<E: Hash + Eq> From<HashSet<E>> for CopiableHash<E> {
+#[cfg(feature = "std")]
+impl<E: Hash + Eq + Clone, const N: usize> From<HashSet<E>> for CopiableHash<E, N> {
     fn from(set: HashSet<E>) -> Self {
         let mut hash = CopiableHash::empty();
         for key in set {
@@ -28,57 +158,221 @@ impl<E: Hash + Eq> From<HashSet<E>> for CopiableHash<E> {
     }
 }
 
-impl<E> CopiableHash<E> {
+impl<E, const N: usize> CopiableHash<E, N> {
     /// Creates an empty hash.
-    pub fn empty() -> Self {
+    pub const fn empty() -> Self {
         Self {
-            data: [None; DEBUG_MAP_HASH_SIZE],
-            len: 0,
-            _marker: std::marker::PhantomData,
+            table: None,
+            journal: Journal::new(),
         }
     }
+
+    /// Returns the number of tracked elements, counting repeats.
+    pub fn len(&self) -> usize {
+        self.table.as_ref().map_or(0, |table| table.len)
+    }
+
+    /// Returns whether no more elements can be added.
+    pub fn is_full(&self) -> bool {
+        self.len() >= N
+    }
+
+    /// Returns the recent updates, as recorded with [`journal_mut`](Self::journal_mut).
+    pub fn journal(&self) -> &Journal<E> {
+        &self.journal
+    }
+
+    pub fn journal_mut(&mut self) -> &mut Journal<E> {
+        &mut self.journal
+    }
 }
 
-impl<E: Hash> CopiableHash<E> {
-    /// Adds a new element to the hash.
-    pub fn insert(&mut self, key: E) -> bool {
+impl<E: Hash, const N: usize> CopiableHash<E, N> {
+    /// Returns the value under which `key` is tracked.
+    pub fn element_hash(key: &E) -> u64 {
         let mut hasher = FxHasher::default();
         key.hash(&mut hasher);
-        let hash = hasher.finish();
+        hasher.finish()
+    }
+}
 
-        if self
-            .data
-            .iter()
-            .take(self.len)
-            .all(|x| x.map_or(true, |x| x != hash))
-        {
-            assert!(self.len < DEBUG_MAP_HASH_SIZE, "Cannot handle more than {} elements when checking. Please compile in release build or remove the `check_set` feature flag", DEBUG_MAP_HASH_SIZE);
-            self.data[self.len] = Some(hash);
-            self.len += 1;
-            true
-        } else {
-            false
-        }
+impl<E: Hash + Eq + Clone, const N: usize> CopiableHash<E, N> {
+    /// Adds a new element to the hash.
+    pub fn insert(&mut self, key: E) -> bool {
+        let hash = Self::element_hash(&key);
+        self.add(hash, Some(key), false, Slot::matches)
+    }
+
+    /// Adds an element to the hash even if it is already present, for multiset semantics.
+    pub fn insert_repeated(&mut self, key: E) {
+        let hash = Self::element_hash(&key);
+        self.add(hash, Some(key), true, Slot::matches);
     }
 
     /// Removes an element from the hash.
     pub fn remove(&mut self, key: &E) -> bool {
-        let mut hasher = FxHasher::default();
-        key.hash(&mut hasher);
-        let hash = hasher.finish();
+        let hash = Self::element_hash(key);
+        self.take(hash, |slot| slot.matches(key))
+    }
+}
+
+impl<E: Hash + Eq, const N: usize> CopiableHash<E, N> {
+    /// Returns whether `key` is tracked.
+    pub fn contains(&self, key: &E) -> bool {
+        self.table.as_ref().is_some_and(|table| {
+            table
+                .probe(Self::element_hash(key), |slot| slot.matches(key))
+                .1
+        })
+    }
+}
 
-        let pos = self
-            .data
-            .iter()
-            .take(self.len)
-            .position(|x| x.map_or(false, |x| x == hash));
+impl<E: Clone, const N: usize> CopiableHash<E, N> {
+    /// Adds an element, given by its [`element_hash`](Self::element_hash), if it is not present.
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        self.add(hash, None, false, |_, _| true)
+    }
+
+    /// Adds an element, given by its [`element_hash`](Self::element_hash), even if it is present.
+    pub fn insert_repeated_hash(&mut self, hash: u64) {
+        self.add(hash, None, true, |_, _| true);
+    }
 
-        if let Some(pos) = pos {
-            self.data.swap(pos, self.len - 1);
-            self.len -= 1;
-            true
-        } else {
-            false
+    /// Removes an element given by its [`element_hash`](Self::element_hash).
+    pub fn remove_hash(&mut self, hash: u64) -> bool {
+        self.take(hash, |_| true)
+    }
+
+    /// Adds the element with `hash`, comparing `key`, when given, to entries with `matches`.
+    fn add(
+        &mut self,
+        hash: u64,
+        key: Option<E>,
+        repeated: bool,
+        matches: impl Fn(&Slot<E>, &E) -> bool,
+    ) -> bool {
+        let table = Arc::make_mut(self.table.get_or_insert_with(Default::default));
+        table.reserve_slot();
+        let (slot, found) = table.probe(hash, |slot| {
+            key.as_ref().is_none_or(|key| matches(slot, key))
+        });
+        if found && !repeated {
+            return false;
+        }
+        assert!(table.len < N, "Cannot handle more than {} elements when checking. Please raise the check capacity, compile in release build or remove the `check_set` feature flag\n{}", N, self.journal);
+        table.fill(slot, found, hash, key);
+        true
+    }
+
+    /// Removes one occurrence of the first entry with `hash` that satisfies `matches`.
+    fn take(&mut self, hash: u64, matches: impl Fn(&Slot<E>) -> bool) -> bool {
+        let Some(shared) = &self.table else {
+            return false;
+        };
+        let (slot, found) = shared.probe(hash, matches);
+        if found {
+            let table = Arc::make_mut(self.table.as_mut().expect("probed table"));
+            table.take_one(slot);
+        }
+        found
+    }
+}
+
+impl<E> Slot<E> {
+    /// Returns whether the entry stands for `key`, either by value or by hash alone.
+    fn matches(&self, key: &E) -> bool
+    where
+        E: PartialEq,
+    {
+        self.key.as_ref().is_none_or(|k| k == key)
+    }
+}
+
+impl<E> Default for Table<E> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            occupied: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<E> Table<E> {
+    fn fill(&mut self, slot: usize, found: bool, hash: u64, key: Option<E>) {
+        match &mut self.slots[slot] {
+            Some(entry) if found => entry.count += 1,
+            entry => {
+                *entry = Some(Slot {
+                    hash,
+                    key,
+                    count: 1,
+                });
+                self.occupied += 1;
+            }
+        }
+        self.len += 1;
+    }
+
+    fn take_one(&mut self, slot: usize) {
+        self.len -= 1;
+        if let Some(entry) = &mut self.slots[slot] {
+            entry.count -= 1;
+            if entry.count == 0 {
+                self.vacate(slot);
+            }
+        }
+    }
+
+    fn home(&self, hash: u64) -> usize {
+        mix64(hash) as usize & (self.slots.len() - 1)
+    }
+
+    /// Returns the first slot holding `hash` whose entry satisfies `matches`, or the empty slot
+    /// where it belongs, and whether it was found.
+    fn probe(&self, hash: u64, matches: impl Fn(&Slot<E>) -> bool) -> (usize, bool) {
+        let mask = self.slots.len() - 1;
+        let mut slot = self.home(hash);
+        loop {
+            match &self.slots[slot] {
+                None => return (slot, false),
+                Some(entry) if entry.hash == hash && matches(entry) => return (slot, true),
+                Some(_) => slot = (slot + 1) & mask,
+            }
+        }
+    }
+
+    /// Empties `slot`, shifting later entries of the probe sequence back so none of them becomes
+    /// unreachable.
+    fn vacate(&mut self, slot: usize) {
+        let mask = self.slots.len() - 1;
+        self.slots[slot] = None;
+        self.occupied -= 1;
+        let mut hole = slot;
+        let mut next = (slot + 1) & mask;
+        while let Some(entry) = &self.slots[next] {
+            let home = self.home(entry.hash);
+            if next.wrapping_sub(home) & mask >= next.wrapping_sub(hole) & mask {
+                self.slots[hole] = self.slots[next].take();
+                hole = next;
+            }
+            next = (next + 1) & mask;
+        }
+    }
+
+    /// Keeps the table at most half full after one more slot is taken.
+    fn reserve_slot(&mut self) {
+        if (self.occupied + 1) * 2 <= self.slots.len() {
+            return;
+        }
+        let slots = (self.slots.len() * 2).max(MIN_SLOTS);
+        let old = core::mem::replace(
+            &mut self.slots,
+            core::iter::repeat_with(|| None).take(slots).collect(),
+        );
+        for entry in old.into_iter().flatten() {
+            let (slot, _) = self.probe(entry.hash, |_| false);
+            self.slots[slot] = Some(entry);
         }
     }
 }
@@ -91,7 +385,7 @@ mod tests {
     #[test]
     fn random_test_with_hashset() {
         let mut reference = std::collections::HashSet::new();
-        let mut target = CopiableHash::empty();
+        let mut target = CopiableHash::<_>::empty();
 
         let mut rng = rand::thread_rng();
         for _ in 0..1000 {
@@ -106,13 +400,140 @@ mod tests {
         }
     }
 
+    #[test]
+    fn repeated_insert_test() {
+        let mut target = CopiableHash::<_>::empty();
+        target.insert_repeated(1);
+        target.insert_repeated(1);
+        assert!(!target.insert(1));
+        assert!(target.remove(&1));
+        assert!(target.remove(&1));
+        assert!(!target.remove(&1));
+    }
+
     #[test]
     #[should_panic]
     fn capacity_over_test() {
-        let mut target = CopiableHash::empty();
-        for i in 0..DEBUG_MAP_HASH_SIZE {
+        let mut target = CopiableHash::<_>::empty();
+        for i in 0..DEFAULT_CHECK_CAPACITY {
             assert!(target.insert(i));
         }
-        target.insert(DEBUG_MAP_HASH_SIZE);
+        target.insert(DEFAULT_CHECK_CAPACITY);
+    }
+
+    #[test]
+    fn custom_capacity_test() {
+        let mut target = CopiableHash::<_, 16>::empty();
+        for i in 0..16 {
+            assert!(target.insert(i));
+        }
+        assert!(target.remove(&3));
+        assert!(target.insert(16));
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Colliding(u32);
+
+    impl Hash for Colliding {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            0u32.hash(state);
+        }
+    }
+
+    #[test]
+    fn colliding_keys_are_distinct() {
+        let mut target = CopiableHash::<_>::empty();
+        assert!(target.insert(Colliding(1)));
+        assert!(target.insert(Colliding(2)));
+        assert!(!target.insert(Colliding(1)));
+        assert!(target.remove(&Colliding(1)));
+        assert!(!target.remove(&Colliding(1)));
+        assert!(target.remove(&Colliding(2)));
+    }
+
+    #[test]
+    fn hash_only_entries_match_any_key() {
+        let mut target = CopiableHash::<_>::empty();
+        assert!(target.insert_hash(CopiableHash::<u32>::element_hash(&7)));
+        assert!(!target.insert(7u32));
+        assert!(target.remove(&7));
+        assert!(target.insert(7));
+        assert!(target.remove_hash(CopiableHash::<u32>::element_hash(&7)));
+        assert!(!target.remove(&7));
+    }
+
+    #[test]
+    fn journal_keeps_the_latest_operations() {
+        let mut journal = Journal::default();
+        assert_eq!(journal.to_string(), "no earlier operations");
+        for i in 0..JOURNAL_LEN + 2 {
+            journal.record("add", &i, i as u64);
+        }
+        let dump = journal.to_string();
+        assert!(
+            dump.starts_with("last 32 operations, oldest first:\n  add 2 -> 0x0000000000000002")
+        );
+        assert!(dump.ends_with("add 33 -> 0x0000000000000021"));
+
+        journal.record_key("remove", 0xab, 0);
+        assert!(journal
+            .to_string()
+            .ends_with("remove key 0x00000000000000ab -> 0x0000000000000000"));
+    }
+
+    #[test]
+    fn clones_share_the_table_until_updated() {
+        let mut target = CopiableHash::<_>::empty();
+        for i in 0..100u32 {
+            target.insert(i);
+        }
+        let mut copy = target.clone();
+        let (Some(original), Some(shared)) = (&target.table, &copy.table) else {
+            panic!("tables were allocated");
+        };
+        assert!(Arc::ptr_eq(original, shared));
+
+        assert!(copy.remove(&7));
+        assert!(target.contains(&7));
+        assert!(!copy.contains(&7));
+        assert_eq!((target.len(), copy.len()), (100, 99));
+    }
+
+    #[test]
+    fn storage_is_not_inline() {
+        assert!(std::mem::size_of::<CopiableHash<u64>>() <= 12 * std::mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn random_repeated_test_with_counts() {
+        let mut reference = std::collections::HashMap::<u64, usize>::new();
+        let mut target = CopiableHash::<_>::empty();
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20000 {
+            let key = rng.gen_range(0..500u64);
+            match rng.gen_range(0..3) {
+                0 => {
+                    let absent = !reference.contains_key(&key);
+                    assert_eq!(absent, target.insert(key));
+                    reference.entry(key).or_insert(1);
+                }
+                1 => {
+                    target.insert_repeated(key);
+                    *reference.entry(key).or_insert(0) += 1;
+                }
+                _ => {
+                    let present = match reference.get_mut(&key) {
+                        Some(count) if *count > 1 => {
+                            *count -= 1;
+                            true
+                        }
+                        Some(_) => reference.remove(&key).is_some(),
+                        None => false,
+                    };
+                    assert_eq!(present, target.remove(&key));
+                }
+            }
+        }
     }
 }
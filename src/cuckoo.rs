@@ -0,0 +1,247 @@
+use crate::keys::{mix64, splitmix64};
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+const SLOTS_PER_BUCKET: usize = 4;
+const MAX_KICKS: usize = 500;
+
+/// A Zobrist hash paired with a cuckoo filter over the same elements, answering approximate
+/// membership queries that stay correct across removals.
+///
+/// Each element leaves a fingerprint of `fingerprint_bits` bits in one of two buckets. An element
+/// that was never added is reported as present with probability about `8 / 2^fingerprint_bits`,
+/// and [`remove`](Self::remove) deletes the fingerprint again. Removing an element that was never
+/// added can delete another element's fingerprint, as it would corrupt the hash.
+///
+/// ```rust
+/// use zobristhash_set::CuckooZobristSet;
+///
+/// let mut expanded = CuckooZobristSet::with_capacity(1000, 12);
+/// expanded.add(&"e2e4");
+/// assert!(expanded.likely_contains(&"e2e4"));
+///
+/// expanded.remove(&"e2e4");
+/// assert!(!expanded.likely_contains(&"e2e4"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CuckooZobristSet<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    fingerprints: PackedArray,
+    mask: usize,
+    /// Fingerprints that found no slot, with one of their buckets.
+    stash: Vec<(usize, u32)>,
+    rng: u64,
+}
+
+impl<E> CuckooZobristSet<E> {
+    /// Creates an empty set whose filter holds about `expected` elements, with fingerprints of
+    /// `fingerprint_bits` bits (from 4 to 32).
+    pub fn with_capacity(expected: usize, fingerprint_bits: u32) -> Self {
+        Self::with_keys(FxKeys, expected, fingerprint_bits)
+    }
+}
+
+impl<E, K> CuckooZobristSet<E, K> {
+    /// Like [`with_capacity`](CuckooZobristSet::with_capacity), deriving keys from `keys`.
+    pub fn with_keys(keys: K, expected: usize, fingerprint_bits: u32) -> Self {
+        assert!(
+            (4..=32).contains(&fingerprint_bits),
+            "fingerprints must have 4 to 32 bits"
+        );
+        // Cuckoo filters with four-slot buckets fill reliably up to about 95%.
+        let buckets = (expected * 100 / 95 / SLOTS_PER_BUCKET + 1).next_power_of_two();
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            fingerprints: PackedArray::new(buckets * SLOTS_PER_BUCKET, fingerprint_bits),
+            mask: buckets - 1,
+            stash: Vec::new(),
+            rng: 0,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the size of the filter in bytes.
+    pub fn filter_bytes(&self) -> usize {
+        self.fingerprints.words.len() * 8 + self.stash.len() * std::mem::size_of::<(usize, u32)>()
+    }
+
+    /// Returns the fingerprint and the two candidate buckets of an element with key `key`.
+    fn locate(&self, key: u64) -> (u32, usize, usize) {
+        let mixed = mix64(key);
+        let mask = (1u64 << self.fingerprints.bits) - 1;
+        // Zero marks an empty slot.
+        let fingerprint = ((mixed >> 32) & mask).max(1) as u32;
+        let first = mixed as usize & self.mask;
+        (fingerprint, first, self.alternate(first, fingerprint))
+    }
+
+    /// Returns the other bucket of a fingerprint stored in `bucket`.
+    fn alternate(&self, bucket: usize, fingerprint: u32) -> usize {
+        bucket ^ (mix64(fingerprint as u64) as usize & self.mask)
+    }
+
+    fn slots(bucket: usize) -> std::ops::Range<usize> {
+        bucket * SLOTS_PER_BUCKET..(bucket + 1) * SLOTS_PER_BUCKET
+    }
+
+    fn find(&self, bucket: usize, fingerprint: u32) -> Option<usize> {
+        Self::slots(bucket).find(|&slot| self.fingerprints.get(slot) == fingerprint)
+    }
+
+    fn insert(&mut self, fingerprint: u32, first: usize, second: usize) {
+        for bucket in [first, second] {
+            if let Some(slot) = self.find(bucket, 0) {
+                self.fingerprints.set(slot, fingerprint);
+                return;
+            }
+        }
+        // Both buckets are full: evict a random occupant to its other bucket, and so on.
+        let mut fingerprint = fingerprint;
+        let mut bucket = if splitmix64(&mut self.rng) & 1 == 0 {
+            first
+        } else {
+            second
+        };
+        for _ in 0..MAX_KICKS {
+            let slot =
+                Self::slots(bucket).start + splitmix64(&mut self.rng) as usize % SLOTS_PER_BUCKET;
+            let evicted = self.fingerprints.get(slot);
+            self.fingerprints.set(slot, fingerprint);
+            fingerprint = evicted;
+            bucket = self.alternate(bucket, fingerprint);
+            if let Some(slot) = self.find(bucket, 0) {
+                self.fingerprints.set(slot, fingerprint);
+                return;
+            }
+        }
+        self.stash.push((bucket, fingerprint));
+    }
+
+    fn delete(&mut self, fingerprint: u32, first: usize, second: usize) {
+        for bucket in [first, second] {
+            if let Some(slot) = self.find(bucket, fingerprint) {
+                self.fingerprints.set(slot, 0);
+                return;
+            }
+        }
+        if let Some(i) = self
+            .stash
+            .iter()
+            .position(|&(b, f)| f == fingerprint && (b == first || b == second))
+        {
+            self.stash.swap_remove(i);
+        }
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> CuckooZobristSet<E, K> {
+    pub fn add(&mut self, key: &E) {
+        let element_key = self.set.add(key);
+        let (fingerprint, first, second) = self.locate(element_key);
+        self.insert(fingerprint, first, second);
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        let element_key = self.set.remove(key);
+        let (fingerprint, first, second) = self.locate(element_key);
+        self.delete(fingerprint, first, second);
+    }
+
+    /// Returns `true` if `element` is probably in the set, and `false` if it certainly is not.
+    pub fn likely_contains(&self, element: &E) -> bool {
+        let (fingerprint, first, second) = self.locate(self.set.keys.key(element));
+        self.find(first, fingerprint).is_some()
+            || self.find(second, fingerprint).is_some()
+            || self
+                .stash
+                .iter()
+                .any(|&(b, f)| f == fingerprint && (b == first || b == second))
+    }
+}
+
+/// Fixed-width unsigned values packed into 64-bit words.
+#[derive(Clone, Debug)]
+struct PackedArray {
+    words: Vec<u64>,
+    bits: u32,
+}
+
+impl PackedArray {
+    fn new(len: usize, bits: u32) -> Self {
+        Self {
+            words: vec![0; (len * bits as usize).div_ceil(64) + 1],
+            bits,
+        }
+    }
+
+    fn get(&self, index: usize) -> u32 {
+        let bit = index * self.bits as usize;
+        let (word, offset) = (bit / 64, bit % 64);
+        let mut value = self.words[word] >> offset;
+        if offset + self.bits as usize > 64 {
+            value |= self.words[word + 1] << (64 - offset);
+        }
+        (value & ((1 << self.bits) - 1)) as u32
+    }
+
+    fn set(&mut self, index: usize, value: u32) {
+        let bit = index * self.bits as usize;
+        let (word, offset) = (bit / 64, bit % 64);
+        let mask = (1u64 << self.bits) - 1;
+        let value = value as u64;
+        self.words[word] = self.words[word] & !(mask << offset) | value << offset;
+        if offset + self.bits as usize > 64 {
+            let shift = 64 - offset;
+            self.words[word + 1] = self.words[word + 1] & !(mask >> shift) | value >> shift;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_array_round_trip() {
+        for bits in [4, 7, 12, 32] {
+            let mut array = PackedArray::new(100, bits);
+            let mask = ((1u64 << bits) - 1) as u32;
+            for i in 0..100 {
+                array.set(i, (i as u32).wrapping_mul(0x9E37_79B9) & mask);
+            }
+            for i in 0..100 {
+                assert_eq!(array.get(i), (i as u32).wrapping_mul(0x9E37_79B9) & mask);
+            }
+        }
+    }
+
+    #[test]
+    fn removals_are_forgotten() {
+        let mut set = CuckooZobristSet::with_capacity(4_000, 12);
+        for i in 0..4_000u32 {
+            set.add(&i);
+        }
+        assert!((0..4_000u32).all(|i| set.likely_contains(&i)));
+        for i in (0..4_000u32).step_by(2) {
+            set.remove(&i);
+        }
+        assert!((1..4_000u32).step_by(2).all(|i| set.likely_contains(&i)));
+        let false_positives = (0..4_000u32)
+            .step_by(2)
+            .chain(4_000..100_000)
+            .filter(|i| set.likely_contains(i))
+            .count();
+        // About 8 / 4096 of the 98 000 absent elements.
+        assert!(false_positives < 400, "{false_positives}");
+        for i in (1..4_000u32).step_by(2) {
+            set.remove(&i);
+        }
+        assert_eq!(set.hash(), 0);
+        assert!(set.fingerprints.words.iter().all(|&word| word == 0));
+        assert!(set.stash.is_empty());
+    }
+}
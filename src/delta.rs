@@ -0,0 +1,209 @@
+use crate::{Combiner, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A set of pending additions and removals, to apply to any number of sets.
+///
+/// A move of a board game is typically a handful of elements leaving and entering the
+/// position. Recording it once as a `Delta` lets an engine apply it to the main hash and to
+/// auxiliary hashes such as a pawn hash alike, and unapply it with [`unapply`](Self::unapply)
+/// or [`invert`](Self::invert) when the move is taken back.
+///
+/// A `Delta` holds the net change: removing an element whose addition is pending cancels the
+/// addition, and vice versa. Elements are compared linearly, which suits the few elements a move
+/// touches.
+///
+/// ```rust
+/// use zobristhash_set::{Delta, ZobristHashSet};
+///
+/// let mut board = ZobristHashSet::empty();
+/// board.add(&("pawn", 12));
+/// let before = board.hash();
+///
+/// let mut push = Delta::new();
+/// push.remove(("pawn", 12)).add(("pawn", 28));
+/// push.apply(&mut board);
+/// push.unapply(&mut board);
+/// assert_eq!(board.hash(), before);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Delta<E> {
+    added: Vec<E>,
+    removed: Vec<E>,
+}
+
+impl<E> Delta<E> {
+    pub fn new() -> Self {
+        Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    /// Returns the elements the delta adds.
+    pub fn added(&self) -> &[E] {
+        &self.added
+    }
+
+    /// Returns the elements the delta removes.
+    pub fn removed(&self) -> &[E] {
+        &self.removed
+    }
+
+    /// Returns `true` if applying the delta changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Returns the delta that undoes this one.
+    pub fn invert(self) -> Self {
+        Self {
+            added: self.removed,
+            removed: self.added,
+        }
+    }
+}
+
+impl<E> Default for Delta<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: PartialEq> Delta<E> {
+    /// Records the addition of `element`, cancelling a pending removal of it.
+    pub fn add(&mut self, element: E) -> &mut Self {
+        record(&mut self.added, &mut self.removed, element);
+        self
+    }
+
+    /// Records the removal of `element`, cancelling a pending addition of it.
+    pub fn remove(&mut self, element: E) -> &mut Self {
+        record(&mut self.removed, &mut self.added, element);
+        self
+    }
+
+    /// Returns the delta that applies this one and then `next`.
+    ///
+    /// ```rust
+    /// use zobristhash_set::Delta;
+    ///
+    /// let mut first = Delta::new();
+    /// first.remove(1).add(2);
+    /// let mut second = Delta::new();
+    /// second.remove(2).add(3);
+    ///
+    /// let both = first.compose(second);
+    /// assert_eq!((both.added(), both.removed()), (&[3][..], &[1][..]));
+    /// ```
+    pub fn compose(mut self, next: Self) -> Self {
+        for element in next.removed {
+            self.remove(element);
+        }
+        for element in next.added {
+            self.add(element);
+        }
+        self
+    }
+}
+
+fn record<E: PartialEq>(to: &mut Vec<E>, cancelled: &mut Vec<E>, element: E) {
+    match cancelled.iter().position(|pending| *pending == element) {
+        Some(index) => {
+            cancelled.swap_remove(index);
+        }
+        None => to.push(element),
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug> Delta<E> {
+    /// Applies the delta to `set`, removals first.
+    pub fn apply<K: KeySource<E>, C: Combiner, const CHECK_CAPACITY: usize>(
+        &self,
+        set: &mut ZobristHashSet<E, K, C, CHECK_CAPACITY>,
+    ) {
+        for element in &self.removed {
+            set.remove(element);
+        }
+        for element in &self.added {
+            set.add(element);
+        }
+    }
+
+    /// Undoes [`apply`](Self::apply) on `set`.
+    pub fn unapply<K: KeySource<E>, C: Combiner, const CHECK_CAPACITY: usize>(
+        &self,
+        set: &mut ZobristHashSet<E, K, C, CHECK_CAPACITY>,
+    ) {
+        for element in &self.added {
+            set.remove(element);
+        }
+        for element in &self.removed {
+            set.add(element);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deltas_apply_to_several_sets() {
+        let mut main = ZobristHashSet::empty();
+        let mut pawns = ZobristHashSet::<(&str, u8), _>::with_keys(crate::SplitMixKeys);
+        main.add(&("pawn", 12));
+        main.add(&("king", 4));
+        pawns.add(&("pawn", 12));
+        let (main_before, pawns_before) = (main.hash(), pawns.hash());
+
+        let mut push = Delta::new();
+        push.remove(("pawn", 12)).add(("pawn", 28));
+        push.apply(&mut main);
+        push.apply(&mut pawns);
+
+        let mut expected = ZobristHashSet::empty();
+        expected.add(&("king", 4));
+        expected.add(&("pawn", 28));
+        assert_eq!(main.hash(), expected.hash());
+
+        push.clone().invert().apply(&mut main);
+        push.unapply(&mut pawns);
+        assert_eq!((main.hash(), pawns.hash()), (main_before, pawns_before));
+    }
+
+    #[test]
+    fn composition_cancels_intermediate_steps() {
+        let mut there = Delta::new();
+        there.remove(1).add(2);
+        let back = there.clone().invert();
+        assert!(there.clone().compose(back).is_empty());
+
+        let mut set = ZobristHashSet::empty();
+        set.add(&1u32);
+        let mut onward = Delta::new();
+        onward.remove(2).add(3);
+        there.clone().compose(onward.clone()).apply(&mut set);
+
+        let mut stepwise = ZobristHashSet::empty();
+        stepwise.add(&1u32);
+        there.apply(&mut stepwise);
+        onward.apply(&mut stepwise);
+        assert_eq!(set.hash(), stepwise.hash());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn deltas_round_trip_through_serde() {
+        let mut delta = Delta::new();
+        delta
+            .remove(("pawn".to_string(), 12u8))
+            .add(("pawn".to_string(), 28));
+        let json = serde_json::to_string(&delta).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Delta<(String, u8)>>(&json).unwrap(),
+            delta
+        );
+    }
+}
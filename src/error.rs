@@ -0,0 +1,64 @@
+/// Misuse of a [`ZobristHashSet`](crate::ZobristHashSet) detected by its debug checker.
+///
+/// Returned by [`try_add`](crate::ZobristHashSet::try_add) and
+/// [`try_remove`](crate::ZobristHashSet::try_remove), which only detect misuse in debug builds
+/// with the `check_set_behavior` feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZobristError {
+    /// The element was added while already present.
+    DuplicateAdd,
+    /// The element was removed while not present.
+    MissingRemove,
+    /// The checker already tracks as many elements as it can hold.
+    CheckerCapacity,
+}
+
+impl core::fmt::Display for ZobristError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let message = match self {
+            ZobristError::DuplicateAdd => "element is already present",
+            ZobristError::MissingRemove => "element is not present",
+            ZobristError::CheckerCapacity => "checker capacity is exhausted",
+        };
+        f.write_str(message)
+    }
+}
+
+impl core::error::Error for ZobristError {}
+
+impl ZobristError {
+    /// Counts the error in the `zobristhash_set.checker_violations` metric.
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    pub(crate) fn recorded(self) -> Self {
+        #[cfg(feature = "metrics")]
+        match self {
+            ZobristError::DuplicateAdd => crate::telemetry::duplicate_adds(),
+            ZobristError::MissingRemove => crate::telemetry::missing_removes(),
+            ZobristError::CheckerCapacity => crate::telemetry::exhausted_capacity(),
+        }
+        .increment(1);
+        self
+    }
+}
+
+/// The error returned by [`ZobristHashSet::verify`](crate::ZobristHashSet::verify) when the
+/// incrementally maintained hash differs from the one recomputed from the full state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifyError {
+    /// The hash maintained by the set.
+    pub incremental: u64,
+    /// The hash of the elements passed to `verify`.
+    pub recomputed: u64,
+}
+
+impl core::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "incremental hash {:#018x} differs from recomputed hash {:#018x}",
+            self.incremental, self.recomputed
+        )
+    }
+}
+
+impl core::error::Error for VerifyError {}
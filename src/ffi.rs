@@ -0,0 +1,194 @@
+//! A C API over [`ZobristHashSet`] and [`TranspositionTable`], for engines written in C or C++.
+//!
+//! Sets hash byte strings: adding the bytes `b` changes the value exactly as adding
+//! `b.to_vec()` to a `ZobristHashSet<Vec<u8>, BytesKeys>` does, on every target, so C, Rust,
+//! Python and WebAssembly code can share hashes. The
+//! transposition table stores one `uint64_t` per position. Both are opaque pointers created
+//! by a `_new` function and released with the matching `_free`.
+//!
+//! Build a shared library with `cargo rustc --release --features ffi --crate-type cdylib`; the
+//! declarations are in `include/zobristhash_set.h`. No panic unwinds into C: misuse the debug
+//! checker detects, such as removing bytes that were never added, makes the update return
+//! `false`, and constructors return null if they panic. Running out of memory aborts the
+//! process instead, as it does in Rust.
+
+use crate::transposition::TranspositionTable;
+use crate::{BytesKeys, ZobristHashSet};
+use std::panic::{self, AssertUnwindSafe};
+
+/// The set behind a `ZobristSet *`.
+pub type ZobristSet = ZobristHashSet<Vec<u8>, BytesKeys>;
+
+/// The table behind a `ZobristTable *`.
+pub type ZobristTable = TranspositionTable<u64>;
+
+/// Returns a new empty set, to be released with [`zobrist_free`].
+#[no_mangle]
+pub extern "C" fn zobrist_new() -> *mut ZobristSet {
+    guarded(std::ptr::null_mut(), || {
+        Box::into_raw(Box::new(ZobristSet::with_keys(BytesKeys)))
+    })
+}
+
+/// Releases a set returned by [`zobrist_new`]. Null is ignored.
+///
+/// # Safety
+///
+/// `set` must be null or a pointer returned by [`zobrist_new`] that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn zobrist_free(set: *mut ZobristSet) {
+    if !set.is_null() {
+        guarded((), || drop(Box::from_raw(set)));
+    }
+}
+
+/// Adds the `len` bytes at `bytes` to `set`, returning `false` without changing it if the
+/// checker rejects the update.
+///
+/// # Safety
+///
+/// `set` must be a live set, and `bytes` must point to `len` readable bytes, or be null with
+/// `len` zero.
+#[no_mangle]
+pub unsafe extern "C" fn zobrist_add_bytes(
+    set: *mut ZobristSet,
+    bytes: *const u8,
+    len: usize,
+) -> bool {
+    guarded(false, || (*set).try_add(&element(bytes, len)).is_ok())
+}
+
+/// Removes the `len` bytes at `bytes` from `set`, like [`zobrist_add_bytes`].
+///
+/// # Safety
+///
+/// As for [`zobrist_add_bytes`].
+#[no_mangle]
+pub unsafe extern "C" fn zobrist_remove_bytes(
+    set: *mut ZobristSet,
+    bytes: *const u8,
+    len: usize,
+) -> bool {
+    guarded(false, || (*set).try_remove(&element(bytes, len)).is_ok())
+}
+
+/// Returns the hash value of `set`.
+///
+/// # Safety
+///
+/// `set` must be a live set.
+#[no_mangle]
+pub unsafe extern "C" fn zobrist_value(set: *const ZobristSet) -> u64 {
+    (*set).hash()
+}
+
+/// Returns a new table with room for about `entries` values, to be released with
+/// [`zobrist_tt_free`].
+#[no_mangle]
+pub extern "C" fn zobrist_tt_new(entries: usize) -> *mut ZobristTable {
+    guarded(std::ptr::null_mut(), || {
+        Box::into_raw(Box::new(ZobristTable::with_entries(entries)))
+    })
+}
+
+/// Releases a table returned by [`zobrist_tt_new`]. Null is ignored.
+///
+/// # Safety
+///
+/// `table` must be null or a pointer returned by [`zobrist_tt_new`] that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn zobrist_tt_free(table: *mut ZobristTable) {
+    if !table.is_null() {
+        guarded((), || drop(Box::from_raw(table)));
+    }
+}
+
+/// Stores `value` for `hash`.
+///
+/// # Safety
+///
+/// `table` must be a live table.
+#[no_mangle]
+pub unsafe extern "C" fn zobrist_tt_store(table: *mut ZobristTable, hash: u64, value: u64) {
+    guarded((), || (*table).store(hash, value));
+}
+
+/// Looks up `hash`, writing the stored value to `value` and returning `true` on a hit.
+///
+/// # Safety
+///
+/// `table` must be a live table and `value` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn zobrist_tt_probe(
+    table: *const ZobristTable,
+    hash: u64,
+    value: *mut u64,
+) -> bool {
+    guarded(false, || match (*table).probe(hash) {
+        Some(&found) => {
+            *value = found;
+            true
+        }
+        None => false,
+    })
+}
+
+/// Runs `f`, returning `fallback` instead of unwinding if it panics.
+fn guarded<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+unsafe fn element(bytes: *const u8, len: usize) -> Vec<u8> {
+    if len == 0 {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(bytes, len).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    use crate::KeySource;
+
+    #[test]
+    fn c_api_matches_rust_api() {
+        unsafe {
+            let set = zobrist_new();
+            assert!(zobrist_add_bytes(set, b"e4".as_ptr(), 2));
+            assert!(zobrist_add_bytes(set, b"Nf3".as_ptr(), 3));
+            assert!(zobrist_remove_bytes(set, b"e4".as_ptr(), 2));
+            assert!(zobrist_add_bytes(set, std::ptr::null(), 0));
+
+            let mut expected = ZobristSet::with_keys(BytesKeys);
+            expected.add(&b"Nf3".to_vec());
+            expected.add(&Vec::new());
+            assert_eq!(zobrist_value(set), expected.hash());
+
+            let table = zobrist_tt_new(1024);
+            let mut value = 0;
+            assert!(!zobrist_tt_probe(table, zobrist_value(set), &mut value));
+            zobrist_tt_store(table, zobrist_value(set), 77);
+            assert!(zobrist_tt_probe(table, zobrist_value(set), &mut value));
+            assert_eq!(value, 77);
+
+            zobrist_tt_free(table);
+            zobrist_free(set);
+            zobrist_free(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn rejected_updates_return_false() {
+        unsafe {
+            let set = zobrist_new();
+            assert!(!zobrist_remove_bytes(set, b"e4".as_ptr(), 2));
+            assert!(zobrist_add_bytes(set, b"e4".as_ptr(), 2));
+            assert!(!zobrist_add_bytes(set, b"e4".as_ptr(), 2));
+            assert_eq!(zobrist_value(set), BytesKeys.key(b"e4"));
+            zobrist_free(set);
+        }
+    }
+}
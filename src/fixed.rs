@@ -0,0 +1,204 @@
+use crate::keys::mix64;
+use crate::{FxKeys, KeySource};
+use core::hash::Hash;
+
+/// The error returned when inserting into a full [`FixedTrackedZobristHashSet`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError;
+
+impl core::fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("fixed-capacity set is full")
+    }
+}
+
+impl core::error::Error for CapacityError {}
+
+/// A [`TrackedZobristHashSet`](crate::TrackedZobristHashSet) that stores up to `N` elements
+/// inline, without allocating.
+///
+/// Elements live in an open-addressing table of `N` slots, so the set can be used on targets
+/// without an allocator. Inserting a new element into a full set fails with [`CapacityError`]
+/// and leaves the set unchanged. Lookups slow down as the table fills up; keep `N` about a
+/// quarter larger than the expected number of elements.
+///
+/// ```rust
+/// use zobristhash_set::{CapacityError, FixedTrackedZobristHashSet, ZobristHashSet};
+///
+/// let mut stones = FixedTrackedZobristHashSet::<(u8, u8), 4>::new();
+/// for point in [(0, 0), (1, 2), (3, 3), (2, 0)] {
+///     assert_eq!(stones.insert(point), Ok(true));
+/// }
+/// assert_eq!(stones.insert((1, 1)), Err(CapacityError));
+/// assert_eq!(stones.insert((1, 2)), Ok(false));
+///
+/// assert!(stones.remove(&(1, 2)));
+/// let mut expected = ZobristHashSet::empty();
+/// for point in [(0u8, 0u8), (3, 3), (2, 0)] {
+///     expected.add(&point);
+/// }
+/// assert_eq!(stones.hash(), u64::from(expected));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FixedTrackedZobristHashSet<E, const N: usize, K = FxKeys> {
+    slots: [Option<E>; N],
+    len: usize,
+    hash: u64,
+    keys: K,
+}
+
+impl<E, const N: usize> FixedTrackedZobristHashSet<E, N> {
+    pub fn new() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E, const N: usize> Default for FixedTrackedZobristHashSet<E, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, const N: usize, K> FixedTrackedZobristHashSet<E, N, K> {
+    /// Creates an empty set that derives element keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        assert!(N > 0, "capacity must be positive");
+        Self {
+            slots: [const { None }; N],
+            len: 0,
+            hash: 0,
+            keys,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the maximum number of elements, `N`.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
+        self.slots.iter().flatten()
+    }
+
+    /// Removes every element.
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+        self.hash = 0;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(op = "clear", hash = self.hash);
+    }
+}
+
+impl<E: Hash + Eq, const N: usize, K: KeySource<E>> FixedTrackedZobristHashSet<E, N, K> {
+    /// Adds `element`, returning `Ok(false)` if it was already present.
+    pub fn insert(&mut self, element: E) -> Result<bool, CapacityError> {
+        let key = self.keys.key(&element);
+        match self.find(&element, key) {
+            Ok(_) => Ok(false),
+            Err(Some(slot)) => {
+                self.slots[slot] = Some(element);
+                self.len += 1;
+                self.hash ^= key;
+                #[cfg(feature = "tracing")]
+                tracing::trace!(op = "add", key, hash = self.hash);
+                Ok(true)
+            }
+            Err(None) => Err(CapacityError),
+        }
+    }
+
+    /// Removes `element`, returning `false` if it was not present.
+    pub fn remove(&mut self, element: &E) -> bool {
+        let key = self.keys.key(element);
+        let Ok(mut hole) = self.find(element, key) else {
+            return false;
+        };
+        self.slots[hole] = None;
+        self.len -= 1;
+        self.hash ^= key;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(op = "remove", key, hash = self.hash);
+        // Shift later members of the probe run back so lookups never stop at the hole early.
+        let mut slot = hole;
+        loop {
+            slot = (slot + 1) % N;
+            let Some(moved) = &self.slots[slot] else {
+                return true;
+            };
+            let home = home_slot::<N>(self.keys.key(moved));
+            if (slot + N - home) % N >= (slot + N - hole) % N {
+                self.slots[hole] = self.slots[slot].take();
+                hole = slot;
+            }
+        }
+    }
+
+    pub fn contains(&self, element: &E) -> bool {
+        self.find(element, self.keys.key(element)).is_ok()
+    }
+
+    /// Returns the slot holding `element`, or else the free slot where it belongs, if any.
+    fn find(&self, element: &E, key: u64) -> Result<usize, Option<usize>> {
+        let home = home_slot::<N>(key);
+        for probe in 0..N {
+            let slot = (home + probe) % N;
+            match &self.slots[slot] {
+                Some(present) if present == element => return Ok(slot),
+                Some(_) => {}
+                None => return Err(Some(slot)),
+            }
+        }
+        Err(None)
+    }
+}
+
+fn home_slot<const N: usize>(key: u64) -> usize {
+    ((u128::from(mix64(key)) * N as u128) >> 64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TrackedZobristHashSet;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn random_operations_match_the_tracked_set() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let mut fixed = FixedTrackedZobristHashSet::<u8, 64>::new();
+        let mut reference = TrackedZobristHashSet::new();
+        for _ in 0..10_000 {
+            let element = rng.gen_range(0..80u8);
+            if rng.gen_bool(0.5) {
+                match fixed.insert(element) {
+                    Ok(inserted) => assert_eq!(inserted, reference.insert(element)),
+                    Err(CapacityError) => {
+                        assert_eq!(reference.len(), 64);
+                        assert!(!reference.contains(&element));
+                    }
+                }
+            } else {
+                assert_eq!(fixed.remove(&element), reference.remove(&element));
+            }
+            assert_eq!(fixed.hash(), reference.hash());
+            assert_eq!(fixed.len(), reference.len());
+        }
+        for element in 0..80u8 {
+            assert_eq!(fixed.contains(&element), reference.contains(&element));
+        }
+    }
+}
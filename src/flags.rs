@@ -0,0 +1,115 @@
+use crate::{KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A fixed number of boolean state bits, such as castling rights, tracked in a parent hash.
+///
+/// Flag `i` is represented in the parent [`ZobristHashSet`] by the element `element(i)` while it
+/// is set. In checked builds, setting a flag to the value it already has panics, because in a
+/// plain XOR hash that mistake would silently toggle the flag's key back out.
+///
+/// ```rust
+/// use zobristhash_set::{ZobristFlags, ZobristHashSet};
+///
+/// #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+/// enum Element {
+///     Castling(usize),
+///     Ko(usize),
+/// }
+///
+/// let mut hash = ZobristHashSet::<Element>::empty();
+/// let mut castling = ZobristFlags::<_, 4>::new(Element::Castling);
+///
+/// castling.set_flag(&mut hash, 0, true);
+/// castling.set_flag(&mut hash, 2, true);
+/// castling.set_flag(&mut hash, 0, false);
+///
+/// let mut expected = ZobristHashSet::<Element>::empty();
+/// expected.add(&Element::Castling(2));
+/// assert_eq!(u64::from(hash), u64::from(expected));
+/// assert!(castling.get(2));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ZobristFlags<E, const N: usize> {
+    flags: [bool; N],
+    element: fn(usize) -> E,
+}
+
+impl<E, const N: usize> ZobristFlags<E, N> {
+    /// Creates a set of cleared flags that contribute `element(i)` for each set flag `i`.
+    pub fn new(element: fn(usize) -> E) -> Self {
+        Self {
+            flags: [false; N],
+            element,
+        }
+    }
+
+    /// Returns whether flag `index` is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    pub fn get(&self, index: usize) -> bool {
+        self.flags[index]
+    }
+
+    /// Sets flag `index` to `value`, updating `parent`, and returns the previous value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`, and in checked builds if the flag already has `value`.
+    pub fn set_flag<K>(
+        &mut self,
+        parent: &mut ZobristHashSet<E, K>,
+        index: usize,
+        value: bool,
+    ) -> bool
+    where
+        E: Hash + Eq + Clone + Debug,
+        K: KeySource<E>,
+    {
+        let old = self.flags[index];
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        assert_ne!(old, value, "flag {index} is already {value}");
+        if old != value {
+            let element = (self.element)(index);
+            if value {
+                parent.add(&element);
+            } else {
+                parent.remove(&element);
+            }
+            self.flags[index] = value;
+        }
+        old
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_contribute_elements() {
+        let mut hash = ZobristHashSet::<(u8, usize)>::empty();
+        let mut flags = ZobristFlags::<_, 3>::new(|i| (1, i));
+        assert!(!flags.set_flag(&mut hash, 1, true));
+        assert!(!flags.set_flag(&mut hash, 2, true));
+        assert!(flags.set_flag(&mut hash, 1, false));
+
+        let mut expected = ZobristHashSet::empty();
+        expected.add(&(1u8, 2usize));
+        assert_eq!(hash.hash, expected.hash);
+        assert!(!flags.get(1));
+        assert!(flags.get(2));
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn double_set_debug() {
+        let mut hash = ZobristHashSet::<usize>::empty();
+        let mut flags = ZobristFlags::<_, 2>::new(|i| i);
+        flags.set_flag(&mut hash, 0, true);
+        flags.set_flag(&mut hash, 0, true);
+    }
+}
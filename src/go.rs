@@ -0,0 +1,296 @@
+use crate::{KeySource, ZobristHashSet};
+use rustc_hash::FxHashSet;
+
+/// A stone colour on a Go board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stone {
+    Black,
+    White,
+}
+
+impl Stone {
+    /// Returns the opposing colour.
+    pub fn opponent(self) -> Stone {
+        match self {
+            Stone::Black => Stone::White,
+            Stone::White => Stone::Black,
+        }
+    }
+}
+
+type PointHash = ZobristHashSet<(usize, Stone)>;
+
+/// Why a move was rejected by [`SuperkoBoard::play`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IllegalMove {
+    OutOfBounds,
+    Occupied,
+    Suicide,
+    /// The move would recreate an earlier board position.
+    Superko,
+}
+
+impl std::fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            IllegalMove::OutOfBounds => "point is outside the board",
+            IllegalMove::Occupied => "point is already occupied",
+            IllegalMove::Suicide => "move would capture its own group",
+            IllegalMove::Superko => "move would repeat an earlier position",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+/// A Go board that enforces positional superko.
+///
+/// The board keeps a Zobrist hash of its stones and the set of hashes of every position seen so
+/// far, so checking a move against the whole game history costs one hash lookup.
+///
+/// ```rust
+/// use zobristhash_set::go::{IllegalMove, Stone, SuperkoBoard};
+///
+/// // . B W .
+/// // B W . W
+/// // . B W .
+/// let mut board = SuperkoBoard::new(4);
+/// for (x, y) in [(1, 0), (0, 1), (1, 2)] {
+///     board.play(x, y, Stone::Black).unwrap();
+/// }
+/// for (x, y) in [(2, 0), (1, 1), (3, 1), (2, 2)] {
+///     board.play(x, y, Stone::White).unwrap();
+/// }
+///
+/// // Black takes the ko; White may not take it straight back.
+/// board.play(2, 1, Stone::Black).unwrap();
+/// assert!(board.would_violate_superko(1, 1, Stone::White));
+/// assert_eq!(board.play(1, 1, Stone::White), Err(IllegalMove::Superko));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SuperkoBoard {
+    size: usize,
+    points: Vec<Option<Stone>>,
+    zobrist: PointHash,
+    history: FxHashSet<u64>,
+}
+
+impl SuperkoBoard {
+    /// Creates an empty `size` by `size` board.
+    pub fn new(size: usize) -> Self {
+        let zobrist = PointHash::empty();
+        let mut history = FxHashSet::default();
+        history.insert(zobrist.hash);
+        Self {
+            size,
+            points: vec![None; size * size],
+            zobrist,
+            history,
+        }
+    }
+
+    /// Returns the stone at `(x, y)`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the point is outside the board.
+    pub fn get(&self, x: usize, y: usize) -> Option<Stone> {
+        assert!(x < self.size && y < self.size, "point is outside the board");
+        self.points[y * self.size + x]
+    }
+
+    /// Returns the hash of the current position.
+    pub fn hash(&self) -> u64 {
+        self.zobrist.hash
+    }
+
+    /// Returns whether playing `stone` at `(x, y)` would recreate an earlier position.
+    ///
+    /// Moves that are illegal for other reasons return `false`.
+    pub fn would_violate_superko(&self, x: usize, y: usize, stone: Stone) -> bool {
+        matches!(self.resolve(x, y, stone), Ok((_, hash)) if self.history.contains(&hash))
+    }
+
+    /// Plays `stone` at `(x, y)`, removing captured stones, and returns the captured points.
+    pub fn play(
+        &mut self,
+        x: usize,
+        y: usize,
+        stone: Stone,
+    ) -> Result<Vec<(usize, usize)>, IllegalMove> {
+        let (captured, hash) = self.resolve(x, y, stone)?;
+        if self.history.contains(&hash) {
+            return Err(IllegalMove::Superko);
+        }
+        let point = y * self.size + x;
+        self.points[point] = Some(stone);
+        self.zobrist.add(&(point, stone));
+        for &point in &captured {
+            self.points[point] = None;
+            self.zobrist.remove(&(point, stone.opponent()));
+        }
+        debug_assert_eq!(self.zobrist.hash, hash);
+        self.history.insert(hash);
+        Ok(captured
+            .into_iter()
+            .map(|point| (point % self.size, point / self.size))
+            .collect())
+    }
+
+    /// Computes the captures and resulting hash of a move without applying it.
+    fn resolve(&self, x: usize, y: usize, stone: Stone) -> Result<(Vec<usize>, u64), IllegalMove> {
+        if x >= self.size || y >= self.size {
+            return Err(IllegalMove::OutOfBounds);
+        }
+        let point = y * self.size + x;
+        if self.points[point].is_some() {
+            return Err(IllegalMove::Occupied);
+        }
+
+        let at = |p: usize| {
+            if p == point {
+                Some(stone)
+            } else {
+                self.points[p]
+            }
+        };
+
+        let mut captured = Vec::new();
+        let mut is_captured = vec![false; self.points.len()];
+        for neighbor in self.neighbors(point) {
+            if at(neighbor) == Some(stone.opponent()) && !is_captured[neighbor] {
+                // Captured stones never border another group of their colour, so treating them
+                // as empty does not change whether this group has a liberty.
+                let (group, has_liberty) = self.group(neighbor, &at, &is_captured);
+                if !has_liberty {
+                    for &p in &group {
+                        is_captured[p] = true;
+                    }
+                    captured.extend(group);
+                }
+            }
+        }
+        let (_, has_liberty) = self.group(point, &at, &is_captured);
+        if !has_liberty {
+            return Err(IllegalMove::Suicide);
+        }
+
+        let keys = &self.zobrist.keys;
+        let hash = captured
+            .iter()
+            .fold(self.zobrist.hash ^ keys.key(&(point, stone)), |hash, &p| {
+                hash ^ keys.key(&(p, stone.opponent()))
+            });
+        Ok((captured, hash))
+    }
+
+    /// Returns the group containing `start` and whether it has a liberty, treating the points
+    /// marked in `removed` as empty.
+    fn group(
+        &self,
+        start: usize,
+        at: &impl Fn(usize) -> Option<Stone>,
+        removed: &[bool],
+    ) -> (Vec<usize>, bool) {
+        let color = at(start);
+        let mut in_group = vec![false; self.points.len()];
+        in_group[start] = true;
+        let mut group = vec![start];
+        let mut stack = vec![start];
+        let mut has_liberty = false;
+        while let Some(p) = stack.pop() {
+            for neighbor in self.neighbors(p) {
+                if at(neighbor).is_none() || removed[neighbor] {
+                    has_liberty = true;
+                } else if at(neighbor) == color && !in_group[neighbor] {
+                    in_group[neighbor] = true;
+                    group.push(neighbor);
+                    stack.push(neighbor);
+                }
+            }
+        }
+        (group, has_liberty)
+    }
+
+    fn neighbors(&self, point: usize) -> impl Iterator<Item = usize> {
+        let size = self.size;
+        let (x, y) = (point % size, point / size);
+        [
+            (x > 0).then(|| point - 1),
+            (x + 1 < size).then(|| point + 1),
+            (y > 0).then(|| point - size),
+            (y + 1 < size).then(|| point + size),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_removes_stones_and_hash() {
+        let mut board = SuperkoBoard::new(3);
+        board.play(0, 0, Stone::White).unwrap();
+        board.play(1, 0, Stone::Black).unwrap();
+        assert_eq!(board.play(0, 1, Stone::Black), Ok(vec![(0, 0)]));
+        assert_eq!(board.get(0, 0), None);
+
+        let mut expected = SuperkoBoard::new(3);
+        expected.play(1, 0, Stone::Black).unwrap();
+        expected.play(0, 1, Stone::Black).unwrap();
+        assert_eq!(board.hash(), expected.hash());
+    }
+
+    #[test]
+    fn large_captures_update_the_hash_in_place() {
+        let mut board = SuperkoBoard::new(9);
+        let mut expected = SuperkoBoard::new(9);
+        for y in 0..9 {
+            board.play(0, y, Stone::White).unwrap();
+        }
+        for y in 0..8 {
+            board.play(1, y, Stone::Black).unwrap();
+        }
+        for y in 0..9 {
+            expected.play(1, y, Stone::Black).unwrap();
+        }
+        let captured = board.play(1, 8, Stone::Black).unwrap();
+        assert_eq!(captured.len(), 9);
+        assert!((0..9).all(|y| board.get(0, y).is_none()));
+        assert_eq!(board.hash(), expected.hash());
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let mut board = SuperkoBoard::new(3);
+        board.play(1, 0, Stone::Black).unwrap();
+        board.play(0, 1, Stone::Black).unwrap();
+        assert_eq!(board.play(0, 0, Stone::White), Err(IllegalMove::Suicide));
+        assert_eq!(board.play(1, 0, Stone::White), Err(IllegalMove::Occupied));
+        assert_eq!(
+            board.play(3, 0, Stone::White),
+            Err(IllegalMove::OutOfBounds)
+        );
+        assert!(!board.would_violate_superko(0, 0, Stone::White));
+    }
+
+    #[test]
+    fn ko_recapture_is_superko() {
+        let mut board = SuperkoBoard::new(4);
+        for (x, y) in [(1, 0), (0, 1), (1, 2)] {
+            board.play(x, y, Stone::Black).unwrap();
+        }
+        for (x, y) in [(2, 0), (1, 1), (3, 1), (2, 2)] {
+            board.play(x, y, Stone::White).unwrap();
+        }
+        assert!(!board.would_violate_superko(2, 1, Stone::Black));
+        assert_eq!(board.play(2, 1, Stone::Black), Ok(vec![(1, 1)]));
+        assert!(board.would_violate_superko(1, 1, Stone::White));
+        assert_eq!(board.play(1, 1, Stone::White), Err(IllegalMove::Superko));
+        assert_eq!(board.get(1, 1), None);
+    }
+}
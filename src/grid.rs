@@ -0,0 +1,112 @@
+use crate::ZobristHashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A `W` by `H` board of optional cells whose Zobrist hash is maintained automatically.
+///
+/// Each occupied cell contributes the element `(x, y, value)`. Replacing a cell's content removes
+/// the old element and adds the new one, which is the bookkeeping every board representation
+/// otherwise has to repeat.
+///
+/// ```rust
+/// use zobristhash_set::ZobristGrid;
+///
+/// let mut board = ZobristGrid::<char, 3, 3>::new();
+/// board.set(1, 1, Some('x'));
+/// let hash = board.hash();
+///
+/// board.set(0, 2, Some('o'));
+/// board.set(0, 2, None);
+/// assert_eq!(board.hash(), hash);
+/// assert_eq!(board.get(1, 1), Some(&'x'));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    not(all(debug_assertions, feature = "check_set_behavior")),
+    derive(Copy)
+)]
+pub struct ZobristGrid<T, const W: usize, const H: usize> {
+    cells: [[Option<T>; W]; H],
+    zobrist: ZobristHashSet<(usize, usize, T)>,
+}
+
+impl<T, const W: usize, const H: usize> ZobristGrid<T, W, H> {
+    /// Creates a grid with every cell empty.
+    pub fn new() -> Self {
+        Self {
+            cells: std::array::from_fn(|_| std::array::from_fn(|_| None)),
+            zobrist: ZobristHashSet::empty(),
+        }
+    }
+
+    /// Returns the content of the cell at `(x, y)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x >= W` or `y >= H`.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.cells[y][x].as_ref()
+    }
+
+    /// Returns the hash of the current board.
+    pub fn hash(&self) -> u64 {
+        self.zobrist.hash
+    }
+}
+
+impl<T, const W: usize, const H: usize> Default for ZobristGrid<T, W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq + Clone + Debug, const W: usize, const H: usize> ZobristGrid<T, W, H> {
+    /// Replaces the content of the cell at `(x, y)` and returns the previous content.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x >= W` or `y >= H`.
+    pub fn set(&mut self, x: usize, y: usize, value: Option<T>) -> Option<T> {
+        if let Some(old) = &self.cells[y][x] {
+            self.zobrist.remove(&(x, y, old.clone()));
+        }
+        if let Some(new) = &value {
+            self.zobrist.add(&(x, y, new.clone()));
+        }
+        std::mem::replace(&mut self.cells[y][x], value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_matches_set_of_cells() {
+        let mut grid = ZobristGrid::<u8, 4, 2>::new();
+        grid.set(3, 1, Some(7));
+        grid.set(0, 0, Some(2));
+        assert_eq!(grid.set(0, 0, Some(5)), Some(2));
+
+        let mut expected = ZobristHashSet::empty();
+        expected.add(&(3usize, 1usize, 7u8));
+        expected.add(&(0, 0, 5));
+        assert_eq!(grid.hash(), expected.hash);
+    }
+
+    #[test]
+    fn clearing_restores_empty_hash() {
+        let mut grid = ZobristGrid::<u8, 2, 2>::default();
+        grid.set(1, 0, Some(1));
+        assert_eq!(grid.set(1, 0, None), Some(1));
+        assert_eq!(grid.get(1, 0), None);
+        assert_eq!(grid.hash(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        let mut grid = ZobristGrid::<u8, 2, 2>::new();
+        grid.set(2, 0, Some(1));
+    }
+}
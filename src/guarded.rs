@@ -0,0 +1,133 @@
+use crate::keys::mix64;
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash that catches removals of elements that were never added, in release builds
+/// too.
+///
+/// Next to the hash it keeps a counting sketch of `COUNTERS` one-byte counters, two per element:
+/// adding an element increments its counters and removing it decrements them, so removing an
+/// element that was never added usually finds one of its counters at zero and panics. Detection
+/// is probabilistic; it misses when both counters happen to be raised by other elements, which
+/// becomes likely once the set holds about as many elements as there are counters. Unlike the
+/// debug checker it costs `COUNTERS` bytes and a few instructions per update, with no limit on
+/// the number of elements.
+///
+/// ```rust,should_panic
+/// use zobristhash_set::GuardedZobristHashSet;
+///
+/// let mut position = GuardedZobristHashSet::<_>::empty();
+/// position.add(&("knight", 1));
+/// position.remove(&("knight", 2)); // panics
+/// ```
+#[derive(Clone, Debug)]
+pub struct GuardedZobristHashSet<E, K = FxKeys, const COUNTERS: usize = 64> {
+    set: ZobristHashSet<E, K>,
+    counters: [u8; COUNTERS],
+}
+
+impl<E, const COUNTERS: usize> GuardedZobristHashSet<E, FxKeys, COUNTERS> {
+    pub fn empty() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E, const COUNTERS: usize> Default for GuardedZobristHashSet<E, FxKeys, COUNTERS> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<E, K, const COUNTERS: usize> GuardedZobristHashSet<E, K, COUNTERS> {
+    /// Creates an empty set that derives element keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        assert!(COUNTERS > 0, "the sketch needs at least one counter");
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            counters: [0; COUNTERS],
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    fn counters_of(key: u64) -> [usize; 2] {
+        let mixed = mix64(key);
+        [
+            (mixed as u32) as usize % COUNTERS,
+            (mixed >> 32) as usize % COUNTERS,
+        ]
+    }
+}
+
+impl<E, K, const COUNTERS: usize> From<GuardedZobristHashSet<E, K, COUNTERS>> for u64 {
+    fn from(hash: GuardedZobristHashSet<E, K, COUNTERS>) -> u64 {
+        hash.set.hash
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>, const COUNTERS: usize>
+    GuardedZobristHashSet<E, K, COUNTERS>
+{
+    pub fn add(&mut self, key: &E) {
+        for i in Self::counters_of(self.set.keys.key(key)) {
+            // A saturated counter stays saturated, so it never reaches zero wrongly.
+            self.counters[i] = self.counters[i].saturating_add(1);
+        }
+        self.set.add(key);
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        let counters = Self::counters_of(self.set.keys.key(key));
+        assert!(
+            counters.iter().all(|&i| self.counters[i] > 0),
+            "cannot remove an element that was never added"
+        );
+        for i in counters {
+            if self.counters[i] != u8::MAX {
+                self.counters[i] -= 1;
+            }
+        }
+        self.set.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balanced_updates_are_accepted() {
+        let mut set = GuardedZobristHashSet::<u32, FxKeys, 16>::empty();
+        for round in 0..3 {
+            for i in 0..1000 {
+                set.add(&(round * 1000 + i));
+            }
+            for i in 0..1000 {
+                set.remove(&(round * 1000 + i));
+            }
+        }
+        assert_eq!(set.hash(), 0);
+        assert!(set.counters.iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn unmatched_removals_are_usually_caught() {
+        let mut caught = 0;
+        for stray in 100..200u32 {
+            let mut set = GuardedZobristHashSet::<u32>::empty();
+            for i in 0..8 {
+                set.add(&i);
+            }
+            // Only count panics of the sketch, not of the debug checker behind it.
+            let result = std::panic::catch_unwind(move || set.remove(&stray));
+            caught += usize::from(result.is_err_and(|payload| {
+                payload.downcast_ref::<&str>()
+                    == Some(&"cannot remove an element that was never added")
+            }));
+        }
+        assert!(caught > 90, "{caught}");
+    }
+}
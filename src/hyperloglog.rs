@@ -0,0 +1,144 @@
+use crate::keys::mix64;
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash paired with a HyperLogLog sketch estimating how many distinct elements were
+/// added.
+///
+/// A sketch with precision `p` uses 2<sup>p</sup> one-byte registers and has a standard error
+/// of about 1.04 / 2<sup>p/2</sup>, e.g. 1.6% for `p = 12`. HyperLogLog cannot forget:
+/// [`remove`](Self::remove) updates the hash but not the estimate, so the estimate counts every
+/// element that was ever added.
+///
+/// ```rust
+/// use zobristhash_set::HllZobristSet;
+///
+/// let mut set = HllZobristSet::with_precision(12);
+/// for i in 0..5000u32 {
+///     set.add(&i);
+/// }
+/// let estimate = set.estimated_len();
+/// assert!((4500.0..5500.0).contains(&estimate));
+/// ```
+#[derive(Clone, Debug)]
+pub struct HllZobristSet<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    registers: Vec<u8>,
+    precision: u32,
+}
+
+impl<E> HllZobristSet<E> {
+    /// Creates an empty set with 2<sup>`precision`</sup> registers; `precision` must be in
+    /// `4..=18`.
+    pub fn with_precision(precision: u32) -> Self {
+        Self::with_keys(FxKeys, precision)
+    }
+}
+
+impl<E, K> HllZobristSet<E, K> {
+    /// Like [`with_precision`](HllZobristSet::with_precision), deriving keys from `keys`.
+    pub fn with_keys(keys: K, precision: u32) -> Self {
+        assert!((4..=18).contains(&precision), "precision must be in 4..=18");
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            registers: vec![0; 1 << precision],
+            precision,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the size of the sketch in bytes.
+    pub fn sketch_bytes(&self) -> usize {
+        self.registers.len()
+    }
+
+    /// Estimates the number of distinct elements added so far.
+    pub fn estimated_len(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| 2f64.powi(-i32::from(rank)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|&&rank| rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate while many registers are still empty.
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        }
+    }
+
+    /// Adds every element counted by `other` to this sketch; the hashes are left unchanged.
+    ///
+    /// Both sets must have the same precision.
+    pub fn merge_sketch(&mut self, other: &Self) {
+        assert_eq!(
+            self.precision, other.precision,
+            "sketches must have the same precision"
+        );
+        for (rank, &other) in self.registers.iter_mut().zip(&other.registers) {
+            *rank = (*rank).max(other);
+        }
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> HllZobristSet<E, K> {
+    pub fn add(&mut self, key: &E) {
+        let h = mix64(self.set.add(key));
+        let index = (h >> (64 - self.precision)) as usize;
+        let rest = (h << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        self.set.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_are_within_a_few_standard_errors() {
+        for count in [10u32, 100, 1000, 8000] {
+            let mut set = HllZobristSet::with_precision(10);
+            for i in 0..count {
+                set.add(&i);
+            }
+            let error = (set.estimated_len() - f64::from(count)).abs() / f64::from(count);
+            assert!(error < 0.15, "{count}: {}", set.estimated_len());
+        }
+    }
+
+    #[test]
+    fn removal_keeps_the_estimate() {
+        let mut a = HllZobristSet::with_precision(8);
+        let mut b = HllZobristSet::with_precision(8);
+        for i in 0..100u32 {
+            a.add(&i);
+            b.add(&(i + 100));
+        }
+        let before = a.estimated_len();
+        for i in 0..100u32 {
+            a.remove(&i);
+        }
+        assert_eq!(a.hash(), 0);
+        assert_eq!(a.estimated_len(), before);
+        a.merge_sketch(&b);
+        assert!((150.0..250.0).contains(&a.estimated_len()));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::keys::mix64;
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+const HASHES: usize = 3;
+
+/// An invertible Bloom lookup table over 64-bit element keys.
+///
+/// The table of one set minus the table of another can be [decoded](Self::decode) into the keys
+/// that differ, as long as there are not too many of them: with `cells` cells, differences of up
+/// to about `cells / 1.5` keys decode with high probability. Both tables must have the same
+/// number of cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Iblt {
+    cells: Vec<Cell>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Cell {
+    count: i64,
+    key_sum: u64,
+    check_sum: u64,
+}
+
+impl Cell {
+    fn toggle(&mut self, key: u64, count: i64) {
+        self.count += count;
+        self.key_sum ^= key;
+        self.check_sum ^= check(key);
+    }
+
+    /// Returns the only key left in the cell, if there is one.
+    fn pure_key(&self) -> Option<u64> {
+        (matches!(self.count, 1 | -1) && self.check_sum == check(self.key_sum))
+            .then_some(self.key_sum)
+    }
+}
+
+fn check(key: u64) -> u64 {
+    mix64(key ^ 0x5851_F42D_4C95_7F2D)
+}
+
+impl Iblt {
+    /// Creates an empty table with `cells` cells, rounded up to a multiple of three.
+    pub fn with_cells(cells: usize) -> Self {
+        Self {
+            cells: vec![Cell::default(); cells.max(1).div_ceil(HASHES) * HASHES],
+        }
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn insert(&mut self, key: u64) {
+        self.update(key, 1);
+    }
+
+    pub fn remove(&mut self, key: u64) {
+        self.update(key, -1);
+    }
+
+    /// Returns the table of the keys in `self` but not `other`, and vice versa.
+    pub fn subtract(&self, other: &Iblt) -> Iblt {
+        assert_eq!(
+            self.cells.len(),
+            other.cells.len(),
+            "tables must have the same number of cells"
+        );
+        let cells = self
+            .cells
+            .iter()
+            .zip(&other.cells)
+            .map(|(a, b)| Cell {
+                count: a.count - b.count,
+                key_sum: a.key_sum ^ b.key_sum,
+                check_sum: a.check_sum ^ b.check_sum,
+            })
+            .collect();
+        Iblt { cells }
+    }
+
+    /// Lists the keys of the table, split into those inserted more often than removed and the
+    /// reverse. For a [`subtract`](Self::subtract)ed table these are the keys only in the first
+    /// and only in the second set.
+    ///
+    /// Returns `None` if the table holds too many keys to be decoded.
+    pub fn decode(&self) -> Option<(Vec<u64>, Vec<u64>)> {
+        let mut table = self.clone();
+        let (mut positive, mut negative) = (Vec::new(), Vec::new());
+        let mut pending: Vec<usize> = (0..table.cells.len()).collect();
+        while let Some(i) = pending.pop() {
+            let Some(key) = table.cells[i].pure_key() else {
+                continue;
+            };
+            let count = table.cells[i].count;
+            if count > 0 {
+                positive.push(key);
+            } else {
+                negative.push(key);
+            }
+            for j in table.positions(key) {
+                table.cells[j].toggle(key, -count);
+                pending.push(j);
+            }
+        }
+        table
+            .cells
+            .iter()
+            .all(|cell| *cell == Cell::default())
+            .then_some((positive, negative))
+    }
+
+    fn update(&mut self, key: u64, count: i64) {
+        for i in self.positions(key) {
+            self.cells[i].toggle(key, count);
+        }
+    }
+
+    /// Returns one cell in each third of the table.
+    fn positions(&self, key: u64) -> [usize; HASHES] {
+        let part = self.cells.len() / HASHES;
+        std::array::from_fn(|j| {
+            let h = mix64(key.wrapping_add((j as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)));
+            j * part + (h % part as u64) as usize
+        })
+    }
+}
+
+/// A Zobrist hash paired with an [`Iblt`] of its element keys, so that when two replicas'
+/// hashes disagree they can find out which elements differ.
+///
+/// ```rust
+/// use zobristhash_set::{KeySource, FxKeys, ReconcilableZobristSet};
+///
+/// let mut ours = ReconcilableZobristSet::with_cells(30);
+/// let mut theirs = ReconcilableZobristSet::with_cells(30);
+/// for i in 0..1000u32 {
+///     ours.add(&i);
+///     theirs.add(&i);
+/// }
+/// ours.remove(&7);
+/// theirs.add(&5000);
+///
+/// let (only_ours, only_theirs) = ours.difference(&theirs).unwrap();
+/// assert!(only_ours.is_empty());
+/// assert_eq!(only_theirs.len(), 2);
+/// assert!(only_theirs.contains(&FxKeys.key(&7u32)));
+/// assert!(only_theirs.contains(&FxKeys.key(&5000u32)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReconcilableZobristSet<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    iblt: Iblt,
+}
+
+impl<E> ReconcilableZobristSet<E> {
+    /// Creates an empty set whose table has `cells` cells.
+    pub fn with_cells(cells: usize) -> Self {
+        Self::with_keys(FxKeys, cells)
+    }
+}
+
+impl<E, K> ReconcilableZobristSet<E, K> {
+    /// Creates an empty set whose table has `cells` cells, deriving keys from `keys`.
+    pub fn with_keys(keys: K, cells: usize) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            iblt: Iblt::with_cells(cells),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    pub fn iblt(&self) -> &Iblt {
+        &self.iblt
+    }
+
+    /// Returns the keys of the elements only in `self` and only in `other`, or `None` if the
+    /// difference is too large to decode.
+    pub fn difference(&self, other: &Self) -> Option<(Vec<u64>, Vec<u64>)> {
+        if self.set.hash == other.set.hash {
+            return Some((Vec::new(), Vec::new()));
+        }
+        self.iblt.subtract(&other.iblt).decode()
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> ReconcilableZobristSet<E, K> {
+    pub fn add(&mut self, key: &E) {
+        let element_key = self.set.add(key);
+        self.iblt.insert(element_key);
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        let element_key = self.set.remove(key);
+        self.iblt.remove(element_key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_small_differences() {
+        let mut a = Iblt::with_cells(60);
+        let mut b = Iblt::with_cells(60);
+        for key in 0..5000u64 {
+            a.insert(key * 3);
+            b.insert(key * 3);
+        }
+        for key in 1..=20u64 {
+            a.insert(key * 1_000_003);
+            b.insert(key * 7_000_001);
+        }
+        let (mut only_a, mut only_b) = a.subtract(&b).decode().unwrap();
+        only_a.sort_unstable();
+        only_b.sort_unstable();
+        assert_eq!(only_a, (1..=20).map(|k| k * 1_000_003).collect::<Vec<_>>());
+        assert_eq!(only_b, (1..=20).map(|k| k * 7_000_001).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn large_differences_are_reported() {
+        let mut a = Iblt::with_cells(12);
+        for key in 0..100u64 {
+            a.insert(key);
+        }
+        assert_eq!(a.decode(), None);
+        assert_eq!(Iblt::with_cells(12).decode(), Some((vec![], vec![])));
+    }
+}
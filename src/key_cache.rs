@@ -0,0 +1,187 @@
+use crate::KeySource;
+use rustc_hash::FxHashMap;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+thread_local! {
+    /// Per-thread caches keyed by the [`CachedKeys`] id and element type.
+    static CACHES: RefCell<FxHashMap<(u64, TypeId), Box<dyn Any>>> = RefCell::default();
+    static STATS: RefCell<FxHashMap<u64, KeyCacheStats>> = RefCell::default();
+}
+
+/// A key source that remembers recently derived keys in a thread-local cache.
+///
+/// The cache keeps about `capacity` elements per thread and element type, evicting the least
+/// recently used ones in bulk: once half the capacity has been filled since the last eviction,
+/// elements not used since then are dropped. Looking an element up hashes it with `FxHash`, so
+/// the cache only pays off when the wrapped source is considerably more expensive than that,
+/// such as a cryptographic key source or elements with long fields. Check [`stats`](Self::stats)
+/// to confirm it does.
+///
+/// Copies of a `CachedKeys` share their caches. Entries live until [`clear`](Self::clear) is
+/// called or the thread exits.
+///
+/// ```rust
+/// use zobristhash_set::{CachedKeys, SplitMixKeys, ZobristHashSet};
+///
+/// let keys = CachedKeys::new(SplitMixKeys, 1024);
+/// let mut hash = ZobristHashSet::with_keys(keys);
+/// for _ in 0..10 {
+///     hash.add(&"a long element that is expensive to hash");
+///     hash.remove(&"a long element that is expensive to hash");
+/// }
+/// let stats = keys.stats();
+/// assert_eq!(stats.misses, 1);
+/// assert_eq!(stats.hits, 19);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct CachedKeys<S> {
+    source: S,
+    id: u64,
+    capacity: usize,
+}
+
+/// Lookup counters of a [`CachedKeys`] on one thread.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl KeyCacheStats {
+    /// Returns the fraction of lookups that were answered from the cache, or `0.0` before the
+    /// first one.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+struct Cache<E> {
+    recent: FxHashMap<E, u64>,
+    older: FxHashMap<E, u64>,
+}
+
+impl<S> CachedKeys<S> {
+    /// Wraps `source` with caches of about `capacity` elements.
+    pub fn new(source: S, capacity: usize) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            source,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            capacity: capacity.max(2),
+        }
+    }
+
+    /// Returns the counters of the current thread.
+    pub fn stats(&self) -> KeyCacheStats {
+        STATS.with(|stats| stats.borrow().get(&self.id).copied().unwrap_or_default())
+    }
+
+    /// Empties the caches and counters of the current thread.
+    pub fn clear(&self) {
+        CACHES.with(|caches| caches.borrow_mut().retain(|&(id, _), _| id != self.id));
+        STATS.with(|stats| stats.borrow_mut().remove(&self.id));
+    }
+
+    fn record(&self, hit: bool) {
+        STATS.with(|stats| {
+            let mut stats = stats.borrow_mut();
+            let stats = stats.entry(self.id).or_default();
+            if hit {
+                stats.hits += 1;
+            } else {
+                stats.misses += 1;
+            }
+        });
+    }
+}
+
+impl<E, S> KeySource<E> for CachedKeys<S>
+where
+    E: Hash + Eq + Clone + 'static,
+    S: KeySource<E>,
+{
+    fn key(&self, element: &E) -> u64 {
+        let slot = (self.id, TypeId::of::<E>());
+        let cached = CACHES.with(|caches| {
+            let mut caches = caches.borrow_mut();
+            let cache = cache_mut::<E>(&mut caches, slot)?;
+            if let Some(&key) = cache.recent.get(element) {
+                return Some(key);
+            }
+            let key = cache.older.remove(element)?;
+            cache.recent.insert(element.clone(), key);
+            Some(key)
+        });
+        self.record(cached.is_some());
+        if let Some(key) = cached {
+            return key;
+        }
+        // The wrapped source may use the cache itself, so it runs outside the borrow.
+        let key = self.source.key(element);
+        CACHES.with(|caches| {
+            let mut caches = caches.borrow_mut();
+            let cache = caches.entry(slot).or_insert_with(|| {
+                Box::new(Cache::<E> {
+                    recent: FxHashMap::default(),
+                    older: FxHashMap::default(),
+                })
+            });
+            let cache = cache.downcast_mut::<Cache<E>>().expect("slot matches type");
+            if cache.recent.len() >= self.capacity / 2 {
+                cache.older = std::mem::take(&mut cache.recent);
+            }
+            cache.recent.insert(element.clone(), key);
+        });
+        key
+    }
+}
+
+fn cache_mut<E: 'static>(
+    caches: &mut FxHashMap<(u64, TypeId), Box<dyn Any>>,
+    slot: (u64, TypeId),
+) -> Option<&mut Cache<E>> {
+    caches.get_mut(&slot)?.downcast_mut()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FxKeys;
+
+    #[test]
+    fn keys_match_the_wrapped_source() {
+        let keys = CachedKeys::new(FxKeys, 8);
+        for round in 0..3 {
+            for i in 0..20u32 {
+                assert_eq!(keys.key(&i), FxKeys.key(&i), "round {round}");
+            }
+        }
+        assert_eq!(keys.key(&"str"), FxKeys.key(&"str"));
+        let stats = keys.stats();
+        assert_eq!(stats.hits + stats.misses, 61);
+        keys.clear();
+        assert_eq!(keys.stats(), KeyCacheStats::default());
+    }
+
+    #[test]
+    fn recently_used_elements_survive_eviction() {
+        let keys = CachedKeys::new(FxKeys, 8);
+        keys.key(&0u32);
+        for i in 1..100u32 {
+            keys.key(&i);
+            keys.key(&0u32);
+        }
+        assert_eq!(keys.stats().misses, 100);
+        assert_eq!(keys.stats().hit_rate(), 99.0 / 199.0);
+
+        let other = CachedKeys::new(FxKeys, 8);
+        other.key(&0u32);
+        assert_eq!(other.stats().misses, 1);
+    }
+}
@@ -0,0 +1,272 @@
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "std")]
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use rustc_hash::FxHashMap;
+use rustc_hash::FxHasher;
+#[cfg(feature = "std")]
+use std::any::TypeId;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, OnceLock};
+
+/// Derives the 64-bit key that an element contributes to a [`crate::ZobristHashSet`].
+pub trait KeySource<E> {
+    fn key(&self, element: &E) -> u64;
+
+    /// Returns the XOR of the keys of `elements`, as used by the batch updates of a set.
+    ///
+    /// The default folds the keys into eight independent accumulators, which leaves the
+    /// processor free to overlap consecutive key computations. Key sources that can derive
+    /// several keys per instruction, such as [`WordKeys`](crate::WordKeys), override it.
+    fn xor_keys(&self, elements: &[E]) -> u64 {
+        const LANES: usize = 8;
+
+        let mut lanes = [0u64; LANES];
+        let mut chunks = elements.chunks_exact(LANES);
+        for chunk in &mut chunks {
+            for (lane, element) in lanes.iter_mut().zip(chunk) {
+                *lane ^= self.key(element);
+            }
+        }
+        chunks
+            .remainder()
+            .iter()
+            .fold(lanes.iter().fold(0, |a, b| a ^ b), |hash, element| {
+                hash ^ self.key(element)
+            })
+    }
+}
+
+/// The default key source: hashes each element with `FxHash`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct FxKeys;
+
+impl<E: Hash> KeySource<E> for FxKeys {
+    fn key(&self, element: &E) -> u64 {
+        let mut hasher = FxHasher::default();
+        element.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// A key source that runs each element's `FxHash` through the SplitMix64 finalizer.
+///
+/// `FxHash` values of related elements (consecutive integers, tuples differing in one field)
+/// share many bits. Mixing them yields keys that are close to uniform and independent, which is
+/// what Zobrist hashing's collision bounds assume, while staying just as context-less.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct SplitMixKeys;
+
+impl<E: Hash> KeySource<E> for SplitMixKeys {
+    fn key(&self, element: &E) -> u64 {
+        mix64(FxKeys.key(element))
+    }
+}
+
+/// A key source for byte strings that derives the same keys on every platform.
+///
+/// [`FxKeys`] hashes in `usize` steps, so its keys differ between 32-bit and 64-bit targets.
+/// This source reads the bytes in little-endian 64-bit words and only does 64-bit arithmetic,
+/// so a byte string has one key whether it is hashed natively, in WebAssembly or through the
+/// C and Python bindings. It accepts anything that can be viewed as bytes, so `Vec<u8>`,
+/// `&[u8]` and `[u8; N]` elements with the same contents share a key.
+///
+/// ```rust
+/// use zobristhash_set::{BytesKeys, KeySource};
+///
+/// assert_eq!(BytesKeys.key(&b"Ke1".to_vec()), BytesKeys.key(b"Ke1"));
+/// assert_eq!(BytesKeys.key(b"Ke1"), 0xd6e0_7ab8_958a_caa7);
+/// ```
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct BytesKeys;
+
+impl<B: AsRef<[u8]>> KeySource<B> for BytesKeys {
+    fn key(&self, element: &B) -> u64 {
+        const MULTIPLIER: u64 = 0xF135_7AEA_2E62_A9C5;
+
+        let bytes = element.as_ref();
+        let mut chunks = bytes.chunks_exact(8);
+        let mut hash = (bytes.len() as u64 ^ 0x9E37_79B9_7F4A_7C15).wrapping_mul(MULTIPLIER);
+        for chunk in &mut chunks {
+            let word = u64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+            hash = (hash.rotate_left(26) ^ word).wrapping_mul(MULTIPLIER);
+        }
+        let mut last = [0; 8];
+        last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+        hash = (hash.rotate_left(26) ^ u64::from_le_bytes(last)).wrapping_mul(MULTIPLIER);
+        mix64(hash)
+    }
+}
+
+/// An element type with a known, finite set of values, such as a fieldless enum.
+///
+/// `index` must return a distinct value in `0..SIZE` for every element.
+pub trait FiniteDomain {
+    const SIZE: usize;
+
+    fn index(&self) -> usize;
+}
+
+/// A key source that looks elements up in a random table shared by the whole process.
+///
+/// The table for each element type is generated once, on first use, and every
+/// `TableKeys<E>` in the program refers to the same table.
+#[cfg(feature = "std")]
+pub struct TableKeys<E> {
+    table: &'static [u64],
+    _marker: PhantomData<fn(&E)>,
+}
+
+#[cfg(feature = "std")]
+impl<E> Clone for TableKeys<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E> Copy for TableKeys<E> {}
+
+#[cfg(feature = "std")]
+impl<E> std::fmt::Debug for TableKeys<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TableKeys")
+            .field("len", &self.table.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: FiniteDomain + 'static> TableKeys<E> {
+    /// Returns the key source backed by the process-wide table for `E`.
+    pub fn registered() -> Self {
+        Self {
+            table: registered_table::<E>(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: FiniteDomain + 'static> Default for TableKeys<E> {
+    fn default() -> Self {
+        Self::registered()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: FiniteDomain> KeySource<E> for TableKeys<E> {
+    fn key(&self, element: &E) -> u64 {
+        self.table[element.index()]
+    }
+}
+
+#[cfg(feature = "std")]
+type Registry = Mutex<FxHashMap<TypeId, &'static [u64]>>;
+
+#[cfg(feature = "std")]
+fn registered_table<E: FiniteDomain + 'static>() -> &'static [u64] {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+    let mut registry = REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    registry.entry(TypeId::of::<E>()).or_insert_with(|| {
+        let mut hasher = FxHasher::default();
+        std::any::type_name::<E>().hash(&mut hasher);
+        let mut state = hasher.finish();
+        let table: Vec<u64> = (0..E::SIZE).map(|_| splitmix64(&mut state)).collect();
+        Box::leak(table.into_boxed_slice())
+    })
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    mix64(*state)
+}
+
+pub(crate) fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+    enum Color {
+        Red,
+        Green,
+        Blue,
+    }
+
+    impl FiniteDomain for Color {
+        const SIZE: usize = 3;
+
+        fn index(&self) -> usize {
+            *self as usize
+        }
+    }
+
+    #[test]
+    fn registered_table_is_shared() {
+        let a = TableKeys::<Color>::registered();
+        let b = TableKeys::<Color>::registered();
+        assert!(std::ptr::eq(a.table, b.table));
+        assert_eq!(a.key(&Color::Green), b.key(&Color::Green));
+    }
+
+    #[test]
+    fn registered_table_keys_are_distinct() {
+        let keys = TableKeys::<Color>::registered();
+        let red = keys.key(&Color::Red);
+        let green = keys.key(&Color::Green);
+        let blue = keys.key(&Color::Blue);
+        assert_ne!(red, green);
+        assert_ne!(green, blue);
+        assert_ne!(red, blue);
+    }
+
+    #[test]
+    fn bytes_keys_are_pinned() {
+        // Fixed vectors: these values must not change between releases or targets.
+        let expected: [(&[u8], u64); 6] = [
+            (b"", 0x754a_daaf_ba5d_07bf),
+            (b"\0", 0x7b4f_d289_c7f4_e7a6),
+            (b"Ke1", 0xd6e0_7ab8_958a_caa7),
+            (b"12345678", 0x760e_989c_6242_0197),
+            (b"123456789", 0x1c31_e5b3_5ccc_f79a),
+            (&[0xff; 17], 0x43a1_0151_4a34_121e),
+        ];
+        for (bytes, key) in expected {
+            assert_eq!(BytesKeys.key(&bytes), key, "{bytes:?}");
+        }
+        assert_eq!(BytesKeys.key(&b"Ke1".to_vec()), BytesKeys.key(b"Ke1"));
+    }
+
+    #[test]
+    fn split_mix_keys_spread_related_elements() {
+        // Neighbouring integers should differ in roughly half of their bits once mixed.
+        let total: u32 = (0u64..1000)
+            .map(|i| (SplitMixKeys.key(&i) ^ SplitMixKeys.key(&(i + 1))).count_ones())
+            .sum();
+        let average = total as f64 / 1000.0;
+        assert!((28.0..36.0).contains(&average), "average = {average}");
+    }
+}
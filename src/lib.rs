@@ -1,11 +1,235 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
 mod copiable_hash;
 
 #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
 use crate::copiable_hash::CopiableHash;
-use rustc_hash::FxHasher;
+use core::fmt::Debug;
+use core::hash::Hash;
+#[cfg(feature = "std")]
 use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
+
+#[cfg(feature = "std")]
+mod accumulator;
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+mod arbitrary;
+#[cfg(feature = "std")]
+mod atomic;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod bitboard;
+#[cfg(feature = "crypto-hash")]
+mod blake3_keys;
+#[cfg(feature = "std")]
+mod bloom;
+mod canonical;
+#[cfg(feature = "std")]
+mod checked;
+#[cfg(feature = "polyglot")]
+pub mod chess;
+#[cfg(feature = "std")]
+mod collision;
+mod combiner;
+#[cfg(feature = "std")]
+mod cuckoo;
+#[cfg(feature = "std")]
+mod delta;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod fixed;
+#[cfg(feature = "std")]
+mod flags;
+#[cfg(feature = "std")]
+pub mod go;
+#[cfg(feature = "std")]
+mod grid;
+#[cfg(feature = "std")]
+mod guarded;
+#[cfg(feature = "std")]
+mod hyperloglog;
+#[cfg(feature = "std")]
+mod iblt;
+#[cfg(feature = "std")]
+mod key_cache;
+mod keys;
+#[cfg(feature = "secure")]
+mod lthash;
+#[cfg(feature = "std")]
+mod map;
+#[cfg(feature = "std")]
+mod material;
+#[cfg(feature = "std")]
+mod mirrored;
+#[cfg(feature = "secure")]
+mod muhash;
+#[cfg(feature = "std")]
+mod multi_component;
+#[cfg(feature = "std")]
+mod multiset;
+#[cfg(feature = "std")]
+mod nnue;
+#[cfg(feature = "std")]
+mod observed;
+mod ops;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "std")]
+mod path;
+mod persistent;
+#[cfg(feature = "phf")]
+mod phf_keys;
+#[cfg(feature = "std")]
+mod pinsketch;
+mod prehashed;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+mod reduce;
+#[cfg(feature = "std")]
+mod region;
+#[cfg(feature = "std")]
+mod repetition;
+#[cfg(feature = "std")]
+mod scalar_feature;
+#[cfg(feature = "std")]
+mod sequence;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "std")]
+mod simhash;
+#[cfg(feature = "std")]
+mod similarity;
+mod snapshot;
+mod state_diff;
+#[cfg(feature = "std")]
+mod symmetry;
+#[cfg(feature = "metrics")]
+mod telemetry;
+#[cfg(feature = "std")]
+pub mod test_utils;
+#[cfg(feature = "std")]
+mod tracked;
+mod transaction;
+#[cfg(feature = "std")]
+pub mod transposition;
+#[cfg(feature = "std")]
+mod undo;
+mod verify;
+#[cfg(feature = "std")]
+mod visited;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod word_keys;
+
+#[cfg(feature = "std")]
+pub use crate::accumulator::{Accumulator, ScoreAccumulator, ZobristWithAccumulators};
+#[cfg(feature = "proptest")]
+pub use crate::arbitrary::{op_strategy, ops_strategy};
+#[cfg(feature = "std")]
+pub use crate::atomic::AtomicZobristHashSet;
+#[cfg(feature = "crypto-hash")]
+pub use crate::blake3_keys::Blake3Keys;
+#[cfg(feature = "std")]
+pub use crate::bloom::BloomZobristSet;
+pub use crate::canonical::{CanonicalZobristHashSet, KeyAdapter};
+#[cfg(feature = "std")]
+pub use crate::checked::CheckedZobristHashSet;
+#[cfg(feature = "std")]
+pub use crate::collision::CollisionCheckedKeys;
+pub use crate::combiner::{Combiner, GfMul, WrappingAdd, Xor};
+#[cfg(feature = "std")]
+pub use crate::cuckoo::CuckooZobristSet;
+#[cfg(feature = "std")]
+pub use crate::delta::Delta;
+pub use crate::error::{VerifyError, ZobristError};
+pub use crate::fixed::{CapacityError, FixedTrackedZobristHashSet};
+#[cfg(feature = "std")]
+pub use crate::flags::ZobristFlags;
+#[cfg(feature = "std")]
+pub use crate::grid::ZobristGrid;
+#[cfg(feature = "std")]
+pub use crate::guarded::GuardedZobristHashSet;
+#[cfg(feature = "std")]
+pub use crate::hyperloglog::HllZobristSet;
+#[cfg(feature = "std")]
+pub use crate::iblt::{Iblt, ReconcilableZobristSet};
+#[cfg(feature = "std")]
+pub use crate::key_cache::{CachedKeys, KeyCacheStats};
+#[cfg(feature = "std")]
+pub use crate::keys::TableKeys;
+pub use crate::keys::{BytesKeys, FiniteDomain, FxKeys, KeySource, SplitMixKeys};
+#[cfg(feature = "secure")]
+pub use crate::lthash::LtHash;
+#[cfg(feature = "std")]
+pub use crate::map::ZobristHashMap;
+#[cfg(feature = "std")]
+pub use crate::material::MaterialKey;
+#[cfg(feature = "std")]
+pub use crate::mirrored::MirroredZobrist;
+#[cfg(feature = "secure")]
+pub use crate::muhash::MuHash;
+#[cfg(feature = "std")]
+pub use crate::multi_component::MultiComponentZobrist;
+#[cfg(feature = "std")]
+pub use crate::multiset::ZobristHashMultiset;
+#[cfg(feature = "std")]
+pub use crate::nnue::NnueAccumulator;
+#[cfg(feature = "std")]
+pub use crate::observed::{ObservedZobristHashSet, Observer};
+#[cfg(feature = "std")]
+pub use crate::ops::apply_reference_ops;
+pub use crate::ops::Op;
+#[cfg(feature = "rayon")]
+pub use crate::parallel::ParZobristHash;
+#[cfg(feature = "std")]
+pub use crate::path::ZobristPathHash;
+#[cfg(feature = "phf")]
+pub use crate::phf_keys::{PhfKeys, PhfKeysBuilder};
+#[cfg(feature = "std")]
+pub use crate::pinsketch::{PinSketch, SketchedZobristSet};
+pub use crate::prehashed::ElementHash;
+#[cfg(feature = "std")]
+pub use crate::reduce::VerificationBits;
+#[cfg(feature = "std")]
+pub use crate::region::RegionZobrist;
+#[cfg(feature = "std")]
+pub use crate::repetition::RepetitionTracker;
+#[cfg(feature = "std")]
+pub use crate::scalar_feature::ScalarFeature;
+#[cfg(feature = "std")]
+pub use crate::sequence::ZobristSequenceHash;
+#[cfg(feature = "serde")]
+pub use crate::serialize::{SchemeId, HASH_SCHEME_VERSION};
+#[cfg(feature = "std")]
+pub use crate::simhash::SimHashZobristSet;
+#[cfg(feature = "std")]
+pub use crate::similarity::SimilaritySketch;
+pub use crate::snapshot::Snapshot;
+pub use crate::state_diff::StateDiff;
+#[cfg(feature = "std")]
+pub use crate::symmetry::{SymmetricZobrist, Symmetry};
+#[cfg(feature = "std")]
+pub use crate::tracked::TrackedZobristHashSet;
+pub use crate::transaction::UpdateGuard;
+#[cfg(feature = "std")]
+pub use crate::undo::UndoLog;
+#[cfg(feature = "roaring")]
+pub use crate::visited::FoldedRoaring;
+#[cfg(feature = "std")]
+pub use crate::visited::{
+    OpenAddressing, SortedBlocks, VisitedBackend, VisitedSet, VisitedSetBuilder,
+};
+pub use crate::word_keys::WordKeys;
+
+/// The number of elements the debug checker of a [`ZobristHashSet`] tracks unless configured
+/// otherwise.
+pub const DEFAULT_CHECK_CAPACITY: usize = 1024 * 8;
 
 /// Implementation of [Zobrist hashing](https://en.wikipedia.org/wiki/Zobrist_hashing)
 ///
@@ -84,7 +308,7 @@ use std::hash::{Hash, Hasher};
 ///     }
 ///
 ///     pub fn hash(&self) -> u64 {
-///         self.zobrist.into()
+///         self.zobrist.hash()
 ///     }
 /// }
 ///
@@ -104,54 +328,282 @@ use std::hash::{Hash, Hasher};
 /// let hash_after_reset = board.hash();
 /// assert_eq!(initial_hash, hash_after_reset);
 /// ```
-#[derive(Default, Clone, Copy, Debug)]
-pub struct ZobristHashSet<E> {
+///
+/// In debug builds with the `check_set_behavior` feature, the set tracks up to
+/// `CHECK_CAPACITY` elements to catch duplicate additions and unmatched removals, and panics
+/// when more are present. Raise it with [`with_check_capacity`](Self::with_check_capacity) for
+/// large states; it has no effect in other builds. The tracked elements live on the heap, so the
+/// set is only `Copy` when the checker is disabled; use [`hash`](Self::hash) to read the value
+/// without giving up the set.
+///
+/// Adding and removing require elements to implement [`Debug`] in every build, so a failed
+/// check can name the offending element and code that builds in release also builds in debug.
+///
+/// ```rust,compile_fail,E0277
+/// use zobristhash_set::ZobristHashSet;
+///
+/// #[derive(Hash, PartialEq, Eq, Clone)]
+/// struct NoDebug(u8);
+///
+/// ZobristHashSet::empty().add(&NoDebug(0));
+/// ```
+///
+/// With the `rkyv` feature the set can be archived for zero-copy access. The checker is not
+/// archived, so a deserialized set is unchecked, like one created [from a bare hash](From<u64>).
+///
+/// Without the default `std` feature the crate is `no_std`. The set, its key sources and
+/// combiners and [`FixedTrackedZobristHashSet`] only need `core`, the debug checker needs the
+/// `alloc` feature, and the other wrappers, transposition tables and caches need `std`.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    not(all(debug_assertions, feature = "check_set_behavior")),
+    derive(Copy)
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct ZobristHashSet<
+    E,
+    K = FxKeys,
+    C = Xor,
+    const CHECK_CAPACITY: usize = DEFAULT_CHECK_CAPACITY,
+> {
     hash: u64,
-    _data: std::marker::PhantomData<E>,
+    keys: K,
+    _data: core::marker::PhantomData<E>,
+    _combiner: core::marker::PhantomData<C>,
     #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
-    checker: Option<CopiableHash<E>>,
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    checker: Option<CopiableHash<E, CHECK_CAPACITY>>,
+    #[cfg(feature = "count_guard")]
+    len_delta: i64,
 }
 
 impl<E> ZobristHashSet<E> {
-    pub fn empty() -> Self {
+    /// Creates an empty set with the default key source and combiner.
+    ///
+    /// Like the other constructors it is a `const fn`, so sets can be placed in constants and
+    /// statics:
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// static EMPTY_BOARD: ZobristHashSet<(u8, char)> = ZobristHashSet::empty();
+    /// const SEEDED: ZobristHashSet<(u8, char)> = ZobristHashSet::from_raw(0x5eed);
+    ///
+    /// assert_eq!(EMPTY_BOARD.hash(), 0);
+    /// assert_eq!(SEEDED.hash(), 0x5eed);
+    /// ```
+    pub const fn empty() -> Self {
+        Self::with_keys(FxKeys)
+    }
+
+    /// Creates a set holding the hash value `hash`, like [`From<u64>`] but usable in constants.
+    ///
+    /// The elements behind the value are unknown, so the set is unchecked.
+    pub const fn from_raw(hash: u64) -> Self {
+        Self {
+            hash,
+            keys: FxKeys,
+            _data: core::marker::PhantomData,
+            _combiner: core::marker::PhantomData,
+            #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+            checker: None,
+            #[cfg(feature = "count_guard")]
+            len_delta: 0,
+        }
+    }
+}
+
+/// A [`ZobristHashSet`] whose keys come from the process-wide table registered for `E`.
+///
+/// It names the set without spelling out the key source:
+///
+/// ```rust
+/// use zobristhash_set::{FiniteDomain, RegisteredZobristHashSet};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// enum Color {
+///     Red,
+///     Green,
+/// }
+///
+/// impl FiniteDomain for Color {
+///     const SIZE: usize = 2;
+///
+///     fn index(&self) -> usize {
+///         *self as usize
+///     }
+/// }
+///
+/// let mut palette = RegisteredZobristHashSet::<Color>::with_registered_table();
+/// palette.add(&Color::Red);
+/// let mut other = RegisteredZobristHashSet::<Color>::with_registered_table();
+/// other.add(&Color::Green);
+/// other.add(&Color::Red);
+/// other.remove(&Color::Green);
+/// assert_eq!(palette.hash(), other.hash());
+/// ```
+#[cfg(feature = "std")]
+pub type RegisteredZobristHashSet<E> = ZobristHashSet<E, TableKeys<E>>;
+
+#[cfg(feature = "std")]
+impl<E: FiniteDomain + 'static> ZobristHashSet<E, TableKeys<E>> {
+    /// Creates an empty set whose keys come from the process-wide table registered for `E`.
+    ///
+    /// The table is generated the first time it is requested, so every set created this way
+    /// agrees on the keys without having to pass a table around. Name the set type as
+    /// [`RegisteredZobristHashSet<E>`] to pick the element type up front.
+    pub fn with_registered_table() -> Self {
+        Self::with_keys(TableKeys::registered())
+    }
+}
+
+impl<E, K> ZobristHashSet<E, K> {
+    /// Creates an empty set that derives element keys from `keys`.
+    pub const fn with_keys(keys: K) -> Self {
+        Self::with_combiner(keys, Xor)
+    }
+}
+
+impl<E, K, C: Combiner> ZobristHashSet<E, K, C> {
+    /// Creates an empty set that derives element keys from `keys` and folds them with `C`.
+    ///
+    /// With a combiner that is not [self-inverse](Combiner::SELF_INVERSE), the value reflects
+    /// how many times each element was added, and adding an element twice is allowed.
+    ///
+    /// ```rust
+    /// use zobristhash_set::{FxKeys, WrappingAdd, ZobristHashSet};
+    ///
+    /// let mut hash = ZobristHashSet::with_combiner(FxKeys, WrappingAdd);
+    /// hash.add(&42);
+    /// hash.add(&42);
+    /// assert_ne!(u64::from(hash), 0);
+    /// ```
+    pub const fn with_combiner(keys: K, combiner: C) -> Self {
+        Self::with_check_capacity(keys, combiner)
+    }
+}
+
+impl<E, K, C: Combiner, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, C, CHECK_CAPACITY> {
+    /// Creates an empty set like [`with_combiner`](ZobristHashSet::with_combiner) whose debug
+    /// checker tracks up to `CHECK_CAPACITY` elements.
+    ///
+    /// ```rust
+    /// use zobristhash_set::{FxKeys, Xor, ZobristHashSet};
+    ///
+    /// let mut hash = ZobristHashSet::<_, _, _, 20_000>::with_check_capacity(FxKeys, Xor);
+    /// for point in 0..10_000u32 {
+    ///     hash.add(&point);
+    /// }
+    /// ```
+    pub const fn with_check_capacity(keys: K, combiner: C) -> Self {
+        // The combiner only selects the type; forgetting it keeps the function `const`.
+        core::mem::forget(combiner);
         Self {
-            hash: 0,
-            _data: std::marker::PhantomData,
+            hash: C::IDENTITY,
+            keys,
+            _data: core::marker::PhantomData,
+            _combiner: core::marker::PhantomData,
             #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
             checker: Some(CopiableHash::empty()),
+            #[cfg(feature = "count_guard")]
+            len_delta: 0,
         }
     }
 }
 
-impl<E> From<u64> for ZobristHashSet<E> {
+impl<E, K, C, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, C, CHECK_CAPACITY> {
+    /// Returns the hash value, without consuming the set as [`u64::from`] does.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the number of additions minus the number of removals so far.
+    ///
+    /// A negative value means more elements were removed than added, which debug builds assert
+    /// against. Sets created from a bare hash value start at zero.
+    #[cfg(feature = "count_guard")]
+    pub fn len_delta(&self) -> i64 {
+        self.len_delta
+    }
+
+    fn count_updates(&mut self, delta: i64) {
+        #[cfg(feature = "count_guard")]
+        {
+            self.len_delta += delta;
+            debug_assert!(
+                self.len_delta >= 0,
+                "{} more removals than additions",
+                -self.len_delta
+            );
+        }
+        #[cfg(not(feature = "count_guard"))]
+        let _ = delta;
+    }
+}
+
+impl<E, K: Default, C: Combiner, const CHECK_CAPACITY: usize> Default
+    for ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
+    fn default() -> Self {
+        Self::from(C::IDENTITY)
+    }
+}
+
+impl<E, K: Default, C, const CHECK_CAPACITY: usize> From<u64>
+    for ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
     fn from(hash: u64) -> Self {
         Self {
             hash,
-            _data: std::marker::PhantomData,
+            keys: K::default(),
+            _data: core::marker::PhantomData,
+            _combiner: core::marker::PhantomData,
             #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
             checker: None,
+            #[cfg(feature = "count_guard")]
+            len_delta: 0,
         }
     }
 }
 
-impl<E> From<ZobristHashSet<E>> for u64 {
-    fn from(hash: ZobristHashSet<E>) -> u64 {
+impl<E, K, C, const CHECK_CAPACITY: usize> From<ZobristHashSet<E, K, C, CHECK_CAPACITY>> for u64 {
+    fn from(hash: ZobristHashSet<E, K, C, CHECK_CAPACITY>) -> u64 {
         hash.hash
     }
 }
 
 #[cfg(not(all(debug_assertions, feature = "check_set_behavior")))]
-impl<E: Hash + Clone> ZobristHashSet<E> {
-    pub fn add(&mut self, key: &E) {
-        add_remove_impl(self, key);
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>, C: Combiner, const CHECK_CAPACITY: usize>
+    ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
+    /// Adds `key` and returns its 64-bit key, which can be kept to undo the update later.
+    pub fn add(&mut self, key: &E) -> u64 {
+        add_impl(self, key)
+    }
+
+    /// Removes `key` and returns its 64-bit key.
+    pub fn remove(&mut self, key: &E) -> u64 {
+        remove_impl(self, key)
+    }
+
+    /// Adds `key` like [`add`](Self::add), returning an error instead of panicking when the
+    /// checker detects misuse. Without the checker it always succeeds.
+    pub fn try_add(&mut self, key: &E) -> Result<u64, ZobristError> {
+        Ok(self.add(key))
     }
 
-    pub fn remove(&mut self, key: &E) {
-        add_remove_impl(self, key);
+    /// Removes `key` like [`remove`](Self::remove), returning an error instead of panicking when
+    /// the checker detects misuse. Without the checker it always succeeds.
+    pub fn try_remove(&mut self, key: &E) -> Result<u64, ZobristError> {
+        Ok(self.remove(key))
     }
 }
 
-impl<E: Hash + Eq + Clone> From<HashSet<E>> for ZobristHashSet<E> {
+#[cfg(feature = "std")]
+impl<E: Hash + Eq + Clone + Debug> From<HashSet<E>> for ZobristHashSet<E> {
     fn from(set: HashSet<E>) -> Self {
         let mut hash = ZobristHashSet::empty();
         for key in set {
@@ -162,26 +614,101 @@ impl<E: Hash + Eq + Clone> From<HashSet<E>> for ZobristHashSet<E> {
 }
 
 #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
-impl<E: Hash + Eq + Clone> ZobristHashSet<E> {
-    pub fn add(&mut self, key: &E) {
-        assert!(self
-            .checker
-            .as_mut()
-            .map(|x| x.insert(key.clone()))
-            .unwrap_or(true));
-        add_remove_impl(self, key);
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>, C: Combiner, const CHECK_CAPACITY: usize>
+    ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
+    /// Adds `key` and returns its 64-bit key, which can be kept to undo the update later.
+    pub fn add(&mut self, key: &E) -> u64 {
+        if let Some(checker) = self.checker.as_mut() {
+            if C::SELF_INVERSE {
+                assert!(
+                    checker.insert(key.clone()),
+                    "duplicate add of {key:?}: the element is already among the {} tracked elements\n{}",
+                    checker.len(),
+                    checker.journal()
+                );
+            } else {
+                checker.insert_repeated(key.clone());
+            }
+        }
+        let element_key = add_impl(self, key);
+        if let Some(checker) = self.checker.as_mut() {
+            checker.journal_mut().record("add", key, self.hash);
+        }
+        element_key
     }
 
-    pub fn remove(&mut self, key: &E) {
-        assert!(self.checker.as_mut().map(|x| x.remove(key)).unwrap_or(true));
-        add_remove_impl(self, key);
+    /// Removes `key` and returns its 64-bit key.
+    pub fn remove(&mut self, key: &E) -> u64 {
+        if let Some(checker) = self.checker.as_mut() {
+            assert!(
+                checker.remove(key),
+                "missing remove of {key:?}: the element is not among the {} tracked elements\n{}",
+                checker.len(),
+                checker.journal()
+            );
+        }
+        let element_key = remove_impl(self, key);
+        if let Some(checker) = self.checker.as_mut() {
+            checker.journal_mut().record("remove", key, self.hash);
+        }
+        element_key
+    }
+
+    /// Adds `key` like [`add`](Self::add), returning an error instead of panicking when the
+    /// checker detects misuse. The set is left unchanged on error.
+    pub fn try_add(&mut self, key: &E) -> Result<u64, ZobristError> {
+        if let Some(checker) = &self.checker {
+            if C::SELF_INVERSE && checker.contains(key) {
+                return Err(ZobristError::DuplicateAdd.recorded());
+            }
+            if checker.is_full() {
+                return Err(ZobristError::CheckerCapacity.recorded());
+            }
+        }
+        Ok(self.add(key))
+    }
+
+    /// Removes `key` like [`remove`](Self::remove), returning an error instead of panicking when
+    /// the checker detects misuse. The set is left unchanged on error.
+    pub fn try_remove(&mut self, key: &E) -> Result<u64, ZobristError> {
+        if self
+            .checker
+            .as_ref()
+            .is_some_and(|checker| !checker.contains(key))
+        {
+            return Err(ZobristError::MissingRemove.recorded());
+        }
+        Ok(self.remove(key))
     }
 }
 
-fn add_remove_impl<E: Hash>(zobrist_hash: &mut ZobristHashSet<E>, key: &E) {
-    let mut hasher = FxHasher::default();
-    key.hash(&mut hasher);
-    zobrist_hash.hash ^= hasher.finish();
+fn add_impl<E, K: KeySource<E>, C: Combiner, const CHECK_CAPACITY: usize>(
+    zobrist_hash: &mut ZobristHashSet<E, K, C, CHECK_CAPACITY>,
+    key: &E,
+) -> u64 {
+    let element_key = zobrist_hash.keys.key(key);
+    zobrist_hash.hash = C::combine(zobrist_hash.hash, element_key);
+    zobrist_hash.count_updates(1);
+    #[cfg(feature = "tracing")]
+    tracing::trace!(op = "add", key = element_key, hash = zobrist_hash.hash);
+    #[cfg(feature = "metrics")]
+    crate::telemetry::adds().increment(1);
+    element_key
+}
+
+fn remove_impl<E, K: KeySource<E>, C: Combiner, const CHECK_CAPACITY: usize>(
+    zobrist_hash: &mut ZobristHashSet<E, K, C, CHECK_CAPACITY>,
+    key: &E,
+) -> u64 {
+    let element_key = zobrist_hash.keys.key(key);
+    zobrist_hash.hash = C::uncombine(zobrist_hash.hash, element_key);
+    zobrist_hash.count_updates(-1);
+    #[cfg(feature = "tracing")]
+    tracing::trace!(op = "remove", key = element_key, hash = zobrist_hash.hash);
+    #[cfg(feature = "metrics")]
+    crate::telemetry::removes().increment(1);
+    element_key
 }
 
 #[cfg(test)]
@@ -222,7 +749,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "duplicate add of 42: the element is already among the 1 tracked")]
     #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
     fn test_zobrist_hash_double_add_debug() {
         let mut hash = ZobristHashSet::empty();
@@ -232,7 +759,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "missing remove of 42: the element is not among the 0 tracked")]
     #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
     fn test_zobrist_hash_empty_remove_debug() {
         let mut hash = ZobristHashSet::empty();
@@ -240,6 +767,46 @@ mod tests {
         hash.remove(&key);
     }
 
+    #[test]
+    #[should_panic(expected = "last 2 operations, oldest first:\n  add 1 -> ")]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn test_journal_in_panic_debug() {
+        let mut hash = ZobristHashSet::empty();
+        hash.add(&1);
+        hash.add(&2);
+        hash.remove(&3);
+    }
+
+    #[test]
+    fn test_try_add_and_remove() {
+        let mut hash = ZobristHashSet::empty();
+        assert_eq!(hash.try_add(&42), Ok(FxKeys.key(&42)));
+        assert_eq!(hash.try_remove(&42), Ok(FxKeys.key(&42)));
+        assert_eq!(hash.hash, 0);
+    }
+
+    #[test]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn test_try_add_and_remove_report_misuse_debug() {
+        let mut hash = ZobristHashSet::<_, _, _, 2>::with_check_capacity(FxKeys, Xor);
+        hash.add(&1);
+        assert_eq!(hash.try_add(&1), Err(ZobristError::DuplicateAdd));
+        assert_eq!(hash.try_remove(&2), Err(ZobristError::MissingRemove));
+        hash.add(&2);
+        assert_eq!(hash.try_add(&3), Err(ZobristError::CheckerCapacity));
+        assert_eq!(hash.hash, FxKeys.key(&1) ^ FxKeys.key(&2));
+    }
+
+    #[test]
+    fn test_add_returns_element_key() {
+        let mut hash = ZobristHashSet::empty();
+        let key = hash.add(&42);
+        assert_eq!(key, FxKeys.key(&42));
+        assert_eq!(hash.hash, key);
+        assert_eq!(hash.remove(&42), key);
+        assert_eq!(hash.hash, 0);
+    }
+
     #[test]
     fn test_from_hashset() {
         let mut set = HashSet::new();
@@ -260,4 +827,155 @@ mod tests {
         set3.remove(&435);
         assert_eq!(hash.hash, set3.hash);
     }
+
+    #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+    enum Piece {
+        Pawn,
+        Knight,
+        King,
+    }
+
+    impl FiniteDomain for Piece {
+        const SIZE: usize = 3;
+
+        fn index(&self) -> usize {
+            *self as usize
+        }
+    }
+
+    #[test]
+    fn test_registered_table() {
+        let mut hash1 = ZobristHashSet::with_registered_table();
+        hash1.add(&Piece::Pawn);
+        hash1.add(&Piece::King);
+        assert_ne!(hash1.hash, 0);
+
+        let mut hash2 = RegisteredZobristHashSet::<Piece>::with_registered_table();
+        hash2.add(&Piece::King);
+        hash2.add(&Piece::Knight);
+        hash2.add(&Piece::Pawn);
+        hash2.remove(&Piece::Knight);
+        assert_eq!(hash1.hash, hash2.hash);
+    }
+
+    fn combined<C: Combiner + Default>(elements: &[u32]) -> u64 {
+        let mut hash = ZobristHashSet::with_combiner(FxKeys, C::default());
+        for e in elements {
+            hash.add(e);
+        }
+        hash.hash
+    }
+
+    fn check_multiset_combiner<C: Combiner + Default>() {
+        assert_eq!(combined::<C>(&[]), C::IDENTITY);
+        assert_eq!(combined::<C>(&[1, 2, 2]), combined::<C>(&[2, 1, 2]));
+        assert_ne!(combined::<C>(&[1, 2, 2]), combined::<C>(&[1, 2]));
+        assert_ne!(combined::<C>(&[1, 1]), combined::<C>(&[]));
+
+        let mut hash = ZobristHashSet::with_combiner(FxKeys, C::default());
+        hash.add(&5u32);
+        hash.add(&6);
+        hash.remove(&5);
+        hash.remove(&6);
+        assert_eq!(hash.hash, C::IDENTITY);
+    }
+
+    #[test]
+    fn test_multiset_combiners() {
+        check_multiset_combiner::<WrappingAdd>();
+        check_multiset_combiner::<GfMul>();
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn test_multiset_combiner_missing_remove_debug() {
+        let mut hash = ZobristHashSet::with_combiner(FxKeys, WrappingAdd);
+        hash.add(&1);
+        hash.remove(&1);
+        hash.remove(&1);
+    }
+
+    #[test]
+    fn test_check_capacity() {
+        let mut hash = ZobristHashSet::<_, _, _, 16>::with_check_capacity(FxKeys, Xor);
+        for i in 0..16u32 {
+            hash.add(&i);
+        }
+        hash.remove(&3);
+        hash.add(&16);
+
+        let mut expected = ZobristHashSet::empty();
+        for i in (0..17u32).filter(|&i| i != 3) {
+            expected.add(&i);
+        }
+        assert_eq!(hash.hash, expected.hash);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn test_check_capacity_exceeded_debug() {
+        let mut hash = ZobristHashSet::<_, _, _, 4>::with_check_capacity(FxKeys, Xor);
+        for i in 0..5u32 {
+            hash.add(&i);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "count_guard")]
+    fn test_len_delta() {
+        let mut hash = ZobristHashSet::empty();
+        hash.add(&1);
+        hash.add(&2);
+        hash.remove(&1);
+        assert_eq!(hash.len_delta(), 1);
+        hash.add_prehashed(ZobristHashSet::element_hash(&3));
+        assert_eq!(hash.len_delta(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 more removals than additions")]
+    #[cfg(all(debug_assertions, feature = "count_guard"))]
+    fn test_len_delta_negative_debug() {
+        let mut hash = ZobristHashSet::<u32>::from(0);
+        hash.remove(&1);
+    }
+
+    #[test]
+    fn test_split_mix_keys() {
+        let mut hash = ZobristHashSet::with_keys(SplitMixKeys);
+        hash.add(&(1, 42));
+        hash.add(&(2, 42));
+        assert_ne!(hash.hash, 0);
+        hash.remove(&(1, 42));
+        hash.remove(&(2, 42));
+        assert_eq!(hash.hash, 0);
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn archived_set_keeps_the_hash() {
+        let mut set = ZobristHashSet::<u32>::empty();
+        set.add(&7);
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&set).unwrap();
+        let archived =
+            rkyv::access::<ArchivedZobristHashSet<u32>, rkyv::rancor::Error>(&bytes).unwrap();
+        assert_eq!(archived.hash.to_native(), set.hash());
+        let restored: ZobristHashSet<u32> =
+            rkyv::deserialize::<_, rkyv::rancor::Error>(archived).unwrap();
+        assert_eq!(restored.hash(), set.hash());
+    }
+
+    #[test]
+    fn const_sets_start_usable() {
+        const START: ZobristHashSet<u32> = ZobristHashSet::empty();
+        let mut set = START;
+        set.add(&3);
+        set.remove(&3);
+        assert_eq!(set.hash(), START.hash());
+
+        const RAW: ZobristHashSet<u32> = ZobristHashSet::from_raw(0x5eed);
+        assert_eq!(RAW.hash(), ZobristHashSet::<u32>::from(0x5eed).hash());
+    }
 }
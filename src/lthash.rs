@@ -0,0 +1,143 @@
+use crate::muhash::expand;
+use sha2::{Digest, Sha256};
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+/// The number of 16-bit lanes, giving a 2048-byte state.
+const LANES: usize = 1024;
+
+/// A lattice-based homomorphic set hash, after LtHash.
+///
+/// Every element is expanded with SHA-256 into 1024 16-bit lanes, which are added to the state
+/// with wrapping arithmetic. Updates are as cheap as a vector addition, partial states of
+/// disjoint subsets can be [combined](Self::combine) in any order, and finding a collision
+/// requires solving a lattice problem rather than a 64-bit search. Like [`MuHash`](crate::MuHash),
+/// the hash reflects multiplicities and shares its caveat about platform-dependent [`Hash`]
+/// implementations.
+///
+/// ```rust
+/// use zobristhash_set::LtHash;
+///
+/// let mut left = LtHash::new();
+/// left.add(&"alice");
+/// let mut right = LtHash::new();
+/// right.add(&"bob");
+/// right.add(&"carol");
+/// right.remove(&"carol");
+///
+/// let mut whole = LtHash::new();
+/// whole.add(&"bob");
+/// whole.add(&"alice");
+///
+/// left.combine(&right);
+/// assert_eq!(left, whole);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LtHash<E> {
+    lanes: Box<[u16]>,
+    _marker: PhantomData<fn(&E)>,
+}
+
+impl<E> LtHash<E> {
+    pub fn new() -> Self {
+        Self {
+            lanes: vec![0; LANES].into_boxed_slice(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds all elements of `other` to `self`.
+    pub fn combine(&mut self, other: &Self) {
+        for (lane, &other) in self.lanes.iter_mut().zip(other.lanes.iter()) {
+            *lane = lane.wrapping_add(other);
+        }
+    }
+
+    /// Removes all elements of `other` from `self`.
+    pub fn subtract(&mut self, other: &Self) {
+        for (lane, &other) in self.lanes.iter_mut().zip(other.lanes.iter()) {
+            *lane = lane.wrapping_sub(other);
+        }
+    }
+
+    /// Returns the full 2048-byte state, lanes in little-endian order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.lanes
+            .iter()
+            .flat_map(|lane| lane.to_le_bytes())
+            .collect()
+    }
+
+    /// Returns the SHA-256 hash of the state.
+    pub fn digest(&self) -> [u8; 32] {
+        Sha256::digest(self.to_bytes()).into()
+    }
+}
+
+impl<E> Default for LtHash<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> PartialEq for LtHash<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.lanes == other.lanes
+    }
+}
+
+impl<E> Eq for LtHash<E> {}
+
+impl<E: Hash> LtHash<E> {
+    pub fn add(&mut self, key: &E) {
+        for (lane, bytes) in self
+            .lanes
+            .iter_mut()
+            .zip(expand(key, 2 * LANES).chunks_exact(2))
+        {
+            *lane = lane.wrapping_add(u16::from_le_bytes([bytes[0], bytes[1]]));
+        }
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        for (lane, bytes) in self
+            .lanes
+            .iter_mut()
+            .zip(expand(key, 2 * LANES).chunks_exact(2))
+        {
+            *lane = lane.wrapping_sub(u16::from_le_bytes([bytes[0], bytes[1]]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removal_cancels_addition() {
+        let mut set = LtHash::new();
+        for i in 0..100u32 {
+            set.add(&i);
+        }
+        let mut partial = LtHash::new();
+        for i in 50..100u32 {
+            partial.add(&i);
+        }
+        set.subtract(&partial);
+        for i in 0..50u32 {
+            set.remove(&i);
+        }
+        assert_eq!(set, LtHash::new());
+        assert_eq!(set.to_bytes(), vec![0; 2048]);
+    }
+
+    #[test]
+    fn multiplicities_change_the_digest() {
+        let mut once = LtHash::new();
+        once.add(&7u8);
+        let mut twice = once.clone();
+        twice.add(&7u8);
+        assert_ne!(once.digest(), twice.digest());
+    }
+}
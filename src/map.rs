@@ -0,0 +1,156 @@
+use crate::{FxKeys, KeySource};
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+use rustc_hash::FxHashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// An incremental hash of a key-value mapping.
+///
+/// The value is the Zobrist hash of the set of `(key, value)` pairs. Callers state the value a key
+/// currently maps to when removing or updating it; in checked builds the mapping is tracked and a
+/// wrong claim panics.
+///
+/// ```rust
+/// use zobristhash_set::ZobristHashMap;
+///
+/// let mut config = ZobristHashMap::empty();
+/// config.insert(&"depth", &8);
+/// config.update(&"depth", &8, &12);
+///
+/// let mut expected = ZobristHashMap::empty();
+/// expected.insert(&"depth", &12);
+/// assert_eq!(u64::from(config), u64::from(expected));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    not(all(debug_assertions, feature = "check_set_behavior")),
+    derive(Copy)
+)]
+pub struct ZobristHashMap<K, V, S = FxKeys> {
+    hash: u64,
+    keys: S,
+    _data: std::marker::PhantomData<(K, V)>,
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    checker: FxHashMap<K, V>,
+}
+
+impl<K, V> ZobristHashMap<K, V> {
+    pub fn empty() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<K, V> Default for ZobristHashMap<K, V> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<K, V, S> ZobristHashMap<K, V, S> {
+    /// Creates an empty map that derives pair keys from `keys`.
+    pub fn with_keys(keys: S) -> Self {
+        Self {
+            hash: 0,
+            keys,
+            _data: std::marker::PhantomData,
+            #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+            checker: FxHashMap::default(),
+        }
+    }
+}
+
+impl<K, V, S> From<ZobristHashMap<K, V, S>> for u64 {
+    fn from(hash: ZobristHashMap<K, V, S>) -> u64 {
+        hash.hash
+    }
+}
+
+impl<K, V, S> ZobristHashMap<K, V, S>
+where
+    K: Hash + Eq + Clone + Debug,
+    V: Hash + Eq + Clone + Debug,
+    S: KeySource<(K, V)>,
+{
+    /// Adds the mapping `key -> value`. `key` must not be mapped yet.
+    pub fn insert(&mut self, key: &K, value: &V) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        {
+            if let Some(current) = self.checker.get(key) {
+                panic!(
+                    "duplicate add of {key:?}: the key is already mapped to {current:?} among \
+                     the {} tracked mappings",
+                    self.checker.len()
+                );
+            }
+            self.checker.insert(key.clone(), value.clone());
+        }
+        self.toggle(key, value);
+    }
+
+    /// Removes the mapping `key -> old_value`.
+    pub fn remove(&mut self, key: &K, old_value: &V) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        {
+            let current = self.checker.get(key);
+            assert!(
+                current == Some(old_value),
+                "missing remove of {key:?} -> {old_value:?}: the key is mapped to {current:?} \
+                 among the {} tracked mappings",
+                self.checker.len()
+            );
+            self.checker.remove(key);
+        }
+        self.toggle(key, old_value);
+    }
+
+    /// Changes the mapping of `key` from `old_value` to `new_value`.
+    pub fn update(&mut self, key: &K, old_value: &V, new_value: &V) {
+        self.remove(key, old_value);
+        self.insert(key, new_value);
+    }
+
+    fn toggle(&mut self, key: &K, value: &V) {
+        self.hash ^= self.keys.key(&(key.clone(), value.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZobristHashSet;
+
+    #[test]
+    fn matches_set_of_pairs() {
+        let mut map = ZobristHashMap::empty();
+        map.insert(&1u8, &'a');
+        map.insert(&2u8, &'b');
+        map.update(&1, &'a', &'c');
+
+        let mut pairs = ZobristHashSet::empty();
+        pairs.add(&(1u8, 'c'));
+        pairs.add(&(2u8, 'b'));
+        assert_eq!(map.hash, pairs.hash);
+
+        map.remove(&1, &'c');
+        map.remove(&2, &'b');
+        assert_eq!(map.hash, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing remove of 1 -> 'b': the key is mapped to Some('a')")]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn wrong_old_value_debug() {
+        let mut map = ZobristHashMap::empty();
+        map.insert(&1u8, &'a');
+        map.update(&1, &'b', &'c');
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate add of 1: the key is already mapped to 'a' among the 1")]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn double_insert_debug() {
+        let mut map = ZobristHashMap::empty();
+        map.insert(&1u8, &'a');
+        map.insert(&1u8, &'b');
+    }
+}
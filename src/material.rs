@@ -0,0 +1,133 @@
+use crate::{FxKeys, KeySource};
+use rustc_hash::FxHashMap;
+use std::hash::Hash;
+
+/// An order-insensitive hash of a multiset of piece types, for material signatures.
+///
+/// Squares play no part: only how many of each piece type are present. Having `n` pieces of type
+/// `p` contributes the keys of `(p, 0)` through `(p, n - 1)`, so adding or removing one piece is a
+/// single XOR while counts are still fully reflected. Typical uses are endgame tablebase probing
+/// and material-draw detection, updated on captures and promotions.
+///
+/// ```rust
+/// use zobristhash_set::MaterialKey;
+///
+/// let mut kqk = MaterialKey::new();
+/// for piece in ['K', 'Q', 'k'] {
+///     kqk.add(&piece);
+/// }
+///
+/// // A pawn promotes to a queen.
+/// let mut promoted = MaterialKey::new();
+/// for piece in ['K', 'P', 'k'] {
+///     promoted.add(&piece);
+/// }
+/// promoted.remove(&'P');
+/// promoted.add(&'Q');
+///
+/// assert_eq!(kqk.key(), promoted.key());
+/// assert_eq!(promoted.count(&'Q'), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MaterialKey<E, K = FxKeys> {
+    key: u64,
+    counts: FxHashMap<E, u32>,
+    keys: K,
+}
+
+impl<E> MaterialKey<E> {
+    /// Creates the key of an empty board.
+    pub fn new() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E> Default for MaterialKey<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, K> MaterialKey<E, K> {
+    /// Creates the key of an empty board, deriving `(piece, count)` keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        Self {
+            key: 0,
+            counts: FxHashMap::default(),
+            keys,
+        }
+    }
+
+    /// Returns the material key.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+}
+
+impl<E: Hash + Eq + Clone, K: KeySource<(E, u32)>> MaterialKey<E, K> {
+    /// Returns the number of pieces of type `piece`.
+    pub fn count(&self, piece: &E) -> u32 {
+        self.counts.get(piece).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, piece: &E) {
+        let count = self.counts.entry(piece.clone()).or_insert(0);
+        self.key ^= self.keys.key(&(piece.clone(), *count));
+        *count += 1;
+    }
+
+    /// # Panics
+    ///
+    /// Panics if there is no piece of type `piece`.
+    pub fn remove(&mut self, piece: &E) {
+        let count = self
+            .counts
+            .get_mut(piece)
+            .filter(|count| **count > 0)
+            .expect("cannot remove a piece type that is not present");
+        *count -= 1;
+        self.key ^= self.keys.key(&(piece.clone(), *count));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_matter_squares_do_not() {
+        let mut one_rook = MaterialKey::new();
+        one_rook.add(&'R');
+        let mut two_rooks = one_rook.clone();
+        two_rooks.add(&'R');
+        assert_ne!(one_rook.key(), two_rooks.key());
+        assert_ne!(two_rooks.key(), 0);
+
+        two_rooks.remove(&'R');
+        assert_eq!(one_rook.key(), two_rooks.key());
+        two_rooks.remove(&'R');
+        assert_eq!(two_rooks.key(), 0);
+    }
+
+    #[test]
+    fn order_insensitive() {
+        let mut a = MaterialKey::new();
+        let mut b = MaterialKey::new();
+        for piece in [1u8, 2, 1, 3] {
+            a.add(&piece);
+        }
+        for piece in [3u8, 1, 1, 2] {
+            b.add(&piece);
+        }
+        assert_eq!(a.key(), b.key());
+        assert_eq!(a.count(&1), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_missing_piece() {
+        let mut key = MaterialKey::new();
+        key.add(&'N');
+        key.remove(&'B');
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash paired with the hash of its colour-flipped twin.
+///
+/// Every added or removed element is also mapped through a user-supplied `mirror` function
+/// (typically swapping the colour or player of a piece) and folded into a second hash, so a
+/// position and its colour-mirrored counterpart can be looked up without rebuilding either.
+///
+/// ```rust
+/// use zobristhash_set::MirroredZobrist;
+///
+/// #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+/// enum Player {
+///     First,
+///     Second,
+/// }
+///
+/// fn flip(&(player, cell): &(Player, u8)) -> (Player, u8) {
+///     match player {
+///         Player::First => (Player::Second, cell),
+///         Player::Second => (Player::First, cell),
+///     }
+/// }
+///
+/// let mut position = MirroredZobrist::new(flip);
+/// position.add(&(Player::First, 4));
+///
+/// let mut twin = MirroredZobrist::new(flip);
+/// twin.add(&(Player::Second, 4));
+///
+/// assert_eq!(position.mirrored_hash(), twin.hash());
+/// assert_eq!(twin.mirrored_hash(), position.hash());
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    not(all(debug_assertions, feature = "check_set_behavior")),
+    derive(Copy)
+)]
+pub struct MirroredZobrist<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    mirrored: u64,
+    mirror: fn(&E) -> E,
+}
+
+impl<E> MirroredZobrist<E> {
+    /// Creates an empty hash that mirrors elements with `mirror`.
+    pub fn new(mirror: fn(&E) -> E) -> Self {
+        Self::with_keys(FxKeys, mirror)
+    }
+}
+
+impl<E, K> MirroredZobrist<E, K> {
+    /// Creates an empty hash that mirrors elements with `mirror` and derives keys from `keys`.
+    pub fn with_keys(keys: K, mirror: fn(&E) -> E) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            mirrored: 0,
+            mirror,
+        }
+    }
+
+    /// Returns the hash of the elements as added.
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the hash of the mirrored elements.
+    pub fn mirrored_hash(&self) -> u64 {
+        self.mirrored
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> MirroredZobrist<E, K> {
+    pub fn add(&mut self, key: &E) {
+        self.set.add(key);
+        self.mirrored ^= self.set.keys.key(&(self.mirror)(key));
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        self.set.remove(key);
+        self.mirrored ^= self.set.keys.key(&(self.mirror)(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flip(&(color, square): &(bool, u8)) -> (bool, u8) {
+        (!color, 63 - square)
+    }
+
+    #[test]
+    fn mirrored_hash_tracks_mirrored_elements() {
+        let elements = [(true, 0), (false, 12), (true, 40)];
+        let mut hash = MirroredZobrist::new(flip);
+        let mut expected = ZobristHashSet::empty();
+        for element in &elements {
+            hash.add(element);
+            expected.add(&flip(element));
+        }
+        assert_eq!(hash.mirrored_hash(), expected.hash);
+
+        hash.remove(&elements[1]);
+        expected.remove(&flip(&elements[1]));
+        assert_eq!(hash.mirrored_hash(), expected.hash);
+    }
+
+    #[test]
+    fn symmetric_position_is_its_own_mirror() {
+        let mut hash = MirroredZobrist::new(flip);
+        hash.add(&(true, 3));
+        hash.add(&(false, 60));
+        assert_eq!(hash.hash(), hash.mirrored_hash());
+    }
+}
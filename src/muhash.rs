@@ -0,0 +1,168 @@
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+/// The size in bytes of the group elements.
+const BYTES: usize = 384;
+
+/// A multiplicative set hash resistant to deliberately constructed collisions, after MuHash.
+///
+/// Zobrist hashes are linear, so anyone who knows the keys can find a set with any desired
+/// value. `MuHash` instead maps every element to a 3072-bit number modulo the prime
+/// 2<sup>3072</sup> - 1103717 using SHA-256, and multiplies these numbers; finding two sets with
+/// the same product is believed to be as hard as a discrete logarithm. Removals multiply a
+/// separate denominator, so neither updates nor [comparisons](PartialEq) need a modular
+/// inverse; only [`digest`](Self::digest) computes one.
+///
+/// Like [`WrappingAdd`](crate::WrappingAdd), the hash reflects multiplicities. Elements are
+/// serialized through their [`Hash`] implementation, which for integers and `usize` lengths
+/// depends on the platform's endianness and pointer width; use identical platforms or byte
+/// strings of fixed size when digests are compared between machines.
+///
+/// ```rust
+/// use zobristhash_set::MuHash;
+///
+/// let mut a = MuHash::new();
+/// a.add(&"alice");
+/// a.add(&"bob");
+///
+/// let mut b = MuHash::new();
+/// b.add(&"bob");
+/// b.add(&"carol");
+/// b.add(&"alice");
+/// b.remove(&"carol");
+///
+/// assert_eq!(a, b);
+/// assert_eq!(a.digest(), b.digest());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MuHash<E> {
+    numerator: BigUint,
+    denominator: BigUint,
+    _marker: PhantomData<fn(&E)>,
+}
+
+impl<E> MuHash<E> {
+    pub fn new() -> Self {
+        Self {
+            numerator: BigUint::from(1u32),
+            denominator: BigUint::from(1u32),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds all elements of `other` to `self`.
+    pub fn combine(&mut self, other: &Self) {
+        self.numerator = &self.numerator * &other.numerator % modulus();
+        self.denominator = &self.denominator * &other.denominator % modulus();
+    }
+
+    /// Returns the SHA-256 hash of the set's group element.
+    pub fn digest(&self) -> [u8; 32] {
+        let p = modulus();
+        let inverse = self.denominator.modpow(&(p - 2u32), p);
+        let mut bytes = (&self.numerator * inverse % p).to_bytes_le();
+        bytes.resize(BYTES, 0);
+        Sha256::digest(&bytes).into()
+    }
+}
+
+impl<E> Default for MuHash<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> PartialEq for MuHash<E> {
+    fn eq(&self, other: &Self) -> bool {
+        let p = modulus();
+        &self.numerator * &other.denominator % p == &other.numerator * &self.denominator % p
+    }
+}
+
+impl<E> Eq for MuHash<E> {}
+
+impl<E: Hash> MuHash<E> {
+    pub fn add(&mut self, key: &E) {
+        self.numerator = &self.numerator * element(key) % modulus();
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        self.denominator = &self.denominator * element(key) % modulus();
+    }
+}
+
+fn modulus() -> &'static BigUint {
+    static MODULUS: OnceLock<BigUint> = OnceLock::new();
+    MODULUS.get_or_init(|| (BigUint::from(1u32) << 3072) - 1_103_717u32)
+}
+
+/// Maps `key` to a group element.
+fn element<E: Hash>(key: &E) -> BigUint {
+    BigUint::from_bytes_le(&expand(key, BYTES)) % modulus()
+}
+
+/// Expands the SHA-256 hash of `key` to `len` pseudorandom bytes in counter mode.
+pub(crate) fn expand<E: Hash>(key: &E, len: usize) -> Vec<u8> {
+    let mut hasher = Sha256Hasher(Sha256::new());
+    key.hash(&mut hasher);
+    let seed = hasher.0.finalize();
+    let mut bytes = Vec::with_capacity(len.next_multiple_of(32));
+    for block in 0..len.div_ceil(32) as u32 {
+        let digest = Sha256::new()
+            .chain_update(seed)
+            .chain_update(block.to_le_bytes())
+            .finalize();
+        bytes.extend_from_slice(&digest);
+    }
+    bytes.truncate(len);
+    bytes
+}
+
+/// Feeds the bytes written by a [`Hash`] implementation into SHA-256.
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unreachable!("the digest is read with `finalize`")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removal_cancels_addition() {
+        let mut set = MuHash::new();
+        for i in 0..20u32 {
+            set.add(&i);
+        }
+        for i in (0..20u32).rev() {
+            set.remove(&i);
+        }
+        assert_eq!(set, MuHash::new());
+        assert_eq!(set.digest(), MuHash::<u32>::new().digest());
+    }
+
+    #[test]
+    fn multiplicities_and_combination() {
+        let mut once = MuHash::new();
+        once.add(&1u64);
+        let mut twice = once.clone();
+        twice.add(&1u64);
+        assert_ne!(once, twice);
+        assert_ne!(once.digest(), twice.digest());
+
+        let mut other = MuHash::new();
+        other.add(&1u64);
+        once.combine(&other);
+        assert_eq!(once, twice);
+    }
+}
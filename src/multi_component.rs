@@ -0,0 +1,125 @@
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash split into `N` named sub-hashes, such as a pawn hash and a material hash.
+///
+/// A user-supplied classifier decides which components each element belongs to. A single
+/// [`MultiComponentZobrist::add`] or [`MultiComponentZobrist::remove`] updates the full hash and
+/// every component the element is routed to.
+///
+/// ```rust
+/// use zobristhash_set::MultiComponentZobrist;
+///
+/// // (piece, square), where piece 'P' and 'p' are pawns.
+/// fn classify(&(piece, _): &(char, u8)) -> [bool; 1] {
+///     [piece.eq_ignore_ascii_case(&'p')]
+/// }
+///
+/// let mut hash = MultiComponentZobrist::new(["pawns"], classify);
+/// hash.add(&('P', 12));
+/// let pawns = hash.component("pawns").unwrap();
+///
+/// hash.add(&('N', 6));
+/// assert_eq!(hash.component("pawns"), Some(pawns));
+/// assert_ne!(hash.hash(), pawns);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    not(all(debug_assertions, feature = "check_set_behavior")),
+    derive(Copy)
+)]
+pub struct MultiComponentZobrist<E, const N: usize, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    names: [&'static str; N],
+    components: [u64; N],
+    classify: fn(&E) -> [bool; N],
+}
+
+impl<E, const N: usize> MultiComponentZobrist<E, N> {
+    /// Creates an empty hash with components called `names`, routing elements with `classify`.
+    pub fn new(names: [&'static str; N], classify: fn(&E) -> [bool; N]) -> Self {
+        Self::with_keys(FxKeys, names, classify)
+    }
+}
+
+impl<E, const N: usize, K> MultiComponentZobrist<E, N, K> {
+    /// Creates an empty hash like [`MultiComponentZobrist::new`] that derives keys from `keys`.
+    pub fn with_keys(keys: K, names: [&'static str; N], classify: fn(&E) -> [bool; N]) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            names,
+            components: [0; N],
+            classify,
+        }
+    }
+
+    /// Returns the hash of all elements.
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the component called `name`, or `None` if there is no such component.
+    pub fn component(&self, name: &str) -> Option<u64> {
+        let index = self.names.iter().position(|&n| n == name)?;
+        Some(self.components[index])
+    }
+
+    /// Returns the component at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    pub fn component_at(&self, index: usize) -> u64 {
+        self.components[index]
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, const N: usize, K: KeySource<E>> MultiComponentZobrist<E, N, K> {
+    pub fn add(&mut self, key: &E) {
+        self.set.add(key);
+        self.toggle_components(key);
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        self.set.remove(key);
+        self.toggle_components(key);
+    }
+
+    fn toggle_components(&mut self, key: &E) {
+        let element_key = self.set.keys.key(key);
+        for (component, routed) in self.components.iter_mut().zip((self.classify)(key)) {
+            if routed {
+                *component ^= element_key;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(&(kind, _): &(u8, u8)) -> [bool; 2] {
+        [kind == 0, kind <= 1]
+    }
+
+    #[test]
+    fn components_only_see_routed_elements() {
+        let mut hash = MultiComponentZobrist::new(["zero", "small"], classify);
+        let elements = [(0, 1), (1, 2), (2, 3), (0, 4)];
+        for element in &elements {
+            hash.add(element);
+        }
+        hash.remove(&(0, 4));
+
+        let zero = ZobristHashSet::rebuild_from(&[(0u8, 1u8)]);
+        let small = ZobristHashSet::rebuild_from(&[(0u8, 1u8), (1, 2)]);
+        let all = ZobristHashSet::rebuild_from(&[(0u8, 1u8), (1, 2), (2, 3)]);
+
+        assert_eq!(hash.component("zero"), Some(zero.hash));
+        assert_eq!(hash.component_at(1), small.hash);
+        assert_eq!(hash.hash(), all.hash);
+        assert_eq!(hash.component("missing"), None);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{Combiner, FxKeys, KeySource, WrappingAdd, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// An incremental hash of a multiset, where adding an element twice is not the same as never
+/// adding it.
+///
+/// It is a [`ZobristHashSet`] whose keys are combined by wrapping addition (or another
+/// non-[self-inverse](Combiner::SELF_INVERSE) [`Combiner`]) instead of XOR, so the digest
+/// reflects how many times each element is present. This fits state such as shogi pieces in
+/// hand or resource counts. In checked builds the debug checker of the set tracks the
+/// multiplicities, and removing an element that is not present panics.
+///
+/// ```rust
+/// use zobristhash_set::ZobristHashMultiset;
+///
+/// let mut once = ZobristHashMultiset::empty();
+/// once.add(&"silver");
+///
+/// let mut twice = once.clone();
+/// twice.add(&"silver");
+/// assert_ne!(once.hash(), twice.hash());
+///
+/// twice.remove(&"silver");
+/// assert_eq!(u64::from(once), u64::from(twice));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ZobristHashMultiset<E, K = FxKeys, C = WrappingAdd> {
+    set: ZobristHashSet<E, K, C>,
+}
+
+impl<E> ZobristHashMultiset<E> {
+    pub fn empty() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E> Default for ZobristHashMultiset<E> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<E, K> ZobristHashMultiset<E, K> {
+    /// Creates an empty multiset that derives element keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        Self::with_combiner(keys, WrappingAdd)
+    }
+}
+
+impl<E, K, C: Combiner> ZobristHashMultiset<E, K, C> {
+    /// Creates an empty multiset that derives element keys from `keys` and folds them with `C`.
+    pub fn with_combiner(keys: K, combiner: C) -> Self {
+        Self {
+            set: ZobristHashSet::with_combiner(keys, combiner),
+        }
+    }
+}
+
+impl<E, K, C> ZobristHashMultiset<E, K, C> {
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the underlying set.
+    pub fn into_inner(self) -> ZobristHashSet<E, K, C> {
+        self.set
+    }
+}
+
+impl<E, K, C> From<ZobristHashMultiset<E, K, C>> for u64 {
+    fn from(hash: ZobristHashMultiset<E, K, C>) -> u64 {
+        hash.set.hash
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>, C: Combiner> ZobristHashMultiset<E, K, C> {
+    /// Adds one occurrence of `key`.
+    pub fn add(&mut self, key: &E) {
+        self.set.add(key);
+    }
+
+    /// Removes one occurrence of `key`.
+    pub fn remove(&mut self, key: &E) {
+        self.set.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiplicity_is_reflected() {
+        let mut hash = ZobristHashMultiset::empty();
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..4 {
+            hash.add(&7u32);
+            assert!(seen.insert(hash.hash()));
+        }
+        for _ in 0..4 {
+            hash.remove(&7u32);
+        }
+        assert_eq!(hash.hash(), 0);
+    }
+
+    #[test]
+    fn order_insensitive() {
+        let mut a = ZobristHashMultiset::empty();
+        let mut b = ZobristHashMultiset::empty();
+        for e in [1u32, 2, 1, 3, 1] {
+            a.add(&e);
+        }
+        for e in [3u32, 1, 1, 1, 2] {
+            b.add(&e);
+        }
+        assert_eq!(a.hash(), b.hash());
+    }
+
+    #[test]
+    fn gf_mul_combiner() {
+        let mut hash = ZobristHashMultiset::with_combiner(FxKeys, crate::GfMul);
+        hash.add(&3u32);
+        hash.add(&3u32);
+        hash.remove(&3u32);
+        hash.remove(&3u32);
+        assert_eq!(hash.hash(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn remove_missing_debug() {
+        let mut hash = ZobristHashMultiset::empty();
+        hash.add(&1u32);
+        hash.remove(&1u32);
+        hash.remove(&1u32);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::Accumulator;
+
+/// One row of weights or accumulated values, aligned so whole rows load into vector registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C, align(64))]
+struct Row<const N: usize>([i16; N]);
+
+impl<const N: usize> Row<N> {
+    fn add(&mut self, other: &Self) {
+        for (value, weight) in self.0.iter_mut().zip(&other.0) {
+            *value = value.wrapping_add(*weight);
+        }
+    }
+
+    fn sub(&mut self, other: &Self) {
+        for (value, weight) in self.0.iter_mut().zip(&other.0) {
+            *value = value.wrapping_sub(*weight);
+        }
+    }
+}
+
+/// The first layer of an NNUE-style network, kept up to date as features are added and
+/// removed.
+///
+/// Every element maps to a feature index, and every feature to a row of `N` weights. The
+/// accumulator holds the bias plus the rows of all present features, updated with one row
+/// addition or subtraction per update. Rows are aligned fixed-size arrays, so the loops compile
+/// to vector instructions. Driven by a [`ZobristWithAccumulators`](crate::ZobristWithAccumulators),
+/// the network input and the position hash follow the same add and remove events.
+///
+/// ```rust
+/// use zobristhash_set::{NnueAccumulator, ZobristWithAccumulators};
+///
+/// // Two features, one per square, with four outputs each.
+/// let weights = vec![[1, 2, 3, 4], [10, 20, 30, 40]];
+/// let nnue = NnueAccumulator::new(&weights, [100; 4], |square: &u8| *square as usize);
+///
+/// let mut position = ZobristWithAccumulators::new(nnue);
+/// position.add(&0);
+/// position.add(&1);
+/// position.remove(&0);
+/// assert_eq!(position.accumulators().values(), &[110, 120, 130, 140]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct NnueAccumulator<F, const N: usize> {
+    weights: Box<[Row<N>]>,
+    bias: Row<N>,
+    values: Row<N>,
+    feature: F,
+}
+
+impl<F, const N: usize> NnueAccumulator<F, N> {
+    /// Creates an accumulator holding just `bias`, with one row of `weights` per feature and
+    /// `feature` mapping elements to row indices.
+    pub fn new(weights: &[[i16; N]], bias: [i16; N], feature: F) -> Self {
+        Self {
+            weights: weights.iter().map(|&row| Row(row)).collect(),
+            bias: Row(bias),
+            values: Row(bias),
+            feature,
+        }
+    }
+
+    /// Returns the accumulated values.
+    pub fn values(&self) -> &[i16; N] {
+        &self.values.0
+    }
+
+    /// Recomputes the values from scratch for the features of `elements`.
+    ///
+    /// After many incremental updates, or when most features change at once, this is cheaper
+    /// than the equivalent removals and additions.
+    pub fn refresh<'a, E: 'a>(&mut self, elements: impl IntoIterator<Item = &'a E>)
+    where
+        F: Fn(&E) -> usize,
+    {
+        let mut values = self.bias;
+        for element in elements {
+            values.add(&self.weights[(self.feature)(element)]);
+        }
+        self.values = values;
+    }
+}
+
+impl<E, F: Fn(&E) -> usize, const N: usize> Accumulator<E> for NnueAccumulator<F, N> {
+    fn on_add(&mut self, element: &E) {
+        self.values.add(&self.weights[(self.feature)(element)]);
+    }
+
+    fn on_remove(&mut self, element: &E) {
+        self.values.sub(&self.weights[(self.feature)(element)]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZobristWithAccumulators;
+
+    #[test]
+    fn incremental_updates_match_refresh() {
+        let weights: Vec<[i16; 32]> = (0..64i16)
+            .map(|feature| std::array::from_fn(|i| feature * 31 - i as i16 * 7))
+            .collect();
+        let bias = std::array::from_fn(|i| i as i16);
+        let mut position =
+            ZobristWithAccumulators::new(NnueAccumulator::new(&weights, bias, |s: &u8| {
+                *s as usize
+            }));
+        for square in 0..64u8 {
+            position.add(&square);
+        }
+        for square in (0..64u8).step_by(3) {
+            position.remove(&square);
+        }
+
+        let mut fresh = NnueAccumulator::new(&weights, bias, |s: &u8| *s as usize);
+        let present: Vec<u8> = (0..64u8).filter(|s| s % 3 != 0).collect();
+        fresh.refresh(&present);
+        assert_eq!(position.accumulators().values(), fresh.values());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Receives the key of every element added to or removed from an [`ObservedZobristHashSet`].
+///
+/// Both methods do nothing by default, so an observer only implements the events it needs.
+pub trait Observer {
+    /// Called after an element with key `key` was added.
+    fn on_add(&mut self, key: u64) {
+        let _ = key;
+    }
+
+    /// Called after an element with key `key` was removed.
+    fn on_remove(&mut self, key: u64) {
+        let _ = key;
+    }
+}
+
+/// The observer that ignores every event.
+impl Observer for () {}
+
+/// A Zobrist hash that reports every update to an [`Observer`].
+///
+/// Instrumentation, mirroring updates into a secondary structure, or extra validation can be
+/// layered on the set this way without changing the code that updates it.
+///
+/// ```rust
+/// use zobristhash_set::{ObservedZobristHashSet, Observer};
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     adds: usize,
+///     removes: usize,
+/// }
+///
+/// impl Observer for Counter {
+///     fn on_add(&mut self, _key: u64) {
+///         self.adds += 1;
+///     }
+///
+///     fn on_remove(&mut self, _key: u64) {
+///         self.removes += 1;
+///     }
+/// }
+///
+/// let mut position = ObservedZobristHashSet::new(Counter::default());
+/// position.add(&("knight", 1));
+/// position.remove(&("knight", 1));
+/// position.add(&("knight", 18));
+/// assert_eq!(position.observer().adds, 2);
+/// assert_eq!(position.observer().removes, 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ObservedZobristHashSet<E, O, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    observer: O,
+}
+
+impl<E, O> ObservedZobristHashSet<E, O> {
+    /// Creates an empty set reporting to `observer`.
+    pub fn new(observer: O) -> Self {
+        Self::with_keys(FxKeys, observer)
+    }
+}
+
+impl<E, O, K> ObservedZobristHashSet<E, O, K> {
+    /// Creates an empty set that derives element keys from `keys` and reports to `observer`.
+    pub fn with_keys(keys: K, observer: O) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            observer,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Returns the set and the observer.
+    pub fn into_parts(self) -> (ZobristHashSet<E, K>, O) {
+        (self.set, self.observer)
+    }
+}
+
+impl<E, O, K> From<ObservedZobristHashSet<E, O, K>> for u64 {
+    fn from(set: ObservedZobristHashSet<E, O, K>) -> u64 {
+        set.set.hash
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, O: Observer, K: KeySource<E>> ObservedZobristHashSet<E, O, K> {
+    /// Adds `key`, reports it and returns its 64-bit key.
+    pub fn add(&mut self, key: &E) -> u64 {
+        let element_key = self.set.add(key);
+        self.observer.on_add(element_key);
+        element_key
+    }
+
+    /// Removes `key`, reports it and returns its 64-bit key.
+    pub fn remove(&mut self, key: &E) -> u64 {
+        let element_key = self.set.remove(key);
+        self.observer.on_remove(element_key);
+        element_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Mirror(Vec<(bool, u64)>);
+
+    impl Observer for Mirror {
+        fn on_add(&mut self, key: u64) {
+            self.0.push((true, key));
+        }
+
+        fn on_remove(&mut self, key: u64) {
+            self.0.push((false, key));
+        }
+    }
+
+    #[test]
+    fn reports_keys_in_order() {
+        let mut set = ObservedZobristHashSet::new(Mirror::default());
+        let one = set.add(&1u32);
+        let two = set.add(&2u32);
+        set.remove(&1u32);
+        assert_eq!(set.observer().0, [(true, one), (true, two), (false, one)]);
+
+        let (plain, _) = set.into_parts();
+        assert_eq!(plain.hash(), two);
+    }
+}
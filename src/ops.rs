@@ -0,0 +1,151 @@
+use crate::{KeySource, Xor, ZobristHashSet};
+use core::fmt::Debug;
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+/// An update of a set, for replaying recorded or generated update sequences.
+///
+/// Fuzzers and property tests can decode one sequence of operations and apply it both to a
+/// [`ZobristHashSet`] with [`apply_ops`](ZobristHashSet::apply_ops) and to a reference
+/// [`HashSet`] with [`apply_reference_ops`], then compare the results.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Op<E> {
+    Add(E),
+    Remove(E),
+    /// Removes every element.
+    Clear,
+    /// Adds the element if it is absent and removes it otherwise.
+    Toggle(E),
+}
+
+impl<E, K, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, Xor, CHECK_CAPACITY>
+where
+    E: Hash + Eq + Clone + Debug,
+    K: KeySource<E>,
+{
+    /// Applies `ops` in order.
+    ///
+    /// `Add` and `Remove` behave like [`add`](Self::add) and [`remove`](Self::remove), so the
+    /// checker reports an element added twice or removed while absent. With the XOR combiner
+    /// adding and removing change the hash the same way, so `Toggle` needs no membership test;
+    /// checked builds still pick the matching operation to keep the checker in sync.
+    ///
+    /// ```rust
+    /// use std::collections::HashSet;
+    /// use zobristhash_set::{apply_reference_ops, Op, ZobristHashSet};
+    ///
+    /// let ops = [Op::Add(1), Op::Toggle(2), Op::Remove(1), Op::Toggle(3), Op::Toggle(2)];
+    /// let mut hash = ZobristHashSet::empty();
+    /// hash.apply_ops(&ops);
+    ///
+    /// let mut reference = HashSet::new();
+    /// apply_reference_ops(&mut reference, &ops);
+    /// assert_eq!(hash.verify(&reference), Ok(()));
+    /// ```
+    pub fn apply_ops(&mut self, ops: &[Op<E>]) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("apply_ops", len = ops.len()).entered();
+        for op in ops {
+            match op {
+                Op::Add(element) => {
+                    self.add(element);
+                }
+                Op::Remove(element) => {
+                    self.remove(element);
+                }
+                Op::Clear => self.rebuild(core::iter::empty()),
+                Op::Toggle(element) => self.toggle(element),
+            }
+        }
+    }
+
+    fn toggle(&mut self, element: &E) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        if self
+            .checker
+            .as_ref()
+            .is_some_and(|checker| checker.contains(element))
+        {
+            self.remove(element);
+            return;
+        }
+        self.add(element);
+    }
+}
+
+/// Applies `ops` to `set`, the reference model for [`ZobristHashSet::apply_ops`].
+///
+/// Adding a present element or removing an absent one leaves `set` unchanged.
+#[cfg(feature = "std")]
+pub fn apply_reference_ops<E: Hash + Eq + Clone>(set: &mut HashSet<E>, ops: &[Op<E>]) {
+    for op in ops {
+        match op {
+            Op::Add(element) => {
+                set.insert(element.clone());
+            }
+            Op::Remove(element) => {
+                set.remove(element);
+            }
+            Op::Clear => set.clear(),
+            Op::Toggle(element) => {
+                if !set.remove(element) {
+                    set.insert(element.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Drops every `Add` of a present and `Remove` of an absent element, as judged by replaying
+/// `ops` on a reference set, so the rest can be applied without tripping the checker.
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+pub(crate) fn retain_valid<E: Hash + Eq + Clone>(ops: Vec<Op<E>>) -> Vec<Op<E>> {
+    let mut reference = HashSet::new();
+    ops.into_iter()
+        .filter(|op| {
+            let valid = match op {
+                Op::Add(element) => !reference.contains(element),
+                Op::Remove(element) => reference.contains(element),
+                Op::Clear | Op::Toggle(_) => true,
+            };
+            if valid {
+                apply_reference_ops(&mut reference, std::slice::from_ref(op));
+            }
+            valid
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn random_ops_match_the_reference() {
+        let mut rng = rand::thread_rng();
+        let mut hash = ZobristHashSet::empty();
+        let mut reference = HashSet::new();
+        for _ in 0..200 {
+            // Only add absent and remove present elements, which the checker requires.
+            let mut ops = Vec::new();
+            let mut expected = reference.clone();
+            for _ in 0..20 {
+                let element = rng.gen_range(0..16u8);
+                let op = match rng.gen_range(0..10) {
+                    0 => Op::Clear,
+                    1..=4 if expected.contains(&element) => Op::Remove(element),
+                    1..=4 => Op::Add(element),
+                    _ => Op::Toggle(element),
+                };
+                apply_reference_ops(&mut expected, std::slice::from_ref(&op));
+                ops.push(op);
+            }
+            hash.apply_ops(&ops);
+            apply_reference_ops(&mut reference, &ops);
+            assert_eq!(reference, expected);
+            assert_eq!(hash.verify(&reference), Ok(()));
+        }
+    }
+}
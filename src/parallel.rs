@@ -0,0 +1,92 @@
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Adds elements from a parallel iterator, deriving their keys on rayon's thread pool.
+///
+/// In checked builds the elements are collected and added one by one, so duplicates are still
+/// caught.
+impl<E, K> ParallelExtend<E> for ZobristHashSet<E, K>
+where
+    E: Hash + Eq + Clone + Debug + Send,
+    K: KeySource<E> + Sync,
+{
+    fn par_extend<I: IntoParallelIterator<Item = E>>(&mut self, elements: I) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("par_extend").entered();
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        {
+            let elements: Vec<E> = elements.into_par_iter().collect();
+            for element in &elements {
+                self.add(element);
+            }
+        }
+        #[cfg(not(all(debug_assertions, feature = "check_set_behavior")))]
+        {
+            let keys = &self.keys;
+            let (hash, count) = elements
+                .into_par_iter()
+                .map(|element| (keys.key(&element), 1))
+                .reduce(|| (0, 0), |a, b| (a.0 ^ b.0, a.1 + b.1));
+            self.hash ^= hash;
+            self.count_updates(count);
+            #[cfg(feature = "tracing")]
+            tracing::trace!(op = "par_extend", len = count, hash = self.hash);
+        }
+    }
+}
+
+impl<E, K> FromParallelIterator<E> for ZobristHashSet<E, K>
+where
+    E: Hash + Eq + Clone + Debug + Send,
+    K: KeySource<E> + Default + Sync,
+{
+    fn from_par_iter<I: IntoParallelIterator<Item = E>>(elements: I) -> Self {
+        let mut set = Self::with_keys(K::default());
+        set.par_extend(elements);
+        set
+    }
+}
+
+/// Hashes the items of a parallel iterator as a set.
+pub trait ParZobristHash: ParallelIterator {
+    /// Returns the Zobrist hash of the items with the default key source.
+    ///
+    /// ```rust
+    /// use rayon::prelude::*;
+    /// use zobristhash_set::{ParZobristHash, ZobristHashSet};
+    ///
+    /// let parallel = (0..5000u32).into_par_iter().par_zobrist_hash();
+    ///
+    /// let mut sequential = ZobristHashSet::empty();
+    /// for i in 0..5000u32 {
+    ///     sequential.add(&i);
+    /// }
+    /// assert_eq!(parallel, u64::from(sequential));
+    /// ```
+    fn par_zobrist_hash(self) -> u64
+    where
+        Self::Item: Hash + Eq + Clone + Debug,
+    {
+        u64::from(ZobristHashSet::<Self::Item, FxKeys>::from_par_iter(self))
+    }
+}
+
+impl<I: ParallelIterator> ParZobristHash for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_extension_matches_sequential_adds() {
+        let mut parallel: ZobristHashSet<u32> = (0..3000).into_par_iter().collect();
+        parallel.par_extend(3000..6000u32);
+        let mut sequential = ZobristHashSet::empty();
+        for i in 0..6000u32 {
+            sequential.add(&i);
+        }
+        assert_eq!(u64::from(parallel), u64::from(sequential));
+    }
+}
@@ -0,0 +1,109 @@
+/// A hash of the sequence of states visited along a search path.
+///
+/// Each pushed state hash is folded into the running value together with the new depth, with a
+/// multiplication and a rotation, so reaching the same state by a different route gives a
+/// different path hash. Mixing in the depth keeps a state hash of 0, such as that of an empty
+/// set, from leaving the path hash unchanged. The previous values are kept on a stack, so
+/// [`pop`](Self::pop) unwinds in constant time when the search backtracks.
+///
+/// ```rust
+/// use zobristhash_set::ZobristPathHash;
+///
+/// let mut path = ZobristPathHash::new();
+/// path.push(0x1234);
+/// path.push(0x5678);
+///
+/// let mut other = ZobristPathHash::new();
+/// other.push(0x5678);
+/// other.push(0x1234);
+/// assert_ne!(path.hash(), other.hash());
+///
+/// path.pop();
+/// other.pop();
+/// other.pop();
+/// other.push(0x1234);
+/// assert_eq!(path.hash(), other.hash());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ZobristPathHash {
+    hash: u64,
+    stack: Vec<u64>,
+}
+
+const MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+impl ZobristPathHash {
+    /// Creates the hash of the empty path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the number of states on the path.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Extends the path with a state identified by `state_hash`.
+    pub fn push(&mut self, state_hash: u64) {
+        self.stack.push(self.hash);
+        let depth = (self.stack.len() as u64).wrapping_mul(MULTIPLIER);
+        self.hash = (self.hash ^ state_hash ^ depth)
+            .wrapping_mul(MULTIPLIER)
+            .rotate_left(23);
+    }
+
+    /// Removes the last state from the path. Returns `false` if the path was already empty.
+    pub fn pop(&mut self) -> bool {
+        match self.stack.pop() {
+            Some(previous) => {
+                self.hash = previous;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl From<ZobristPathHash> for u64 {
+    fn from(path: ZobristPathHash) -> u64 {
+        path.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_and_pop() {
+        let mut path = ZobristPathHash::new();
+        let mut seen = vec![path.hash()];
+        for state in [3u64, 1, 4, 1, 5] {
+            path.push(state);
+            seen.push(path.hash());
+        }
+        assert_eq!(path.depth(), 5);
+        // Revisiting state 1 still moves the path hash forward.
+        assert_ne!(seen[2], seen[4]);
+        for expected in seen.iter().rev().skip(1) {
+            assert!(path.pop());
+            assert_eq!(path.hash(), *expected);
+        }
+        assert!(!path.pop());
+    }
+
+    #[test]
+    fn empty_states_extend_the_path() {
+        let mut path = ZobristPathHash::new();
+        let mut seen = vec![path.hash()];
+        for _ in 0..3 {
+            path.push(0);
+            assert!(!seen.contains(&path.hash()));
+            seen.push(path.hash());
+        }
+    }
+}
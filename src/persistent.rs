@@ -0,0 +1,72 @@
+use crate::{Combiner, KeySource, ZobristError, ZobristHashSet};
+use core::fmt::Debug;
+use core::hash::Hash;
+
+impl<E, K, C, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, C, CHECK_CAPACITY>
+where
+    E: Hash + Eq + Clone + Debug,
+    K: KeySource<E> + Clone,
+    C: Combiner + Clone,
+{
+    /// Returns a copy of the set with `key` added, leaving `self` unchanged.
+    ///
+    /// Without the debug checker the set is `Copy`, so this costs no more than
+    /// [`add`](Self::add). It suits immutable game trees where each node owns its hash and
+    /// children are derived from their parent.
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// let root = ZobristHashSet::empty().added(&("king", 4));
+    /// let child = root.removed(&("king", 4)).added(&("king", 5));
+    /// let sibling = root.removed(&("king", 4)).added(&("king", 3));
+    ///
+    /// assert_ne!(child.hash(), sibling.hash());
+    /// assert_eq!(root.hash(), ZobristHashSet::empty().added(&("king", 4)).hash());
+    /// ```
+    #[must_use]
+    pub fn added(&self, key: &E) -> Self {
+        let mut set = self.clone();
+        set.add(key);
+        set
+    }
+
+    /// Returns a copy of the set with `key` removed, leaving `self` unchanged.
+    #[must_use]
+    pub fn removed(&self, key: &E) -> Self {
+        let mut set = self.clone();
+        set.remove(key);
+        set
+    }
+
+    /// Returns a copy of the set updated like [`apply_diff`](Self::apply_diff), leaving `self`
+    /// unchanged.
+    pub fn with_diff(&self, added: &[E], removed: &[E]) -> Result<Self, ZobristError> {
+        let mut set = self.clone();
+        set.apply_diff(added, removed)?;
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ZobristHashSet;
+
+    #[test]
+    fn derived_sets_leave_the_original_alone() {
+        let mut mutated = ZobristHashSet::empty();
+        mutated.add(&1u32);
+        let root = ZobristHashSet::empty().added(&1u32);
+        assert_eq!(root.hash(), mutated.hash());
+
+        let child = root.with_diff(&[2, 3], &[1]).unwrap();
+        mutated.remove(&1);
+        mutated.add(&2);
+        mutated.add(&3);
+        assert_eq!(child.hash(), mutated.hash());
+        assert_eq!(child.removed(&2).removed(&3).hash(), 0);
+
+        // The root still holds 1, checker included.
+        assert_eq!(root.removed(&1).hash(), 0);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::keys::{splitmix64, KeySource};
+use phf::{OrderedSet, PhfEq, PhfHash};
+
+const DEFAULT_SEED: u64 = 0x5A0B_7157_0000_0001;
+
+/// A key source for closed domains described by a compile-time perfect hash.
+///
+/// The domain is a [`phf::OrderedSet`] built with `phf_ordered_set!` (or `phf_codegen`). Each
+/// element's slot in that set indexes a table of random keys, so looking up a key costs one
+/// perfect-hash probe and no collision handling.
+///
+/// ```rust
+/// use phf::phf_ordered_set;
+/// use zobristhash_set::{PhfKeys, ZobristHashSet};
+///
+/// // Pieces in FEN notation.
+/// static PIECES: phf::OrderedSet<char> = phf_ordered_set! {
+///     'P', 'N', 'B', 'R', 'Q', 'K', 'p', 'n', 'b', 'r', 'q', 'k',
+/// };
+///
+/// let keys = PhfKeys::builder(&PIECES).seed(42).build();
+/// let mut hash = ZobristHashSet::with_keys(keys);
+/// hash.add(&'P');
+/// hash.add(&'k');
+/// hash.remove(&'P');
+///
+/// let mut expected = ZobristHashSet::with_keys(keys);
+/// expected.add(&'k');
+/// assert_eq!(u64::from(hash), u64::from(expected));
+/// ```
+pub struct PhfKeys<T: 'static> {
+    domain: &'static OrderedSet<T>,
+    table: &'static [u64],
+}
+
+impl<T> Clone for PhfKeys<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for PhfKeys<T> {}
+
+impl<T> std::fmt::Debug for PhfKeys<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PhfKeys")
+            .field("len", &self.table.len())
+            .finish()
+    }
+}
+
+impl<T> PhfKeys<T> {
+    /// Starts building a key source over `domain`.
+    pub fn builder(domain: &'static OrderedSet<T>) -> PhfKeysBuilder<T> {
+        PhfKeysBuilder {
+            domain,
+            seed: DEFAULT_SEED,
+        }
+    }
+}
+
+impl<T: Eq + PhfHash> KeySource<T> for PhfKeys<T>
+where
+    T: PhfEq<T>,
+{
+    fn key(&self, element: &T) -> u64 {
+        let index = self
+            .domain
+            .get_index(element)
+            .expect("element is not part of the perfect-hash domain");
+        self.table[index]
+    }
+}
+
+/// Builder for [`PhfKeys`].
+pub struct PhfKeysBuilder<T: 'static> {
+    domain: &'static OrderedSet<T>,
+    seed: u64,
+}
+
+impl<T> PhfKeysBuilder<T> {
+    /// Sets the seed the key table is generated from.
+    ///
+    /// Key sources built from the same domain and seed produce identical keys.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Generates the key table.
+    ///
+    /// The table lives for the rest of the program, so build the key source once and copy it into
+    /// every set that should share it.
+    pub fn build(self) -> PhfKeys<T> {
+        let mut state = self.seed;
+        let table: Vec<u64> = (0..self.domain.len())
+            .map(|_| splitmix64(&mut state))
+            .collect();
+        PhfKeys {
+            domain: self.domain,
+            table: Box::leak(table.into_boxed_slice()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use phf::phf_ordered_set;
+
+    static SQUARES: OrderedSet<u8> = phf_ordered_set! { 0u8, 1u8, 2u8, 3u8 };
+
+    #[test]
+    fn keys_are_distinct_per_slot() {
+        let keys = PhfKeys::builder(&SQUARES).build();
+        let all: std::collections::HashSet<u64> = (0..4u8).map(|i| keys.key(&i)).collect();
+        assert_eq!(all.len(), 4);
+    }
+
+    #[test]
+    fn same_seed_same_keys() {
+        let a = PhfKeys::builder(&SQUARES).seed(7).build();
+        let b = PhfKeys::builder(&SQUARES).seed(7).build();
+        let c = PhfKeys::builder(&SQUARES).seed(8).build();
+        assert_eq!(a.key(&2), b.key(&2));
+        assert_ne!(a.key(&2), c.key(&2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn element_outside_domain() {
+        let keys = PhfKeys::builder(&SQUARES).build();
+        keys.key(&9);
+    }
+}
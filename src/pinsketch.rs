@@ -0,0 +1,407 @@
+use crate::combiner::{gf_inv, gf_mul};
+use crate::keys::splitmix64;
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A linear sketch of a set of non-zero 64-bit keys from which up to `capacity` keys can be
+/// recovered, after the PinSketch construction.
+///
+/// The sketch stores the odd power sums `Σk, Σk³, …, Σk^(2·capacity-1)` in
+/// GF(2<sup>64</sup>), which is `8 · capacity` bytes regardless of the number of keys. Adding
+/// and removing a key are the same operation, and [merging](Self::merge) the sketches of two
+/// sets yields the sketch of their symmetric difference. A zero key cannot be represented.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PinSketch {
+    sums: Vec<u64>,
+}
+
+impl PinSketch {
+    /// Creates the sketch of the empty set, able to decode up to `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            sums: vec![0; capacity],
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.sums.len()
+    }
+
+    /// Adds `key` to the sketch, or removes it if it is already present.
+    pub fn toggle(&mut self, key: u64) {
+        let square = gf_mul(key, key);
+        let mut power = key;
+        for sum in &mut self.sums {
+            *sum ^= power;
+            power = gf_mul(power, square);
+        }
+    }
+
+    /// Replaces `self` by the sketch of the symmetric difference of both sets.
+    pub fn merge(&mut self, other: &PinSketch) {
+        assert_eq!(
+            self.sums.len(),
+            other.sums.len(),
+            "sketches must have the same capacity"
+        );
+        for (sum, other) in self.sums.iter_mut().zip(&other.sums) {
+            *sum ^= other;
+        }
+    }
+
+    /// Recovers the keys of the sketched set, or returns `None` if it cannot be decoded.
+    ///
+    /// A sketch of at most [`capacity`](Self::capacity) keys always decodes to exactly those
+    /// keys. An overfull sketch usually returns `None`, but with probability about
+    /// `1 / capacity!` it decodes to a different set of at most `capacity` keys that happens to
+    /// have the same sketch. The sketch alone cannot tell the two apart, so when the set may
+    /// have exceeded the capacity, verify the result against a hash of the set that the sketch
+    /// does not determine, such as the wrapping sum of the keys kept by a set with the
+    /// [`WrappingAdd`](crate::WrappingAdd) combiner. The XOR of the keys is the first power sum,
+    /// so an XOR hash always agrees with a wrong result.
+    pub fn decode(&self) -> Option<Vec<u64>> {
+        let locator = berlekamp_massey(&self.syndromes());
+        let degree = locator.len() - 1;
+        if degree > self.sums.len() || locator[degree] == 0 {
+            return None;
+        }
+        // The locator's roots are the inverses of the keys; reversing it gives a monic
+        // polynomial whose roots are the keys themselves.
+        let poly: Vec<u64> = locator.into_iter().rev().collect();
+        if !splits_into_distinct_roots(&poly) {
+            return None;
+        }
+        let mut keys = Vec::with_capacity(degree);
+        let mut state = 0;
+        if !find_roots(&poly, &mut keys, &mut state) {
+            return None;
+        }
+        let mut check = PinSketch::with_capacity(self.sums.len());
+        for &key in &keys {
+            check.toggle(key);
+        }
+        (check == *self).then_some(keys)
+    }
+
+    /// Serializes the sketch as `8 · capacity` little-endian bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.sums.iter().flat_map(|sum| sum.to_le_bytes()).collect()
+    }
+
+    /// Reads a sketch written by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if !bytes.len().is_multiple_of(8) {
+            return None;
+        }
+        let sums = bytes
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Some(Self { sums })
+    }
+
+    /// Returns all power sums `Σk^j` for `j` in `1..=2·capacity`.
+    fn syndromes(&self) -> Vec<u64> {
+        let mut syndromes = vec![0; 2 * self.sums.len()];
+        for j in 1..=syndromes.len() {
+            syndromes[j - 1] = if j % 2 == 1 {
+                self.sums[j / 2]
+            } else {
+                // In characteristic two, Σk^(2i) = (Σk^i)².
+                gf_mul(syndromes[j / 2 - 1], syndromes[j / 2 - 1])
+            };
+        }
+        syndromes
+    }
+}
+
+/// Returns the shortest linear recurrence generating `syndromes`, lowest coefficient first.
+fn berlekamp_massey(syndromes: &[u64]) -> Vec<u64> {
+    let mut current = vec![1];
+    let mut previous = vec![1];
+    let mut length = 0;
+    let mut shift = 1;
+    let mut previous_discrepancy = 1;
+    for n in 0..syndromes.len() {
+        let mut discrepancy = syndromes[n];
+        for i in 1..=length.min(current.len() - 1) {
+            discrepancy ^= gf_mul(current[i], syndromes[n - i]);
+        }
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let factor = gf_mul(discrepancy, gf_inv(previous_discrepancy));
+        let saved = current.clone();
+        if current.len() < previous.len() + shift {
+            current.resize(previous.len() + shift, 0);
+        }
+        for (i, &coefficient) in previous.iter().enumerate() {
+            current[i + shift] ^= gf_mul(factor, coefficient);
+        }
+        if 2 * length <= n {
+            length = n + 1 - length;
+            previous = saved;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+    current.resize(length + 1, 0);
+    current
+}
+
+/// Returns `true` if `poly` divides x^(2^64) - x, i.e. it has distinct roots in the field.
+fn splits_into_distinct_roots(poly: &[u64]) -> bool {
+    let x = poly_rem(&[0, 1], poly);
+    let mut power = x.clone();
+    for _ in 0..64 {
+        power = poly_rem(&poly_square(&power), poly);
+    }
+    power == x
+}
+
+/// Appends the roots of `poly`, which must split into distinct linear factors, to `roots`.
+fn find_roots(poly: &[u64], roots: &mut Vec<u64>, state: &mut u64) -> bool {
+    match poly.len() {
+        0 => false,
+        1 => true,
+        2 => {
+            roots.push(gf_mul(poly[0], gf_inv(poly[1])));
+            true
+        }
+        _ => {
+            // The trace of βx is 0 for about half of the roots and 1 for the rest, so its gcd
+            // with `poly` usually splits it.
+            for _ in 0..64 {
+                let beta = poly_rem(&[0, splitmix64(state)], poly);
+                let mut trace = beta.clone();
+                let mut term = beta;
+                for _ in 1..64 {
+                    term = poly_rem(&poly_square(&term), poly);
+                    trace = poly_add(&trace, &term);
+                }
+                let factor = poly_gcd(poly.to_vec(), trace);
+                if factor.len() > 1 && factor.len() < poly.len() {
+                    let (quotient, _) = poly_divmod(poly, &factor);
+                    return find_roots(&factor, roots, state)
+                        && find_roots(&quotient, roots, state);
+                }
+            }
+            false
+        }
+    }
+}
+
+fn trim(poly: &mut Vec<u64>) {
+    while poly.last() == Some(&0) {
+        poly.pop();
+    }
+}
+
+fn poly_add(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut sum = vec![0; a.len().max(b.len())];
+    for (i, &c) in a.iter().enumerate() {
+        sum[i] ^= c;
+    }
+    for (i, &c) in b.iter().enumerate() {
+        sum[i] ^= c;
+    }
+    trim(&mut sum);
+    sum
+}
+
+fn poly_square(poly: &[u64]) -> Vec<u64> {
+    let mut square = vec![0; (2 * poly.len()).saturating_sub(1)];
+    for (i, &c) in poly.iter().enumerate() {
+        square[2 * i] = gf_mul(c, c);
+    }
+    square
+}
+
+fn poly_divmod(a: &[u64], b: &[u64]) -> (Vec<u64>, Vec<u64>) {
+    let mut rem = a.to_vec();
+    trim(&mut rem);
+    if rem.len() < b.len() {
+        return (Vec::new(), rem);
+    }
+    let lead_inverse = gf_inv(b[b.len() - 1]);
+    let mut quotient = vec![0; rem.len() - b.len() + 1];
+    while rem.len() >= b.len() {
+        let offset = rem.len() - b.len();
+        let factor = gf_mul(rem[rem.len() - 1], lead_inverse);
+        quotient[offset] = factor;
+        for (i, &c) in b.iter().enumerate() {
+            rem[offset + i] ^= gf_mul(factor, c);
+        }
+        trim(&mut rem);
+    }
+    (quotient, rem)
+}
+
+fn poly_rem(a: &[u64], b: &[u64]) -> Vec<u64> {
+    poly_divmod(a, b).1
+}
+
+/// Returns the monic greatest common divisor of `a` and `b`.
+fn poly_gcd(mut a: Vec<u64>, mut b: Vec<u64>) -> Vec<u64> {
+    trim(&mut a);
+    trim(&mut b);
+    while !b.is_empty() {
+        let rem = poly_rem(&a, &b);
+        a = b;
+        b = rem;
+    }
+    if let Some(&lead) = a.last() {
+        let inverse = gf_inv(lead);
+        for c in &mut a {
+            *c = gf_mul(*c, inverse);
+        }
+    }
+    a
+}
+
+/// A Zobrist hash paired with a [`PinSketch`] of its element keys.
+///
+/// When two peers know their sets differ in at most `capacity` elements, exchanging the
+/// `8 · capacity` byte sketches is enough for each of them to recover the keys of the differing
+/// elements.
+///
+/// ```rust
+/// use zobristhash_set::{FxKeys, KeySource, PinSketch, SketchedZobristSet};
+///
+/// let mut ours = SketchedZobristSet::with_capacity(8);
+/// let mut theirs = SketchedZobristSet::with_capacity(8);
+/// for i in 0..1000u32 {
+///     ours.add(&i);
+///     theirs.add(&i);
+/// }
+/// ours.remove(&7);
+/// theirs.add(&5000);
+///
+/// let received = PinSketch::from_bytes(&theirs.sketch().to_bytes()).unwrap();
+/// let mut differing = ours.difference(&received).unwrap();
+/// differing.sort_unstable();
+/// let mut expected = vec![FxKeys.key(&7u32), FxKeys.key(&5000u32)];
+/// expected.sort_unstable();
+/// assert_eq!(differing, expected);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SketchedZobristSet<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    sketch: PinSketch,
+}
+
+impl<E> SketchedZobristSet<E> {
+    /// Creates an empty set whose sketch can decode differences of up to `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_keys(FxKeys, capacity)
+    }
+}
+
+impl<E, K> SketchedZobristSet<E, K> {
+    /// Creates an empty set whose sketch can decode differences of up to `capacity` elements,
+    /// deriving keys from `keys`.
+    pub fn with_keys(keys: K, capacity: usize) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            sketch: PinSketch::with_capacity(capacity),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    pub fn sketch(&self) -> &PinSketch {
+        &self.sketch
+    }
+
+    /// Returns the keys of the elements in exactly one of `self` and the set sketched by
+    /// `other`, or `None` if there are too many of them.
+    ///
+    /// When the sets may differ in more than `capacity` elements, the result can be wrong, as
+    /// described for [`PinSketch::decode`]. The XOR hashes of the two sets agree with any such
+    /// result, so confirm a reconciliation with a hash independent of the sketch, such as one
+    /// using the [`WrappingAdd`](crate::WrappingAdd) combiner.
+    pub fn difference(&self, other: &PinSketch) -> Option<Vec<u64>> {
+        let mut sketch = self.sketch.clone();
+        sketch.merge(other);
+        sketch.decode()
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> SketchedZobristSet<E, K> {
+    pub fn add(&mut self, key: &E) {
+        let element_key = self.set.add(key);
+        self.sketch.toggle(element_key);
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        let element_key = self.set.remove(key);
+        self.sketch.toggle(element_key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn decodes_up_to_capacity() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        for count in 0..=12 {
+            let mut keys: Vec<u64> = (0..count).map(|_| rng.gen::<u64>() | 1).collect();
+            let mut sketch = PinSketch::with_capacity(12);
+            for &key in &keys {
+                sketch.toggle(key);
+            }
+            let mut decoded = sketch.decode().unwrap();
+            decoded.sort_unstable();
+            keys.sort_unstable();
+            assert_eq!(decoded, keys);
+        }
+    }
+
+    #[test]
+    fn overfull_sketches_never_decode_to_a_verified_set() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let mut wrong = 0;
+        for _ in 0..500 {
+            let keys: Vec<u64> = (0..10).map(|_| rng.gen::<u64>() | 1).collect();
+            let mut sketch = PinSketch::with_capacity(6);
+            for &key in &keys {
+                sketch.toggle(key);
+            }
+            assert_eq!(
+                PinSketch::from_bytes(&sketch.to_bytes()).as_ref(),
+                Some(&sketch)
+            );
+            let Some(decoded) = sketch.decode() else {
+                continue;
+            };
+            // A wrong decoding is another preimage of the sketch, which an independent hash
+            // exposes while the XOR of the keys does not.
+            wrong += 1;
+            assert!(decoded.len() <= 6);
+            let mut check = PinSketch::with_capacity(6);
+            for &key in &decoded {
+                check.toggle(key);
+            }
+            assert_eq!(check, sketch);
+            let xor = |keys: &[u64]| keys.iter().fold(0, |hash, key| hash ^ key);
+            let sum = |keys: &[u64]| keys.iter().fold(0u64, |hash, key| hash.wrapping_add(*key));
+            assert_eq!(xor(&decoded), xor(&keys));
+            assert_ne!(sum(&decoded), sum(&keys));
+        }
+        // Overfull sketches mostly fail to decode; a wrong decoding must stay the exception.
+        assert!(
+            wrong <= 25,
+            "{wrong} of 500 overfull sketches decoded wrongly"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+use crate::copiable_hash::CopiableHash;
+use crate::{Combiner, FxKeys, KeySource, ZobristHashSet};
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+/// The precomputed key of an element of a [`ZobristHashSet<E, K>`].
+///
+/// Computing it once, for example when a move is generated, lets make and unmake apply the move
+/// with [`add_prehashed`](ZobristHashSet::add_prehashed) and
+/// [`remove_prehashed`](ZobristHashSet::remove_prehashed) without hashing the element again.
+/// The type parameters ensure that it is only used with sets of the same element type and key
+/// source.
+///
+/// ```rust
+/// use zobristhash_set::ZobristHashSet;
+///
+/// let from = ZobristHashSet::element_hash(&("knight", 1));
+/// let to = ZobristHashSet::element_hash(&("knight", 18));
+///
+/// let mut board = ZobristHashSet::empty();
+/// board.add(&("knight", 1));
+/// board.remove_prehashed(from);
+/// board.add_prehashed(to);
+///
+/// let mut expected = ZobristHashSet::empty();
+/// expected.add(&("knight", 18));
+/// assert_eq!(u64::from(board), u64::from(expected));
+/// ```
+pub struct ElementHash<E, K = FxKeys> {
+    key: u64,
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    tracked: u64,
+    _marker: PhantomData<fn(&E, &K)>,
+}
+
+impl<E, K> Clone for ElementHash<E, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E, K> Copy for ElementHash<E, K> {}
+
+impl<E, K> PartialEq for ElementHash<E, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<E, K> Eq for ElementHash<E, K> {}
+
+impl<E, K> core::fmt::Debug for ElementHash<E, K> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ElementHash").field(&self.key).finish()
+    }
+}
+
+impl<E: Hash, K: KeySource<E>> ElementHash<E, K> {
+    /// Computes the key of `element` under `keys`.
+    pub fn with_keys(keys: &K, element: &E) -> Self {
+        Self {
+            key: keys.key(element),
+            #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+            tracked: CopiableHash::<E>::element_hash(element),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E, K> ElementHash<E, K> {
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+}
+
+impl<E: Hash> ZobristHashSet<E> {
+    /// Computes the key of `element` under the default key source.
+    pub fn element_hash(element: &E) -> ElementHash<E> {
+        ElementHash::with_keys(&FxKeys, element)
+    }
+}
+
+impl<E: Clone, K, C: Combiner, const CHECK_CAPACITY: usize>
+    ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
+    /// Adds the element whose key was computed in advance.
+    pub fn add_prehashed(&mut self, element: ElementHash<E, K>) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        if let Some(checker) = self.checker.as_mut() {
+            if C::SELF_INVERSE {
+                assert!(
+                    checker.insert_hash(element.tracked),
+                    "duplicate add of {element:?}: the element is already among the {} tracked \
+                     elements\n{}",
+                    checker.len(),
+                    checker.journal()
+                );
+            } else {
+                checker.insert_repeated_hash(element.tracked);
+            }
+        }
+        self.hash = C::combine(self.hash, element.key);
+        self.count_updates(1);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(op = "add", key = element.key, hash = self.hash);
+        #[cfg(feature = "metrics")]
+        crate::telemetry::adds().increment(1);
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        if let Some(checker) = self.checker.as_mut() {
+            checker
+                .journal_mut()
+                .record_key("add", element.key, self.hash);
+        }
+    }
+
+    /// Removes the element whose key was computed in advance.
+    pub fn remove_prehashed(&mut self, element: ElementHash<E, K>) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        if let Some(checker) = self.checker.as_mut() {
+            assert!(
+                checker.remove_hash(element.tracked),
+                "missing remove of {element:?}: the element is not among the {} tracked \
+                 elements\n{}",
+                checker.len(),
+                checker.journal()
+            );
+        }
+        self.hash = C::uncombine(self.hash, element.key);
+        self.count_updates(-1);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(op = "remove", key = element.key, hash = self.hash);
+        #[cfg(feature = "metrics")]
+        crate::telemetry::removes().increment(1);
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        if let Some(checker) = self.checker.as_mut() {
+            checker
+                .journal_mut()
+                .record_key("remove", element.key, self.hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SplitMixKeys, WrappingAdd};
+
+    #[test]
+    fn prehashed_updates_match_plain_ones() {
+        let element = ElementHash::with_keys(&SplitMixKeys, &7u32);
+        assert_eq!(element.key(), SplitMixKeys.key(&7u32));
+
+        let mut prehashed = ZobristHashSet::with_combiner(SplitMixKeys, WrappingAdd);
+        prehashed.add_prehashed(element);
+        prehashed.add_prehashed(element);
+        let mut plain = ZobristHashSet::with_combiner(SplitMixKeys, WrappingAdd);
+        plain.add(&7u32);
+        plain.add(&7u32);
+        assert_eq!(prehashed.hash(), plain.hash());
+
+        prehashed.remove_prehashed(element);
+        plain.remove(&7u32);
+        assert_eq!(u64::from(prehashed), u64::from(plain));
+    }
+
+    #[test]
+    #[should_panic(expected = "missing remove of ElementHash(")]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn prehashed_removal_is_checked_debug() {
+        let mut set = ZobristHashSet::empty();
+        set.add(&1u8);
+        set.remove_prehashed(ZobristHashSet::element_hash(&2u8));
+    }
+}
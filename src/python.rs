@@ -0,0 +1,200 @@
+//! Python bindings, built into an extension module named `zobristhash_set` with the `python`
+//! feature.
+//!
+//! Elements are byte strings keyed with [`BytesKeys`], exactly like the elements of a
+//! `ZobristHashSet<Vec<u8>, BytesKeys>`, so a Python pipeline and a Rust engine that encode
+//! positions the same way agree on every hash, whatever platform either runs on.
+//! Misuse that the debug checker catches raises `ValueError` instead of panicking.
+//!
+//! Build the module with `cargo rustc --release --features python --crate-type cdylib` and
+//! install the library as `zobristhash_set.so`, or let maturin do both.
+//!
+//! ```python
+//! from zobristhash_set import TranspositionTable, ZobristHashSet
+//!
+//! position = ZobristHashSet()
+//! position.add(b"Ke1")
+//! table = TranspositionTable(1 << 16)
+//! table.store(position.hash, {"score": 35})
+//! assert table.probe(position.hash) == {"score": 35}
+//! ```
+
+use crate::transposition::TranspositionTable;
+use crate::{BytesKeys, TrackedZobristHashSet, ZobristError, ZobristHashSet};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+fn value_error(error: ZobristError) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+/// A Zobrist hash of a set of byte strings.
+#[pyclass(name = "ZobristHashSet")]
+struct PyZobristHashSet {
+    set: ZobristHashSet<Vec<u8>, BytesKeys>,
+}
+
+#[pymethods]
+impl PyZobristHashSet {
+    #[new]
+    fn new() -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(BytesKeys),
+        }
+    }
+
+    fn add(&mut self, element: Vec<u8>) -> PyResult<()> {
+        self.set.try_add(&element).map(drop).map_err(value_error)
+    }
+
+    fn remove(&mut self, element: Vec<u8>) -> PyResult<()> {
+        self.set.try_remove(&element).map(drop).map_err(value_error)
+    }
+
+    #[getter]
+    fn hash(&self) -> u64 {
+        self.set.hash()
+    }
+
+    fn copy(&self) -> Self {
+        Self {
+            set: self.set.clone(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ZobristHashSet(hash={:#018x})", self.set.hash())
+    }
+}
+
+/// A set of byte strings that keeps their Zobrist hash up to date.
+#[pyclass(name = "TrackedZobristHashSet")]
+struct PyTrackedZobristHashSet {
+    set: TrackedZobristHashSet<Vec<u8>, BytesKeys>,
+}
+
+#[pymethods]
+impl PyTrackedZobristHashSet {
+    #[new]
+    fn new() -> Self {
+        Self {
+            set: TrackedZobristHashSet::with_keys(BytesKeys),
+        }
+    }
+
+    /// Adds `element`, returning `False` if it was already present.
+    fn insert(&mut self, element: Vec<u8>) -> bool {
+        self.set.insert(element)
+    }
+
+    /// Removes `element`, returning `False` if it was not present.
+    fn remove(&mut self, element: Vec<u8>) -> bool {
+        self.set.remove(&element)
+    }
+
+    fn clear(&mut self) {
+        self.set.clear();
+    }
+
+    #[getter]
+    fn hash(&self) -> u64 {
+        self.set.hash()
+    }
+
+    fn __contains__(&self, element: Vec<u8>) -> bool {
+        self.set.contains(&element)
+    }
+
+    fn __len__(&self) -> usize {
+        self.set.len()
+    }
+}
+
+/// A transposition table mapping hashes to arbitrary Python objects.
+#[pyclass(name = "TranspositionTable", unsendable)]
+struct PyTranspositionTable {
+    table: TranspositionTable<Py<PyAny>>,
+}
+
+#[pymethods]
+impl PyTranspositionTable {
+    #[new]
+    fn new(entries: usize) -> Self {
+        Self {
+            table: TranspositionTable::with_entries(entries),
+        }
+    }
+
+    fn store(&mut self, hash: u64, value: Py<PyAny>) {
+        self.table.store(hash, value);
+    }
+
+    fn probe(&self, py: Python<'_>, hash: u64) -> Option<Py<PyAny>> {
+        self.table.probe(hash).map(|value| value.clone_ref(py))
+    }
+
+    fn new_search(&mut self) {
+        self.table.new_search();
+    }
+
+    fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    #[getter]
+    fn capacity(&self) -> usize {
+        self.table.capacity()
+    }
+}
+
+#[pymodule]
+fn zobristhash_set(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyZobristHashSet>()?;
+    module.add_class::<PyTrackedZobristHashSet>()?;
+    module.add_class::<PyTranspositionTable>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::{IntoPyDict, PyModule};
+
+    #[test]
+    fn python_hashes_match_rust() {
+        let mut expected = ZobristHashSet::with_keys(BytesKeys);
+        expected.add(&b"Ke1".to_vec());
+        expected.add(&b"Ke8".to_vec());
+
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "zobristhash_set")?;
+            zobristhash_set(&module)?;
+            let locals = [
+                ("z", module.into_any()),
+                ("expected", expected.hash().into_pyobject(py)?.into_any()),
+            ]
+            .into_py_dict(py)?;
+            py.run(
+                c"
+s = z.ZobristHashSet()
+s.add(b'Ke1')
+s.add(b'Ke8')
+assert s.hash == expected
+
+t = z.TrackedZobristHashSet()
+assert t.insert(b'Ke8') and t.insert(b'Ke1') and not t.insert(b'Ke1')
+assert t.hash == expected and len(t) == 2 and b'Ke1' in t
+
+table = z.TranspositionTable(1024)
+table.store(s.hash, ('e2e4', 35))
+assert table.probe(s.hash) == ('e2e4', 35)
+assert table.probe(s.hash ^ (1 << 40)) is None
+",
+                None,
+                Some(&locals),
+            )
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use crate::ZobristHashSet;
+
+/// 2^64 divided by the golden ratio.
+const FIBONACCI: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// An unsigned integer type that can hold verification bits taken from a hash.
+pub trait VerificationBits {
+    /// Returns the low bits of `hash` that fit in `Self`.
+    fn from_hash(hash: u64) -> Self;
+}
+
+impl VerificationBits for u8 {
+    fn from_hash(hash: u64) -> Self {
+        hash as u8
+    }
+}
+
+impl VerificationBits for u16 {
+    fn from_hash(hash: u64) -> Self {
+        hash as u16
+    }
+}
+
+impl VerificationBits for u32 {
+    fn from_hash(hash: u64) -> Self {
+        hash as u32
+    }
+}
+
+/// Helpers for indexing user-managed tables with the hash value.
+impl<E, K, C, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, C, CHECK_CAPACITY> {
+    /// Maps the hash to a bucket in `0..table_len`.
+    ///
+    /// Uses Lemire's multiply-shift reduction, which is uniform for any `table_len` and, unlike
+    /// `hash % table_len`, depends on the high bits of the hash rather than the low ones.
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// let mut hash = ZobristHashSet::empty();
+    /// hash.add(&"rook");
+    /// assert!(hash.index_for(1000) < 1000);
+    /// ```
+    pub fn index_for(&self, table_len: usize) -> usize {
+        ((self.hash as u128 * table_len as u128) >> 64) as usize
+    }
+
+    /// Returns the low bits of the hash, to store next to an entry found by
+    /// [`index_for`](Self::index_for).
+    ///
+    /// For tables of up to 2^32 buckets they are independent of the bits that picked the bucket.
+    pub fn verification_bits<T: VerificationBits>(&self) -> T {
+        T::from_hash(self.hash)
+    }
+
+    /// Compresses the hash to 32 bits by Fibonacci hashing: a multiplication by 2^64/φ, keeping
+    /// the high half, so every input bit influences the result.
+    ///
+    /// Prefer it over truncation when storing compact signatures.
+    pub fn fold_u32(&self) -> u32 {
+        (self.hash.wrapping_mul(FIBONACCI) >> 32) as u32
+    }
+
+    /// Compresses the hash to 16 bits, like [`fold_u32`](Self::fold_u32).
+    pub fn fold_u16(&self) -> u16 {
+        (self.hash.wrapping_mul(FIBONACCI) >> 48) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_for_spreads_weak_low_bits() {
+        // Hashes that differ only in their high bits all share `hash % 8`.
+        let mut buckets = [0; 8];
+        for i in 0..256u64 {
+            let hash = ZobristHashSet::<u8>::from(i << 56);
+            buckets[hash.index_for(8)] += 1;
+        }
+        assert!(buckets.iter().all(|&count| count == 32), "{buckets:?}");
+    }
+
+    #[test]
+    fn folds_depend_on_high_bits() {
+        // Truncation would map all of these to zero.
+        let folded: std::collections::HashSet<u16> = (1..=1000u64)
+            .map(|i| ZobristHashSet::<u8>::from(i << 32).fold_u16())
+            .collect();
+        assert!(folded.len() > 980);
+        let hash = ZobristHashSet::<u8>::from(0x0123_4567_89ab_cdef);
+        assert_eq!(hash.fold_u16(), (hash.fold_u32() >> 16) as u16);
+    }
+
+    #[test]
+    fn verification_bits() {
+        let hash = ZobristHashSet::<u8>::from(0x0123_4567_89ab_cdef);
+        assert_eq!(hash.verification_bits::<u16>(), 0xcdef);
+        assert_eq!(hash.verification_bits::<u32>(), 0x89ab_cdef);
+        assert_eq!(hash.index_for(usize::MAX), 0x0123_4567_89ab_cdee);
+    }
+}
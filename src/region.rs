@@ -0,0 +1,165 @@
+use crate::keys::mix64;
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash over a board partitioned into regions, with constant-time region operations.
+///
+/// Elements are added to a region chosen by the caller and are keyed relative to that region, so
+/// each region has a sub-hash that depends only on its contents. The combined hash mixes each
+/// sub-hash with its region's index, which lets [`RegionZobrist::swap_regions`] and
+/// [`RegionZobrist::clear_region`] update it with a constant number of XORs instead of
+/// re-adding every cell.
+///
+/// ```rust
+/// use zobristhash_set::RegionZobrist;
+///
+/// let mut board = RegionZobrist::new(2);
+/// board.add(0, &(1, 'x'));
+/// board.add(1, &(3, 'o'));
+///
+/// let mut swapped = RegionZobrist::new(2);
+/// swapped.add(0, &(3, 'o'));
+/// swapped.add(1, &(1, 'x'));
+///
+/// board.swap_regions(0, 1);
+/// assert_eq!(board.hash(), swapped.hash());
+///
+/// board.clear_region(0);
+/// board.clear_region(1);
+/// assert_eq!(board.hash(), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RegionZobrist<E, K = FxKeys> {
+    regions: Vec<ZobristHashSet<E, K>>,
+    combined: u64,
+    keys: K,
+}
+
+impl<E> RegionZobrist<E> {
+    /// Creates a hash with `regions` empty regions.
+    pub fn new(regions: usize) -> Self {
+        Self::with_keys(FxKeys, regions)
+    }
+}
+
+impl<E, K: Clone> RegionZobrist<E, K> {
+    /// Creates a hash with `regions` empty regions, deriving element keys from `keys`.
+    pub fn with_keys(keys: K, regions: usize) -> Self {
+        Self {
+            regions: (0..regions)
+                .map(|_| ZobristHashSet::with_keys(keys.clone()))
+                .collect(),
+            combined: 0,
+            keys,
+        }
+    }
+
+    /// Returns the number of regions.
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Returns the combined hash of all regions.
+    pub fn hash(&self) -> u64 {
+        self.combined
+    }
+
+    /// Returns the sub-hash of `region`, which only depends on its contents.
+    pub fn region_hash(&self, region: usize) -> u64 {
+        self.regions[region].hash
+    }
+
+    /// Exchanges the contents of regions `a` and `b`.
+    pub fn swap_regions(&mut self, a: usize, b: usize) {
+        let (hash_a, hash_b) = (self.regions[a].hash, self.regions[b].hash);
+        self.combined ^= region_mix(a, hash_a) ^ region_mix(b, hash_b);
+        self.regions.swap(a, b);
+        self.combined ^= region_mix(a, hash_b) ^ region_mix(b, hash_a);
+    }
+
+    /// Removes every element of `region`.
+    pub fn clear_region(&mut self, region: usize) {
+        self.combined ^= region_mix(region, self.regions[region].hash);
+        self.regions[region] = ZobristHashSet::with_keys(self.keys.clone());
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E> + Clone> RegionZobrist<E, K> {
+    pub fn add(&mut self, region: usize, key: &E) {
+        self.update(region, |set| set.add(key));
+    }
+
+    pub fn remove(&mut self, region: usize, key: &E) {
+        self.update(region, |set| set.remove(key));
+    }
+
+    fn update<R>(&mut self, region: usize, f: impl FnOnce(&mut ZobristHashSet<E, K>) -> R) -> R {
+        let set = &mut self.regions[region];
+        self.combined ^= region_mix(region, set.hash);
+        let result = f(set);
+        self.combined ^= region_mix(region, set.hash);
+        result
+    }
+}
+
+/// Binds a sub-hash to its region. Empty regions contribute nothing.
+fn region_mix(region: usize, hash: u64) -> u64 {
+    let salt = mix64((region as u64).wrapping_add(0x9E37_79B9_7F4A_7C15));
+    mix64(hash ^ salt) ^ mix64(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_contents_same_hash() {
+        let mut a = RegionZobrist::new(3);
+        let mut b = RegionZobrist::new(3);
+        a.add(0, &1u32);
+        a.add(2, &5);
+        a.add(2, &6);
+        b.add(2, &6);
+        b.add(0, &1);
+        b.add(2, &5);
+        b.add(1, &9);
+        b.remove(1, &9);
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.region_hash(2), b.region_hash(2));
+    }
+
+    #[test]
+    fn region_placement_matters() {
+        let mut a = RegionZobrist::new(2);
+        let mut b = RegionZobrist::new(2);
+        a.add(0, &1u32);
+        b.add(1, &1u32);
+        assert_eq!(a.region_hash(0), b.region_hash(1));
+        assert_ne!(a.hash(), b.hash());
+    }
+
+    #[test]
+    fn swap_then_clear() {
+        let mut board = RegionZobrist::new(3);
+        board.add(0, &10u32);
+        board.add(2, &20);
+        board.swap_regions(0, 2);
+        assert_eq!(board.region_hash(0), {
+            let mut expected = ZobristHashSet::empty();
+            expected.add(&20u32);
+            expected.hash
+        });
+
+        board.clear_region(0);
+        let mut expected = RegionZobrist::new(3);
+        expected.add(2, &10u32);
+        assert_eq!(board.hash(), expected.hash());
+
+        // Cleared regions can be refilled without tripping the checker.
+        board.add(0, &20);
+        board.clear_region(2);
+        board.clear_region(0);
+        assert_eq!(board.hash(), 0);
+    }
+}
@@ -0,0 +1,190 @@
+use rustc_hash::FxHashMap;
+
+/// Tracks the position hashes of a game to detect repetitions.
+///
+/// Positions are recorded one ply at a time. A position reached by an irreversible move (a
+/// capture or pawn move in chess) is pushed with [`push_reset`](Self::push_reset): no earlier
+/// position can recur after it, so it starts a new segment and counts only look at the current
+/// one. Every operation is O(1).
+///
+/// Calling [`set_root`](Self::set_root) at the start of a search splits the history into the game
+/// played so far and the current search path. A search can then treat a single repetition along
+/// its own path as a draw, while positions from the game still need
+/// [three occurrences](Self::is_threefold).
+///
+/// ```rust
+/// use zobristhash_set::RepetitionTracker;
+///
+/// let mut tracker = RepetitionTracker::new();
+/// for hash in [1, 2, 1, 2, 1] {
+///     tracker.push(hash);
+/// }
+/// assert_eq!(tracker.count(1), 3);
+/// assert!(tracker.is_threefold());
+///
+/// tracker.pop();
+/// tracker.push_reset(1);
+/// assert_eq!(tracker.count(1), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RepetitionTracker {
+    history: Vec<(u64, bool)>,
+    /// Occurrences keyed by `(segment, hash)`.
+    counts: FxHashMap<(usize, u64), u32>,
+    segment: usize,
+    /// Occurrences of positions pushed after `root`, keyed like `counts`.
+    search_counts: FxHashMap<(usize, u64), u32>,
+    root: usize,
+}
+
+impl RepetitionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of recorded plies.
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    /// Records the position reached by a reversible move.
+    pub fn push(&mut self, hash: u64) {
+        self.push_impl(hash, false);
+    }
+
+    /// Records the position reached by an irreversible move, forgetting earlier positions.
+    pub fn push_reset(&mut self, hash: u64) {
+        self.push_impl(hash, true);
+    }
+
+    fn push_impl(&mut self, hash: u64, reset: bool) {
+        if reset {
+            self.segment += 1;
+        }
+        self.history.push((hash, reset));
+        *self.counts.entry((self.segment, hash)).or_insert(0) += 1;
+        if self.history.len() > self.root {
+            *self.search_counts.entry((self.segment, hash)).or_insert(0) += 1;
+        }
+    }
+
+    /// Removes the last recorded position and returns its hash.
+    pub fn pop(&mut self) -> Option<u64> {
+        let (hash, reset) = self.history.pop()?;
+        let key = (self.segment, hash);
+        decrement(&mut self.counts, key);
+        if self.history.len() >= self.root {
+            decrement(&mut self.search_counts, key);
+        }
+        self.root = self.root.min(self.history.len());
+        if reset {
+            self.segment -= 1;
+        }
+        Some(hash)
+    }
+
+    /// Returns the hash of the current position.
+    pub fn last(&self) -> Option<u64> {
+        self.history.last().map(|&(hash, _)| hash)
+    }
+
+    /// Returns how often `hash` occurred since the last irreversible move.
+    pub fn count(&self, hash: u64) -> u32 {
+        self.counts.get(&(self.segment, hash)).copied().unwrap_or(0)
+    }
+
+    /// Returns `true` if the current position has occurred at least three times.
+    pub fn is_threefold(&self) -> bool {
+        self.last().is_some_and(|hash| self.count(hash) >= 3)
+    }
+
+    /// Marks the current position as the root of a search; positions pushed from now on form the
+    /// search path.
+    pub fn set_root(&mut self) {
+        self.root = self.history.len();
+        self.search_counts.clear();
+    }
+
+    /// Returns how often `hash` occurred on the search path since the last irreversible move.
+    pub fn search_count(&self, hash: u64) -> u32 {
+        self.search_counts
+            .get(&(self.segment, hash))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns `true` if the current position should be scored as a draw inside the search: it
+    /// already occurred on the search path, or it occurred three times overall.
+    pub fn is_search_draw(&self) -> bool {
+        self.last()
+            .is_some_and(|hash| self.search_count(hash) >= 2 || self.count(hash) >= 3)
+    }
+}
+
+fn decrement(counts: &mut FxHashMap<(usize, u64), u32>, key: (usize, u64)) {
+    let count = counts.get_mut(&key).expect("recorded position is counted");
+    *count -= 1;
+    if *count == 0 {
+        counts.remove(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reset_points_are_unwound() {
+        let mut tracker = RepetitionTracker::new();
+        tracker.push(1);
+        tracker.push(2);
+        tracker.push(1);
+        tracker.push_reset(3);
+        tracker.push(1);
+        assert_eq!(tracker.count(1), 1);
+        assert_eq!(tracker.count(2), 0);
+        assert_eq!(tracker.pop(), Some(1));
+        assert_eq!(tracker.pop(), Some(3));
+        assert_eq!(tracker.count(1), 2);
+        tracker.push(2);
+        tracker.push(1);
+        assert!(tracker.is_threefold());
+        while tracker.pop().is_some() {}
+        assert!(tracker.counts.is_empty());
+        assert_eq!(tracker.segment, 0);
+    }
+
+    #[test]
+    fn search_path_layer() {
+        let mut tracker = RepetitionTracker::new();
+        for hash in [1, 2, 3, 2] {
+            tracker.push(hash);
+        }
+        tracker.set_root();
+
+        // A position from the game repeated once more is not yet a draw.
+        tracker.push(1);
+        assert_eq!(tracker.search_count(1), 1);
+        assert!(!tracker.is_search_draw());
+
+        // Repeating a position of the search path is.
+        tracker.push(5);
+        tracker.push(6);
+        tracker.push(5);
+        assert!(tracker.is_search_draw());
+        assert!(!tracker.is_threefold());
+
+        // Unwinding below the root moves the root down with it.
+        for _ in 0..6 {
+            tracker.pop();
+        }
+        assert!(tracker.search_counts.is_empty());
+        tracker.push(2);
+        assert_eq!(tracker.search_count(2), 1);
+        assert_eq!(tracker.count(2), 2);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A piece of state that always holds exactly one value, such as the side to move.
+///
+/// The current value contributes to a parent [`ZobristHashSet`]; [`ScalarFeature::set`] removes
+/// the old value's contribution and adds the new one, so the two can never get out of sync.
+///
+/// ```rust
+/// use zobristhash_set::{ScalarFeature, ZobristHashSet};
+///
+/// #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+/// enum Turn {
+///     Black,
+///     White,
+/// }
+///
+/// let mut hash = ZobristHashSet::<Turn>::empty();
+/// let mut turn = ScalarFeature::new(&mut hash, Turn::Black);
+///
+/// turn.set(&mut hash, Turn::White);
+/// turn.set(&mut hash, Turn::Black);
+///
+/// let mut expected = ZobristHashSet::empty();
+/// expected.add(&Turn::Black);
+/// assert_eq!(u64::from(hash), u64::from(expected));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScalarFeature<T> {
+    value: T,
+}
+
+impl<T: Clone> ScalarFeature<T> {
+    /// Creates the feature and adds `value` to `parent`.
+    pub fn new<E, K>(parent: &mut ZobristHashSet<E, K>, value: T) -> Self
+    where
+        T: Into<E>,
+        E: Hash + Eq + Clone + Debug,
+        K: KeySource<E>,
+    {
+        parent.add(&value.clone().into());
+        Self { value }
+    }
+
+    /// Returns the current value.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Replaces the current value, updating `parent`, and returns the old value.
+    pub fn set<E, K>(&mut self, parent: &mut ZobristHashSet<E, K>, value: T) -> T
+    where
+        T: Into<E>,
+        E: Hash + Eq + Clone + Debug,
+        K: KeySource<E>,
+    {
+        parent.remove(&self.value.clone().into());
+        parent.add(&value.clone().into());
+        std::mem::replace(&mut self.value, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+    enum Element {
+        Stone(u8),
+        EnPassant(u8),
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct EnPassant(u8);
+
+    impl From<EnPassant> for Element {
+        fn from(value: EnPassant) -> Self {
+            Element::EnPassant(value.0)
+        }
+    }
+
+    #[test]
+    fn set_replaces_contribution() {
+        let mut hash = ZobristHashSet::empty();
+        hash.add(&Element::Stone(3));
+        let mut en_passant = ScalarFeature::new(&mut hash, EnPassant(1));
+
+        assert_eq!(en_passant.set(&mut hash, EnPassant(5)), EnPassant(1));
+        assert_eq!(*en_passant.get(), EnPassant(5));
+
+        let mut expected = ZobristHashSet::empty();
+        expected.add(&Element::Stone(3));
+        expected.add(&Element::EnPassant(5));
+        assert_eq!(hash.hash, expected.hash);
+    }
+
+    #[test]
+    fn set_same_value_is_noop() {
+        let mut hash = ZobristHashSet::<u32>::empty();
+        let mut feature = ScalarFeature::new(&mut hash, 7u32);
+        let before = hash.hash;
+        feature.set(&mut hash, 7);
+        assert_eq!(hash.hash, before);
+    }
+}
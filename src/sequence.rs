@@ -0,0 +1,208 @@
+use crate::{FxKeys, KeySource};
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+use std::collections::VecDeque;
+
+const BASE: u64 = 0x9E37_79B9_7F4A_7C15;
+const BASE_INVERSE: u64 = inverse(BASE);
+
+/// Multiplicative inverse of an odd number modulo 2^64, by Newton's iteration.
+const fn inverse(odd: u64) -> u64 {
+    let mut x = odd;
+    let mut i = 0;
+    while i < 5 {
+        x = x.wrapping_mul(2u64.wrapping_sub(odd.wrapping_mul(x)));
+        i += 1;
+    }
+    x
+}
+
+/// An incremental, order-sensitive hash of a sequence.
+///
+/// The digest is the polynomial `k_0 * B^(n-1) + ... + k_(n-1)` modulo 2^64 over the element keys,
+/// with an odd base `B`, so elements can be pushed and popped at both ends in constant time.
+/// Popping requires naming the element being removed; in checked builds the sequence is tracked
+/// and a wrong claim panics.
+///
+/// ```rust
+/// use zobristhash_set::ZobristSequenceHash;
+///
+/// let mut ab = ZobristSequenceHash::empty();
+/// ab.push_back(&'a');
+/// ab.push_back(&'b');
+///
+/// let mut ba = ZobristSequenceHash::empty();
+/// ba.push_back(&'b');
+/// ba.push_front(&'a');
+/// assert_eq!(ab.hash(), ba.hash());
+///
+/// ba.pop_back(&'b');
+/// ba.push_front(&'b');
+/// assert_ne!(ab.hash(), ba.hash());
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    not(all(debug_assertions, feature = "check_set_behavior")),
+    derive(Copy)
+)]
+pub struct ZobristSequenceHash<E, K = FxKeys> {
+    hash: u64,
+    /// `B^len`, the weight of the next element pushed to the front.
+    power: u64,
+    len: usize,
+    keys: K,
+    _data: std::marker::PhantomData<E>,
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    checker: VecDeque<u64>,
+}
+
+impl<E> ZobristSequenceHash<E> {
+    pub fn empty() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E> Default for ZobristSequenceHash<E> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<E, K> ZobristSequenceHash<E, K> {
+    /// Creates an empty sequence that derives element keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        Self {
+            hash: 0,
+            power: 1,
+            len: 0,
+            keys,
+            _data: std::marker::PhantomData,
+            #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+            checker: VecDeque::new(),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<E, K> From<ZobristSequenceHash<E, K>> for u64 {
+    fn from(hash: ZobristSequenceHash<E, K>) -> u64 {
+        hash.hash
+    }
+}
+
+impl<E, K: KeySource<E>> ZobristSequenceHash<E, K> {
+    pub fn push_back(&mut self, element: &E) {
+        let key = self.keys.key(element);
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        self.checker.push_back(key);
+        self.hash = self.hash.wrapping_mul(BASE).wrapping_add(key);
+        self.grow();
+    }
+
+    /// Removes `element`, which must be the last element of the sequence.
+    pub fn pop_back(&mut self, element: &E) {
+        let key = self.keys.key(element);
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        assert_eq!(
+            self.checker.pop_back(),
+            Some(key),
+            "element is not at the back of the sequence"
+        );
+        self.shrink();
+        self.hash = self.hash.wrapping_sub(key).wrapping_mul(BASE_INVERSE);
+    }
+
+    pub fn push_front(&mut self, element: &E) {
+        let key = self.keys.key(element);
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        self.checker.push_front(key);
+        self.hash = self.hash.wrapping_add(key.wrapping_mul(self.power));
+        self.grow();
+    }
+
+    /// Removes `element`, which must be the first element of the sequence.
+    pub fn pop_front(&mut self, element: &E) {
+        let key = self.keys.key(element);
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        assert_eq!(
+            self.checker.pop_front(),
+            Some(key),
+            "element is not at the front of the sequence"
+        );
+        self.shrink();
+        self.hash = self.hash.wrapping_sub(key.wrapping_mul(self.power));
+    }
+
+    fn grow(&mut self) {
+        self.len += 1;
+        self.power = self.power.wrapping_mul(BASE);
+    }
+
+    fn shrink(&mut self) {
+        assert!(self.len > 0, "cannot pop from an empty sequence");
+        self.len -= 1;
+        self.power = self.power.wrapping_mul(BASE_INVERSE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_inverse() {
+        assert_eq!(BASE.wrapping_mul(BASE_INVERSE), 1);
+    }
+
+    #[test]
+    fn order_matters_and_pops_unwind() {
+        let mut hash = ZobristSequenceHash::empty();
+        let mut prefixes = vec![hash.hash()];
+        for i in 0..10u32 {
+            hash.push_back(&i);
+            prefixes.push(hash.hash());
+        }
+
+        let mut reversed = ZobristSequenceHash::empty();
+        for i in 0..10u32 {
+            reversed.push_front(&i);
+        }
+        assert_ne!(hash.hash(), reversed.hash());
+
+        let mut from_front = ZobristSequenceHash::empty();
+        for i in (0..10u32).rev() {
+            from_front.push_front(&i);
+        }
+        assert_eq!(hash.hash(), from_front.hash());
+
+        for i in (0..10u32).rev() {
+            hash.pop_back(&i);
+            assert_eq!(hash.hash(), prefixes[i as usize]);
+        }
+        for i in 0..10u32 {
+            from_front.pop_front(&i);
+        }
+        assert_eq!(from_front.hash(), 0);
+        assert!(from_front.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn pop_wrong_element_debug() {
+        let mut hash = ZobristSequenceHash::empty();
+        hash.push_back(&1u32);
+        hash.push_back(&2u32);
+        hash.pop_back(&1u32);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    BytesKeys, FxKeys, GfMul, KeySource, SplitMixKeys, TrackedZobristHashSet, WordKeys,
+    WrappingAdd, Xor, ZobristHashSet,
+};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::hash::Hash;
+
+/// The version of the way element keys and hash values are derived, stored next to every
+/// serialized hash.
+///
+/// It changes whenever a release would compute a different value for the same set, so hashes
+/// saved by an incompatible release are rejected instead of silently never matching.
+pub const HASH_SCHEME_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ZobristHashSet")]
+struct Stored {
+    version: u32,
+    hash: u64,
+    #[serde(default)]
+    fingerprint: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "TrackedZobristHashSet")]
+struct StoredTracked<T> {
+    version: u32,
+    hash: u64,
+    elements: T,
+}
+
+fn check_version<R: Error>(version: u32) -> Result<(), R> {
+    if version == HASH_SCHEME_VERSION {
+        Ok(())
+    } else {
+        Err(R::custom(format_args!(
+            "hash scheme version {version} is not supported, expected {HASH_SCHEME_VERSION}"
+        )))
+    }
+}
+
+/// Names a key source or combiner in the fingerprint stored with a serialized
+/// [`ZobristHashSet`].
+///
+/// The identifier must change whenever the keys or hash values it stands for change, and only
+/// then: a fingerprint mismatch makes deserialization fail. Identifiers are written out
+/// explicitly rather than derived from type names, which change with compiler versions and
+/// refactorings. Key sources whose keys depend on the pointer width, such as [`FxKeys`], include
+/// it in their identifier.
+///
+/// ```rust
+/// use zobristhash_set::{KeySource, SchemeId, ZobristHashSet};
+///
+/// #[derive(Default)]
+/// struct SquareKeys;
+///
+/// impl KeySource<u8> for SquareKeys {
+///     fn key(&self, square: &u8) -> u64 {
+///         u64::from(*square).wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1
+///     }
+/// }
+///
+/// impl SchemeId for SquareKeys {
+///     const SCHEME_ID: &'static str = "my-engine/square-keys/v1";
+/// }
+///
+/// let mut position = ZobristHashSet::with_keys(SquareKeys);
+/// position.add(&4);
+/// let json = serde_json::to_string(&position).unwrap();
+/// let restored: ZobristHashSet<u8, SquareKeys> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(restored.hash(), position.hash());
+/// ```
+pub trait SchemeId {
+    const SCHEME_ID: &'static str;
+}
+
+impl SchemeId for FxKeys {
+    const SCHEME_ID: &'static str = if cfg!(target_pointer_width = "64") {
+        "zobristhash_set/FxKeys/v1/64"
+    } else {
+        "zobristhash_set/FxKeys/v1/32"
+    };
+}
+
+impl SchemeId for SplitMixKeys {
+    const SCHEME_ID: &'static str = if cfg!(target_pointer_width = "64") {
+        "zobristhash_set/SplitMixKeys/v1/64"
+    } else {
+        "zobristhash_set/SplitMixKeys/v1/32"
+    };
+}
+
+impl SchemeId for BytesKeys {
+    const SCHEME_ID: &'static str = "zobristhash_set/BytesKeys/v1";
+}
+
+impl SchemeId for WordKeys {
+    const SCHEME_ID: &'static str = "zobristhash_set/WordKeys/v1";
+}
+
+impl SchemeId for Xor {
+    const SCHEME_ID: &'static str = "zobristhash_set/Xor/v1";
+}
+
+impl SchemeId for WrappingAdd {
+    const SCHEME_ID: &'static str = "zobristhash_set/WrappingAdd/v1";
+}
+
+impl SchemeId for GfMul {
+    const SCHEME_ID: &'static str = "zobristhash_set/GfMul/v1";
+}
+
+/// Identifies the key source and combiner of a set, the same on every platform and toolchain.
+fn fingerprint<K: SchemeId, C: SchemeId>() -> u64 {
+    BytesKeys.key(&[K::SCHEME_ID, C::SCHEME_ID].join("\n"))
+}
+
+/// Serializes the hash value with [`HASH_SCHEME_VERSION`] and a fingerprint of the
+/// [`SchemeId`]s of the key source and combiner.
+///
+/// The elements are not stored, so a deserialized set has no debug checker. Neither is their
+/// type, which keeps no stable name; a value is accepted by any set with the same key source
+/// and combiner. A stored value without a fingerprint is accepted as well.
+///
+/// ```rust
+/// use zobristhash_set::ZobristHashSet;
+///
+/// let mut position = ZobristHashSet::<(&str, u8)>::empty();
+/// position.add(&("king", 4));
+///
+/// let json = serde_json::to_string(&position).unwrap();
+/// let restored: ZobristHashSet<(&str, u8)> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(restored.hash(), position.hash());
+///
+/// // A set with a different key source refuses the value.
+/// use zobristhash_set::SplitMixKeys;
+/// assert!(serde_json::from_str::<ZobristHashSet<(&str, u8), SplitMixKeys>>(&json).is_err());
+/// ```
+impl<E, K: SchemeId, C: SchemeId, const CHECK_CAPACITY: usize> Serialize
+    for ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Stored {
+            version: HASH_SCHEME_VERSION,
+            hash: self.hash,
+            fingerprint: Some(fingerprint::<K, C>()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, E, K: Default + SchemeId, C: SchemeId, const CHECK_CAPACITY: usize> Deserialize<'de>
+    for ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = Stored::deserialize(deserializer)?;
+        check_version(stored.version)?;
+        if stored
+            .fingerprint
+            .is_some_and(|found| found != fingerprint::<K, C>())
+        {
+            return Err(D::Error::custom(
+                "the hash was computed with a different key source or combiner",
+            ));
+        }
+        Ok(Self::from(stored.hash))
+    }
+}
+
+/// Serializes the elements together with the hash and [`HASH_SCHEME_VERSION`].
+///
+/// Deserializing recomputes the hash from the elements and fails if it differs from the stored
+/// one, so a restored set is always consistent.
+///
+/// ```rust
+/// use zobristhash_set::TrackedZobristHashSet;
+///
+/// let mut pieces = TrackedZobristHashSet::new();
+/// pieces.insert((0u8, 'R'));
+/// pieces.insert((4u8, 'K'));
+///
+/// let json = serde_json::to_string(&pieces).unwrap();
+/// let restored: TrackedZobristHashSet<(u8, char)> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(restored.hash(), pieces.hash());
+/// assert!(restored.contains(&(4, 'K')));
+/// ```
+impl<E: Serialize, K> Serialize for TrackedZobristHashSet<E, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StoredTracked {
+            version: HASH_SCHEME_VERSION,
+            hash: self.hash(),
+            elements: &self.elements,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, E, K> Deserialize<'de> for TrackedZobristHashSet<E, K>
+where
+    E: Deserialize<'de> + Hash + Eq,
+    K: KeySource<E> + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = StoredTracked::<Vec<E>>::deserialize(deserializer)?;
+        check_version(stored.version)?;
+        let mut set = Self::with_capacity_and_keys(stored.elements.len(), K::default());
+        set.extend(stored.elements);
+        if set.hash() != stored.hash {
+            return Err(D::Error::custom(format_args!(
+                "the stored hash {:#018x} does not match the elements, which hash to {:#018x}",
+                stored.hash,
+                set.hash()
+            )));
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FxKeys, SplitMixKeys};
+
+    #[test]
+    fn round_trip_keeps_the_hash() {
+        let mut set = ZobristHashSet::empty();
+        set.add(&3u16);
+        set.add(&9u16);
+        let json = serde_json::to_string(&set).unwrap();
+        let restored: ZobristHashSet<u16> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.hash(), set.hash());
+    }
+
+    #[test]
+    fn incompatible_values_are_rejected() {
+        let json = serde_json::to_string(&ZobristHashSet::<u16>::with_keys(FxKeys)).unwrap();
+        assert!(serde_json::from_str::<ZobristHashSet<u16, SplitMixKeys>>(&json).is_err());
+
+        let future = json.replace(r#""version":1"#, r#""version":2"#);
+        let error = serde_json::from_str::<ZobristHashSet<u16>>(&future).unwrap_err();
+        assert!(error.to_string().contains("version 2"), "{error}");
+
+        let json = serde_json::to_string(
+            &ZobristHashSet::<u16, FxKeys, WrappingAdd>::with_combiner(FxKeys, WrappingAdd),
+        )
+        .unwrap();
+        assert!(serde_json::from_str::<ZobristHashSet<u16>>(&json).is_err());
+
+        let bare = r#"{"version":1,"hash":42}"#;
+        let set: ZobristHashSet<u16> = serde_json::from_str(bare).unwrap();
+        assert_eq!(set.hash(), 42);
+    }
+
+    #[test]
+    fn fingerprints_are_pinned() {
+        // Stored values carry these, so they must not change between releases or platforms.
+        assert_eq!(fingerprint::<BytesKeys, Xor>(), 0xdbba_afda_bcb7_c4e0);
+    }
+
+    #[test]
+    fn tracked_round_trip_verifies_the_hash() {
+        let set: TrackedZobristHashSet<u16> = (0..20).collect();
+        let json = serde_json::to_string(&set).unwrap();
+        let restored: TrackedZobristHashSet<u16> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.hash(), set.hash());
+        assert_eq!(restored.len(), 20);
+
+        let mut tampered: serde_json::Value = serde_json::from_str(&json).unwrap();
+        tampered["hash"] = (set.hash() ^ 1).into();
+        let error = serde_json::from_value::<TrackedZobristHashSet<u16>>(tampered).unwrap_err();
+        assert!(error.to_string().contains("does not match"), "{error}");
+    }
+}
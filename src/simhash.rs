@@ -0,0 +1,141 @@
+use crate::keys::mix64;
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash paired with a SimHash fingerprint.
+///
+/// Every element votes on each of the 64 fingerprint bits, and a bit is set when more elements
+/// voted for it than against it. Unlike the Zobrist value, which changes completely with every
+/// update, sets sharing most of their elements get fingerprints at a small
+/// [Hamming distance](Self::hamming_distance), so the fingerprint can bucket roughly similar
+/// states.
+///
+/// ```rust
+/// use zobristhash_set::SimHashZobristSet;
+///
+/// let mut a = SimHashZobristSet::new();
+/// let mut b = SimHashZobristSet::new();
+/// for i in 0..200u32 {
+///     a.add(&i);
+///     b.add(&i);
+/// }
+/// b.add(&1000);
+/// assert_ne!(a.hash(), b.hash());
+/// assert!(a.hamming_distance(&b) < 8);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SimHashZobristSet<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    votes: [i32; 64],
+}
+
+impl<E> SimHashZobristSet<E> {
+    pub fn new() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E> Default for SimHashZobristSet<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, K> SimHashZobristSet<E, K> {
+    pub fn with_keys(keys: K) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            votes: [0; 64],
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the SimHash fingerprint of the set.
+    pub fn simhash(&self) -> u64 {
+        self.votes
+            .iter()
+            .enumerate()
+            .filter(|&(_, &votes)| votes > 0)
+            .fold(0, |fingerprint, (bit, _)| fingerprint | 1 << bit)
+    }
+
+    /// Returns the number of fingerprint bits in which `self` and `other` differ.
+    pub fn hamming_distance(&self, other: &Self) -> u32 {
+        (self.simhash() ^ other.simhash()).count_ones()
+    }
+
+    /// Returns the `index`th of the `bands` equally wide slices of the fingerprint.
+    ///
+    /// Fingerprints at Hamming distance below `bands` agree on at least one band, so indexing
+    /// sets by every band finds all near neighbours.
+    pub fn band(&self, index: u32, bands: u32) -> u64 {
+        assert!(index < bands && bands <= 64, "band index out of range");
+        let width = 64 / bands;
+        let band = self.simhash() >> (index * width);
+        if width == 64 {
+            band
+        } else {
+            band & ((1 << width) - 1)
+        }
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> SimHashZobristSet<E, K> {
+    pub fn add(&mut self, key: &E) {
+        let element_key = self.set.add(key);
+        self.vote(mix64(element_key), 1);
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        let element_key = self.set.remove(key);
+        self.vote(mix64(element_key), -1);
+    }
+
+    fn vote(&mut self, h: u64, weight: i32) {
+        for (bit, votes) in self.votes.iter_mut().enumerate() {
+            *votes += if h >> bit & 1 == 1 { weight } else { -weight };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_grows_with_difference() {
+        let mut base = SimHashZobristSet::new();
+        for i in 0..500u32 {
+            base.add(&i);
+        }
+        let mut near = base.clone();
+        let mut far = base.clone();
+        for i in 0..10u32 {
+            near.remove(&i);
+        }
+        for i in 0..400u32 {
+            far.remove(&i);
+            far.add(&(i + 10_000));
+        }
+        assert!(base.hamming_distance(&near) < base.hamming_distance(&far));
+        for i in 0..10u32 {
+            near.add(&i);
+        }
+        assert_eq!(near.simhash(), base.simhash());
+    }
+
+    #[test]
+    fn bands_cover_the_fingerprint() {
+        let mut set = SimHashZobristSet::new();
+        for i in 0..50u32 {
+            set.add(&i);
+        }
+        let joined = (0..4).fold(0, |acc, i| acc | set.band(i, 4) << (16 * i));
+        assert_eq!(joined, set.simhash());
+        assert_eq!(set.band(0, 1), set.simhash());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::keys::mix64;
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash paired with a k-minimum-values sketch, estimating the Jaccard similarity of
+/// two sets without storing their elements.
+///
+/// The sketch holds the element hashes below a threshold, at most `k` of them; when an
+/// [`add`](Self::add) would exceed `k`, the threshold drops to the largest hash, which is
+/// evicted. Removing an element only deletes its hash, so the sketch stays an exact sample of
+/// the current set but shrinks until later additions refill it. The standard error of
+/// [`jaccard`](Self::jaccard) is about 1 / √k.
+///
+/// ```rust
+/// use zobristhash_set::SimilaritySketch;
+///
+/// let mut a = SimilaritySketch::with_k(256);
+/// let mut b = SimilaritySketch::with_k(256);
+/// for i in 0..1000u32 {
+///     a.add(&i);
+///     b.add(&(i + 500));
+/// }
+/// // 500 shared elements out of 1500.
+/// assert!((a.jaccard(&b).unwrap() - 1.0 / 3.0).abs() < 0.1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SimilaritySketch<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    sample: BTreeSet<u64>,
+    threshold: u64,
+    k: usize,
+}
+
+impl<E> SimilaritySketch<E> {
+    /// Creates an empty set whose sketch keeps up to `k` hashes.
+    pub fn with_k(k: usize) -> Self {
+        Self::with_keys(FxKeys, k)
+    }
+}
+
+impl<E, K> SimilaritySketch<E, K> {
+    /// Like [`with_k`](SimilaritySketch::with_k), deriving keys from `keys`.
+    pub fn with_keys(keys: K, k: usize) -> Self {
+        assert!(k > 0, "k must be positive");
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            sample: BTreeSet::new(),
+            threshold: u64::MAX,
+            k,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the number of hashes currently in the sketch.
+    pub fn sample_len(&self) -> usize {
+        self.sample.len()
+    }
+
+    /// Estimates |A ∩ B| / |A ∪ B| of the sets sketched by `self` and `other`.
+    ///
+    /// Two empty sets are considered identical. Returns `None` when the sets differ but neither
+    /// sample holds a hash below both thresholds, as happens after removals have emptied a
+    /// sample, since the sketches then say nothing about the overlap.
+    pub fn jaccard(&self, other: &Self) -> Option<f64> {
+        if self.set.hash == other.set.hash && self.sample == other.sample {
+            return Some(1.0);
+        }
+        // Below the lower threshold both samples are exact, so together they sample the union.
+        let threshold = self.threshold.min(other.threshold);
+        let ours = self.sample.range(..threshold);
+        let theirs = other.sample.range(..threshold);
+        let (mut shared, mut union) = (0usize, 0usize);
+        let (mut ours, mut theirs) = (ours.peekable(), theirs.peekable());
+        loop {
+            match (ours.peek(), theirs.peek()) {
+                (Some(a), Some(b)) if a == b => {
+                    shared += 1;
+                    ours.next();
+                    theirs.next();
+                }
+                (Some(a), Some(b)) if a < b => {
+                    ours.next();
+                }
+                (Some(_), Some(_)) => {
+                    theirs.next();
+                }
+                (Some(_), None) => {
+                    ours.next();
+                }
+                (None, Some(_)) => {
+                    theirs.next();
+                }
+                (None, None) => break,
+            }
+            union += 1;
+        }
+        (union > 0).then(|| shared as f64 / union as f64)
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> SimilaritySketch<E, K> {
+    pub fn add(&mut self, key: &E) {
+        let h = mix64(self.set.add(key));
+        if h < self.threshold {
+            self.sample.insert(h);
+            if self.sample.len() > self.k {
+                self.threshold = self.sample.pop_last().expect("sample is not empty");
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        let h = mix64(self.set.remove(key));
+        self.sample.remove(&h);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removal_keeps_the_sample_exact() {
+        let mut a = SimilaritySketch::with_k(64);
+        let mut b = SimilaritySketch::with_k(64);
+        for i in 0..2000u32 {
+            a.add(&i);
+        }
+        for i in 1000..2000u32 {
+            a.remove(&i);
+        }
+        for i in 0..1000u32 {
+            b.add(&i);
+        }
+        assert!(a.sample_len() < 64);
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.jaccard(&b), Some(1.0));
+    }
+
+    #[test]
+    fn disjoint_and_empty_sets() {
+        let mut a = SimilaritySketch::with_k(32);
+        let mut b = SimilaritySketch::with_k(32);
+        assert_eq!(a.jaccard(&b), Some(1.0));
+        for i in 0..100u32 {
+            a.add(&i);
+            b.add(&(i + 100));
+        }
+        assert_eq!(a.jaccard(&b), Some(0.0));
+    }
+
+    #[test]
+    fn emptied_samples_give_no_estimate() {
+        let mut emptied = SimilaritySketch::with_k(4);
+        for i in 0..2000u32 {
+            emptied.add(&i);
+        }
+        for i in 0..2000u32 {
+            emptied.remove(&i);
+        }
+        let mut other = SimilaritySketch::with_k(4);
+        other.add(&5000u32);
+        assert_eq!(emptied.sample_len(), 0);
+        assert_eq!(emptied.jaccard(&other), None);
+        assert_eq!(other.jaccard(&emptied), None);
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+use crate::copiable_hash::CopiableHash;
+use crate::{FxKeys, Xor, ZobristHashSet, DEFAULT_CHECK_CAPACITY};
+use core::marker::PhantomData;
+
+/// The state of a [`ZobristHashSet`] at some point, to return to with
+/// [`restore`](ZobristHashSet::restore).
+///
+/// Taking and restoring a snapshot costs the same at any set size. With the debug checker it
+/// also holds the tracked elements, so that the checker agrees with the restored hash; they are
+/// shared with the set, and copied only when the set is next updated.
+///
+/// It is `Clone` but not `Copy` in every build, so code that compiles without the checker
+/// compiles with it too.
+#[derive(Clone, Debug)]
+pub struct Snapshot<E, K = FxKeys, C = Xor, const CHECK_CAPACITY: usize = DEFAULT_CHECK_CAPACITY> {
+    hash: u64,
+    #[cfg(feature = "count_guard")]
+    len_delta: i64,
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    checker: Option<CopiableHash<E, CHECK_CAPACITY>>,
+    _marker: PhantomData<fn(&E, &K, &C)>,
+}
+
+impl<E, K, C, const CHECK_CAPACITY: usize> Snapshot<E, K, C, CHECK_CAPACITY> {
+    /// Returns the hash value the snapshot restores.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl<E: Clone, K, C, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, C, CHECK_CAPACITY> {
+    /// Captures the current hash and checker state.
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// let mut position = ZobristHashSet::empty();
+    /// position.add(&("knight", 1));
+    /// let before = position.snapshot();
+    ///
+    /// // Explore a line, then return to the earlier position.
+    /// position.remove(&("knight", 1));
+    /// position.add(&("knight", 18));
+    /// position.restore(&before);
+    ///
+    /// assert_eq!(position.hash(), before.hash());
+    /// position.remove(&("knight", 1)); // the checker knows the knight is back
+    /// ```
+    pub fn snapshot(&self) -> Snapshot<E, K, C, CHECK_CAPACITY> {
+        Snapshot {
+            hash: self.hash,
+            #[cfg(feature = "count_guard")]
+            len_delta: self.len_delta,
+            #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+            checker: self.checker.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns to the state captured by `snapshot`, undoing every update made since.
+    pub fn restore(&mut self, snapshot: &Snapshot<E, K, C, CHECK_CAPACITY>) {
+        self.hash = snapshot.hash;
+        #[cfg(feature = "count_guard")]
+        {
+            self.len_delta = snapshot.len_delta;
+        }
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        {
+            self.checker.clone_from(&snapshot.checker);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(op = "restore", hash = self.hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ZobristHashSet;
+
+    #[test]
+    fn restore_rolls_back_updates() {
+        let mut set = ZobristHashSet::empty();
+        set.add(&1u32);
+        let snapshot = set.snapshot();
+        for i in 2..100 {
+            set.add(&i);
+        }
+        set.remove(&1);
+        set.restore(&snapshot);
+        assert_eq!(set.hash(), snapshot.hash());
+
+        // The set can be restored again, and the checker accepts updates from the snapshot.
+        set.add(&2);
+        set.restore(&snapshot);
+        set.add(&2);
+        set.remove(&1);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate add of 1")]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn restored_checker_tracks_snapshot_elements() {
+        let mut set = ZobristHashSet::empty();
+        set.add(&1u32);
+        let snapshot = set.snapshot();
+        set.remove(&1);
+        set.restore(&snapshot);
+        set.add(&1);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{Combiner, KeySource, Op, ZobristHashSet, DEFAULT_CHECK_CAPACITY};
+use core::cmp::Ordering;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::iter::Peekable;
+
+impl<E, K, C, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, C, CHECK_CAPACITY> {
+    /// Moves the set from the full state `old` to the full state `new`, yielding the changes.
+    ///
+    /// Both states must list their elements in strictly ascending order. They are merged in a
+    /// single pass without buffering, so the diff of two large dumps needs constant memory. Each
+    /// element only in `old` is removed from the set and yielded as [`Op::Remove`], each element
+    /// only in `new` is added and yielded as [`Op::Add`]. The set is updated as the iterator is
+    /// advanced, so it describes `new` only once the iterator is exhausted.
+    ///
+    /// ```rust
+    /// use zobristhash_set::{Op, ZobristHashSet};
+    ///
+    /// let old = [1, 2, 3, 5];
+    /// let new = [2, 3, 4];
+    /// let mut set = ZobristHashSet::rebuild_from(&old);
+    ///
+    /// let ops: Vec<_> = set.diff_states(old, new).collect();
+    /// assert_eq!(ops, [Op::Remove(1), Op::Add(4), Op::Remove(5)]);
+    /// assert_eq!(set.verify(&new), Ok(()));
+    /// ```
+    pub fn diff_states<I, J>(
+        &mut self,
+        old: I,
+        new: J,
+    ) -> StateDiff<'_, E, K, C, I::IntoIter, J::IntoIter, CHECK_CAPACITY>
+    where
+        I: IntoIterator<Item = E>,
+        J: IntoIterator<Item = E>,
+    {
+        StateDiff {
+            set: self,
+            old: old.into_iter().peekable(),
+            new: new.into_iter().peekable(),
+        }
+    }
+}
+
+/// The iterator returned by [`ZobristHashSet::diff_states`].
+pub struct StateDiff<
+    'a,
+    E,
+    K,
+    C,
+    I: Iterator<Item = E>,
+    J: Iterator<Item = E>,
+    const CHECK_CAPACITY: usize = DEFAULT_CHECK_CAPACITY,
+> {
+    set: &'a mut ZobristHashSet<E, K, C, CHECK_CAPACITY>,
+    old: Peekable<I>,
+    new: Peekable<J>,
+}
+
+impl<E, K, C, I, J, const CHECK_CAPACITY: usize> Iterator
+    for StateDiff<'_, E, K, C, I, J, CHECK_CAPACITY>
+where
+    E: Ord + Hash + Eq + Clone + Debug,
+    K: KeySource<E>,
+    C: Combiner,
+    I: Iterator<Item = E>,
+    J: Iterator<Item = E>,
+{
+    type Item = Op<E>;
+
+    fn next(&mut self) -> Option<Op<E>> {
+        loop {
+            let order = match (self.old.peek(), self.new.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(old), Some(new)) => old.cmp(new),
+            };
+            match order {
+                Ordering::Less => {
+                    let element = next_ascending(&mut self.old);
+                    self.set.remove(&element);
+                    return Some(Op::Remove(element));
+                }
+                Ordering::Greater => {
+                    let element = next_ascending(&mut self.new);
+                    self.set.add(&element);
+                    return Some(Op::Add(element));
+                }
+                Ordering::Equal => {
+                    next_ascending(&mut self.old);
+                    next_ascending(&mut self.new);
+                }
+            }
+        }
+    }
+}
+
+fn next_ascending<E: Ord, I: Iterator<Item = E>>(elements: &mut Peekable<I>) -> E {
+    let element = elements.next().expect("peeked element");
+    debug_assert!(
+        elements.peek().is_none_or(|next| element < *next),
+        "states must be sorted in strictly ascending order"
+    );
+    element
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Op, ZobristHashSet};
+
+    #[test]
+    fn diff_moves_between_states() {
+        let old: Vec<u32> = (0..100).filter(|i| i % 3 != 0).collect();
+        let new: Vec<u32> = (50..150).filter(|i| i % 2 != 0).collect();
+        let mut set = ZobristHashSet::rebuild_from(&old);
+
+        let ops: Vec<_> = set.diff_states(old.clone(), new.clone()).collect();
+        assert_eq!(set.verify(&new), Ok(()));
+
+        let mut replayed = ZobristHashSet::rebuild_from(&old);
+        replayed.apply_ops(&ops);
+        assert_eq!(replayed.hash(), set.hash());
+        assert!(ops.iter().all(|op| match op {
+            Op::Add(i) => !old.contains(i) && new.contains(i),
+            Op::Remove(i) => old.contains(i) && !new.contains(i),
+            _ => false,
+        }));
+
+        assert_eq!(set.diff_states(new.clone(), new).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly ascending")]
+    #[cfg(debug_assertions)]
+    fn unsorted_states_are_reported() {
+        let mut set = ZobristHashSet::rebuild_from(&[2u32, 1]);
+        set.diff_states([2, 1], []).for_each(drop);
+    }
+}
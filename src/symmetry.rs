@@ -0,0 +1,192 @@
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// One of the eight symmetries of a square board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    FlipDiagonal,
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// Maps the point `(x, y)` of a `size` by `size` board.
+    pub fn apply(self, x: usize, y: usize, size: usize) -> (usize, usize) {
+        let last = size - 1;
+        match self {
+            Symmetry::Identity => (x, y),
+            Symmetry::Rotate90 => (last - y, x),
+            Symmetry::Rotate180 => (last - x, last - y),
+            Symmetry::Rotate270 => (y, last - x),
+            Symmetry::FlipHorizontal => (last - x, y),
+            Symmetry::FlipVertical => (x, last - y),
+            Symmetry::FlipDiagonal => (y, x),
+            Symmetry::FlipAntiDiagonal => (last - y, last - x),
+        }
+    }
+}
+
+/// A Zobrist hash maintained under all eight board symmetries at once.
+///
+/// Every added or removed element is also mapped through a user-supplied transform for each
+/// non-identity [`Symmetry`], keeping one hash per symmetry. [`SymmetricZobrist::canonical_hash`]
+/// is the minimum of the eight, so positions that are rotations or reflections of each other
+/// share a canonical hash.
+///
+/// ```rust
+/// use zobristhash_set::{Symmetry, SymmetricZobrist};
+///
+/// fn transform(&(x, y): &(usize, usize), symmetry: Symmetry) -> (usize, usize) {
+///     symmetry.apply(x, y, 3)
+/// }
+///
+/// let mut corner = SymmetricZobrist::new(transform);
+/// corner.add(&(0, 0));
+/// let mut other_corner = SymmetricZobrist::new(transform);
+/// other_corner.add(&(2, 0));
+///
+/// assert_ne!(corner.hash(), other_corner.hash());
+/// assert_eq!(corner.canonical_hash(), other_corner.canonical_hash());
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    not(all(debug_assertions, feature = "check_set_behavior")),
+    derive(Copy)
+)]
+pub struct SymmetricZobrist<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    transformed: [u64; 7],
+    transform: fn(&E, Symmetry) -> E,
+}
+
+impl<E> SymmetricZobrist<E> {
+    /// Creates an empty hash that maps elements with `transform`.
+    pub fn new(transform: fn(&E, Symmetry) -> E) -> Self {
+        Self::with_keys(FxKeys, transform)
+    }
+}
+
+impl<E, K> SymmetricZobrist<E, K> {
+    /// Creates an empty hash that maps elements with `transform` and derives keys from `keys`.
+    pub fn with_keys(keys: K, transform: fn(&E, Symmetry) -> E) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            transformed: [0; 7],
+            transform,
+        }
+    }
+
+    /// Returns the hash of the untransformed elements.
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the hash of the elements transformed by `symmetry`.
+    pub fn hash_under(&self, symmetry: Symmetry) -> u64 {
+        match symmetry {
+            Symmetry::Identity => self.set.hash,
+            _ => self.transformed[symmetry as usize - 1],
+        }
+    }
+
+    /// Returns the smallest of the eight symmetric hashes.
+    pub fn canonical_hash(&self) -> u64 {
+        self.transformed
+            .iter()
+            .fold(self.set.hash, |a, &b| a.min(b))
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> SymmetricZobrist<E, K> {
+    pub fn add(&mut self, key: &E) {
+        self.set.add(key);
+        self.toggle_transformed(key);
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        self.set.remove(key);
+        self.toggle_transformed(key);
+    }
+
+    fn toggle_transformed(&mut self, key: &E) {
+        for (hash, symmetry) in self.transformed.iter_mut().zip(&Symmetry::ALL[1..]) {
+            *hash ^= self.set.keys.key(&(self.transform)(key, *symmetry));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = 5;
+
+    fn transform(&(x, y, c): &(usize, usize, u8), symmetry: Symmetry) -> (usize, usize, u8) {
+        let (x, y) = symmetry.apply(x, y, SIZE);
+        (x, y, c)
+    }
+
+    #[test]
+    fn symmetries_are_permutations() {
+        for symmetry in Symmetry::ALL {
+            let mut seen = std::collections::HashSet::new();
+            for x in 0..SIZE {
+                for y in 0..SIZE {
+                    assert!(seen.insert(symmetry.apply(x, y, SIZE)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hash_under_matches_transformed_position() {
+        let stones = [(0, 1, 1), (3, 4, 2), (2, 2, 1)];
+        let mut hash = SymmetricZobrist::new(transform);
+        for stone in &stones {
+            hash.add(stone);
+        }
+        for symmetry in Symmetry::ALL {
+            let mut expected = ZobristHashSet::empty();
+            for stone in &stones {
+                expected.add(&transform(stone, symmetry));
+            }
+            assert_eq!(hash.hash_under(symmetry), expected.hash);
+        }
+    }
+
+    #[test]
+    fn canonical_hash_is_symmetric() {
+        let stones = [(0, 1, 1), (3, 4, 2), (2, 3, 1)];
+        for symmetry in Symmetry::ALL {
+            let mut original = SymmetricZobrist::new(transform);
+            let mut mapped = SymmetricZobrist::new(transform);
+            for stone in &stones {
+                original.add(stone);
+                mapped.add(&transform(stone, symmetry));
+            }
+            assert_eq!(original.canonical_hash(), mapped.canonical_hash());
+        }
+
+        let mut hash = SymmetricZobrist::new(transform);
+        hash.add(&(1, 1, 1));
+        hash.remove(&(1, 1, 1));
+        assert_eq!(hash.canonical_hash(), 0);
+    }
+}
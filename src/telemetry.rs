@@ -0,0 +1,56 @@
+use metrics::Counter;
+use std::sync::OnceLock;
+
+/// Defines a function returning the handle of a counter, registered with the global recorder
+/// the first time it is needed.
+///
+/// Updates then increment the handle directly instead of looking the counter up by name. A
+/// recorder must be installed before the first update it should see.
+macro_rules! counter_handle {
+    ($(#[$doc:meta])* $name:ident, $key:literal $(, $label:literal => $value:literal)*) => {
+        $(#[$doc])*
+        pub(crate) fn $name() -> &'static Counter {
+            static HANDLE: OnceLock<Counter> = OnceLock::new();
+            HANDLE.get_or_init(|| metrics::counter!($key $(, $label => $value)*))
+        }
+    };
+}
+
+counter_handle!(
+    /// Elements added to any set.
+    adds,
+    "zobristhash_set.adds"
+);
+counter_handle!(
+    /// Elements removed from any set.
+    removes,
+    "zobristhash_set.removes"
+);
+counter_handle!(
+    /// Transposition table probes that found their entry.
+    tt_hits,
+    "zobristhash_set.tt.hits"
+);
+counter_handle!(
+    /// Transposition table probes that found nothing.
+    tt_misses,
+    "zobristhash_set.tt.misses"
+);
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+counter_handle!(
+    duplicate_adds,
+    "zobristhash_set.checker_violations",
+    "kind" => "duplicate_add"
+);
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+counter_handle!(
+    missing_removes,
+    "zobristhash_set.checker_violations",
+    "kind" => "missing_remove"
+);
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+counter_handle!(
+    exhausted_capacity,
+    "zobristhash_set.checker_violations",
+    "kind" => "checker_capacity"
+);
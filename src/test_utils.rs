@@ -0,0 +1,88 @@
+//! Helpers for testing code that keeps Zobrist hashes, built on the crate's own checks.
+
+use crate::{apply_reference_ops, FxKeys, KeySource, Op, ZobristHashSet};
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Replays `ops` on a [`ZobristHashSet`] and on a reference [`HashSet`], panicking at the first
+/// operation after which they disagree.
+///
+/// After every operation the hash must match the one recomputed from the reference, and it must
+/// be the empty hash whenever the reference is empty. The converse is not checked: an element
+/// whose key is zero, such as `0u8` under [`FxKeys`], leaves the hash unchanged.
+///
+/// Operations the reference ignores, adding a present element or removing an absent one, are
+/// skipped on the Zobrist side too, so any sequence is accepted.
+///
+/// ```rust
+/// use zobristhash_set::test_utils::check_consistency;
+/// use zobristhash_set::Op;
+///
+/// check_consistency(&[Op::Add("a"), Op::Add("a"), Op::Toggle("b"), Op::Remove("a"), Op::Clear]);
+/// ```
+pub fn check_consistency<E>(ops: &[Op<E>])
+where
+    E: Hash + Eq + Clone + Debug,
+{
+    check_consistency_with_keys(FxKeys, ops);
+}
+
+/// Like [`check_consistency`], but derives element keys from `keys`.
+pub fn check_consistency_with_keys<E, K>(keys: K, ops: &[Op<E>])
+where
+    E: Hash + Eq + Clone + Debug,
+    K: KeySource<E>,
+{
+    let mut hash = ZobristHashSet::with_keys(keys);
+    let empty = hash.hash();
+    let mut reference = HashSet::new();
+    for (step, op) in ops.iter().enumerate() {
+        let ignored = match op {
+            Op::Add(element) => reference.contains(element),
+            Op::Remove(element) => !reference.contains(element),
+            Op::Clear | Op::Toggle(_) => false,
+        };
+        if ignored {
+            continue;
+        }
+        let op = std::slice::from_ref(op);
+        hash.apply_ops(op);
+        apply_reference_ops(&mut reference, op);
+
+        if let Err(error) = hash.verify(&reference) {
+            panic!("after step {step} ({:?}): {error}", op[0]);
+        }
+        assert!(
+            !reference.is_empty() || hash.hash() == empty,
+            "after step {step} ({:?}): the reference is empty but the hash is {:#018x}",
+            op[0],
+            hash.hash()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn random_sequences_are_consistent() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let ops: Vec<Op<u8>> = (0..100)
+                .map(|_| {
+                    let element = rng.gen_range(0..8);
+                    match rng.gen_range(0..10) {
+                        0 => Op::Clear,
+                        1..=3 => Op::Add(element),
+                        4..=6 => Op::Remove(element),
+                        _ => Op::Toggle(element),
+                    }
+                })
+                .collect();
+            check_consistency(&ops);
+        }
+    }
+}
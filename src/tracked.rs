@@ -0,0 +1,367 @@
+use crate::{FxKeys, KeySource};
+use rustc_hash::FxHashSet;
+use std::hash::Hash;
+
+/// A set that stores its elements and keeps their Zobrist hash up to date.
+///
+/// It offers the usual collection API together with the incremental [`hash`](Self::hash).
+/// Since the elements are stored, inserting a present element or removing an absent one is not
+/// a mistake but simply leaves the set and its hash unchanged, as with
+/// [`std::collections::HashSet`]. The hash equals that of a
+/// [`ZobristHashSet`](crate::ZobristHashSet) with the same key source and elements.
+///
+/// ```rust
+/// use zobristhash_set::{TrackedZobristHashSet, ZobristHashSet};
+///
+/// let mut pieces = TrackedZobristHashSet::new();
+/// assert!(pieces.insert((0, 'R')));
+/// assert!(pieces.insert((4, 'K')));
+/// assert!(!pieces.insert((4, 'K')));
+/// assert!(pieces.contains(&(0, 'R')));
+/// assert_eq!(pieces.len(), 2);
+///
+/// let mut expected = ZobristHashSet::empty();
+/// expected.add(&(0, 'R'));
+/// expected.add(&(4, 'K'));
+/// assert_eq!(pieces.hash(), u64::from(expected));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TrackedZobristHashSet<E, K = FxKeys> {
+    pub(crate) elements: FxHashSet<E>,
+    hash: u64,
+    pub(crate) keys: K,
+}
+
+impl<E> TrackedZobristHashSet<E> {
+    pub fn new() -> Self {
+        Self::with_keys(FxKeys)
+    }
+
+    /// Creates an empty set with room for at least `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_keys(capacity, FxKeys)
+    }
+}
+
+impl<E> Default for TrackedZobristHashSet<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, K> TrackedZobristHashSet<E, K> {
+    /// Creates an empty set that derives element keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        Self::with_capacity_and_keys(0, keys)
+    }
+
+    /// Creates an empty set with room for at least `capacity` elements, deriving element keys
+    /// from `keys`.
+    pub fn with_capacity_and_keys(capacity: usize, keys: K) -> Self {
+        Self {
+            elements: FxHashSet::with_capacity_and_hasher(capacity, Default::default()),
+            hash: 0,
+            keys,
+        }
+    }
+
+    /// Returns the number of elements the set can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.elements.capacity()
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn iter(&self) -> std::collections::hash_set::Iter<'_, E> {
+        self.elements.iter()
+    }
+
+    /// Removes every element and returns them in arbitrary order.
+    ///
+    /// The set and its hash are empty as soon as this is called, even if the iterator is not
+    /// consumed.
+    pub fn drain(&mut self) -> std::collections::hash_set::Drain<'_, E> {
+        self.hash = 0;
+        self.elements.drain()
+    }
+
+    /// Removes every element.
+    pub fn clear(&mut self) {
+        self.elements.clear();
+        self.hash = 0;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(op = "clear", hash = self.hash);
+    }
+}
+
+impl<E: Hash + Eq, K: KeySource<E>> TrackedZobristHashSet<E, K> {
+    /// Adds `element`, returning `false` if it was already present.
+    pub fn insert(&mut self, element: E) -> bool {
+        let key = self.keys.key(&element);
+        let inserted = self.elements.insert(element);
+        if inserted {
+            self.hash ^= key;
+            #[cfg(feature = "tracing")]
+            tracing::trace!(op = "add", key, hash = self.hash);
+        }
+        inserted
+    }
+
+    /// Removes `element`, returning `false` if it was not present.
+    pub fn remove(&mut self, element: &E) -> bool {
+        let removed = self.elements.remove(element);
+        if removed {
+            let key = self.keys.key(element);
+            self.hash ^= key;
+            #[cfg(feature = "tracing")]
+            tracing::trace!(op = "remove", key, hash = self.hash);
+        }
+        removed
+    }
+
+    /// Reserves room for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
+        self.elements.reserve(additional);
+    }
+
+    /// Shrinks the backing storage as much as possible, e.g. to release memory after a large
+    /// search.
+    pub fn shrink_to_fit(&mut self) {
+        self.elements.shrink_to_fit();
+    }
+
+    pub fn contains(&self, element: &E) -> bool {
+        self.elements.contains(element)
+    }
+
+    /// Keeps only the elements for which `keep` returns `true`, removing the others from the hash
+    /// as they are dropped.
+    pub fn retain(&mut self, mut keep: impl FnMut(&E) -> bool) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("retain", len = self.elements.len()).entered();
+        let (keys, hash) = (&self.keys, &mut self.hash);
+        self.elements.retain(|element| {
+            let kept = keep(element);
+            if !kept {
+                *hash ^= keys.key(element);
+            }
+            kept
+        });
+    }
+}
+
+impl<E: Hash + Eq + Clone, K: KeySource<E>> TrackedZobristHashSet<E, K> {
+    /// Adds every element of `other`.
+    pub fn union_with(&mut self, other: &Self) {
+        for element in &other.elements {
+            if !self.elements.contains(element) {
+                self.insert(element.clone());
+            }
+        }
+    }
+
+    /// Removes every element that is not in `other`.
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.retain(|element| other.elements.contains(element));
+    }
+
+    /// Removes every element of `other`.
+    pub fn difference_with(&mut self, other: &Self) {
+        for element in &other.elements {
+            self.remove(element);
+        }
+    }
+
+    /// Removes the elements of `other` that are present and adds those that are not.
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        for element in &other.elements {
+            if !self.remove(element) {
+                self.insert(element.clone());
+            }
+        }
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.elements.is_subset(&other.elements)
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        self.elements.is_superset(&other.elements)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.elements.is_disjoint(&other.elements)
+    }
+}
+
+impl<E: Hash + Eq + Clone, K: KeySource<E> + Clone> TrackedZobristHashSet<E, K> {
+    /// Returns the elements in `self` or `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.union_with(other);
+        result
+    }
+
+    /// Returns the elements in both `self` and `other`.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.intersect_with(other);
+        result
+    }
+
+    /// Returns the elements in `self` but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.difference_with(other);
+        result
+    }
+
+    /// Returns the elements in exactly one of `self` and `other`.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.symmetric_difference_with(other);
+        result
+    }
+}
+
+impl<E: Hash + Eq, K> PartialEq for TrackedZobristHashSet<E, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.elements == other.elements
+    }
+}
+
+impl<E: Hash + Eq, K> Eq for TrackedZobristHashSet<E, K> {}
+
+impl<E, K> From<TrackedZobristHashSet<E, K>> for u64 {
+    fn from(set: TrackedZobristHashSet<E, K>) -> u64 {
+        set.hash
+    }
+}
+
+impl<E: Hash + Eq, K: KeySource<E>> Extend<E> for TrackedZobristHashSet<E, K> {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, elements: I) {
+        for element in elements {
+            self.insert(element);
+        }
+    }
+}
+
+impl<E: Hash + Eq> FromIterator<E> for TrackedZobristHashSet<E> {
+    fn from_iter<I: IntoIterator<Item = E>>(elements: I) -> Self {
+        let mut set = Self::new();
+        set.extend(elements);
+        set
+    }
+}
+
+impl<'a, E, K> IntoIterator for &'a TrackedZobristHashSet<E, K> {
+    type Item = &'a E;
+    type IntoIter = std::collections::hash_set::Iter<'a, E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+
+impl<E, K> IntoIterator for TrackedZobristHashSet<E, K> {
+    type Item = E;
+    type IntoIter = std::collections::hash_set::IntoIter<E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZobristHashSet;
+
+    fn hash_of(elements: impl IntoIterator<Item = u32>) -> u64 {
+        let mut hash = ZobristHashSet::empty();
+        for element in elements {
+            hash.add(&element);
+        }
+        hash.into()
+    }
+
+    #[test]
+    fn capacity_management() {
+        let mut set = TrackedZobristHashSet::with_capacity(100);
+        assert!(set.capacity() >= 100);
+        set.extend(0..1000u32);
+        set.reserve(1000);
+        assert!(set.capacity() >= 2000);
+        set.retain(|&element| element < 10);
+        set.shrink_to_fit();
+        assert!(set.capacity() < 100);
+        assert_eq!(set.hash(), hash_of(0..10));
+    }
+
+    #[test]
+    fn retain_and_drain() {
+        let mut set: TrackedZobristHashSet<u32> = (0..20).collect();
+        set.retain(|&element| element % 3 == 0);
+        assert_eq!(set.hash(), hash_of((0..20).filter(|e| e % 3 == 0)));
+        assert_eq!(set.len(), 7);
+
+        let mut drained: Vec<u32> = set.drain().collect();
+        drained.sort_unstable();
+        assert_eq!(drained, vec![0, 3, 6, 9, 12, 15, 18]);
+        assert!(set.is_empty());
+        assert_eq!(set.hash(), 0);
+    }
+
+    #[test]
+    fn set_algebra_keeps_the_hash() {
+        let a: TrackedZobristHashSet<u32> = (0..10).collect();
+        let b: TrackedZobristHashSet<u32> = (5..15).collect();
+        assert_eq!(a.union(&b).hash(), hash_of(0..15));
+        assert_eq!(a.intersection(&b).hash(), hash_of(5..10));
+        assert_eq!(a.difference(&b).hash(), hash_of(0..5));
+        assert_eq!(
+            a.symmetric_difference(&b).hash(),
+            hash_of((0..5).chain(10..15))
+        );
+        assert_eq!(a.symmetric_difference(&b).hash(), a.hash() ^ b.hash());
+
+        let inner = a.intersection(&b);
+        assert!(inner.is_subset(&a) && inner.is_subset(&b));
+        assert!(a.is_superset(&inner));
+        assert!(a.difference(&b).is_disjoint(&b));
+        assert!(!a.is_subset(&b));
+    }
+
+    #[test]
+    fn redundant_updates_keep_the_hash() {
+        let mut set: TrackedZobristHashSet<u32> = (0..10).collect();
+        let before = set.hash();
+        assert!(!set.insert(3));
+        assert!(!set.remove(&42));
+        assert_eq!(set.hash(), before);
+
+        for i in 0..5 {
+            assert!(set.remove(&i));
+        }
+        let mut expected = ZobristHashSet::empty();
+        for i in 5..10u32 {
+            expected.add(&i);
+        }
+        assert_eq!(set.hash(), u64::from(expected));
+        assert_eq!(set.iter().count(), 5);
+
+        set.clear();
+        assert!(set.is_empty());
+        assert_eq!(set, TrackedZobristHashSet::new());
+    }
+}
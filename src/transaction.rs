@@ -0,0 +1,308 @@
+use crate::{Combiner, KeySource, Op, ZobristError, ZobristHashSet, DEFAULT_CHECK_CAPACITY};
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// A batch of updates to a [`ZobristHashSet`] that takes effect only when committed.
+///
+/// Returned by [`begin_update`](ZobristHashSet::begin_update). Updates are applied to the set
+/// right away, but dropping the guard without calling [`commit`](Self::commit) rolls all of
+/// them back, along with the debug checker. A move that touches several elements therefore
+/// never leaves the set half-updated, even when a check fails or code returns early with `?`.
+///
+/// The guard remembers the hash it started from and, in checked builds, the updates it applied,
+/// so starting an update and rolling it back cost the same at any set size.
+///
+/// ```rust
+/// use zobristhash_set::ZobristHashSet;
+///
+/// let mut board = ZobristHashSet::empty();
+/// board.add(&("king", 4));
+/// board.add(&("rook", 7));
+/// let before = board.hash();
+///
+/// // Castling moves two pieces; an abandoned update leaves the board as it was.
+/// {
+///     let mut update = board.begin_update();
+///     update.remove(&("king", 4));
+///     update.add(&("king", 6));
+/// }
+/// assert_eq!(board.hash(), before);
+///
+/// let mut update = board.begin_update();
+/// update.remove(&("king", 4));
+/// update.add(&("king", 6));
+/// update.remove(&("rook", 7));
+/// update.add(&("rook", 5));
+/// update.commit();
+/// assert_ne!(board.hash(), before);
+/// ```
+pub struct UpdateGuard<
+    'a,
+    E: Hash + Eq + Clone,
+    K,
+    C,
+    const CHECK_CAPACITY: usize = DEFAULT_CHECK_CAPACITY,
+> {
+    set: &'a mut ZobristHashSet<E, K, C, CHECK_CAPACITY>,
+    hash: u64,
+    #[cfg(feature = "count_guard")]
+    len_delta: i64,
+    /// The updates the checker accepted, undone in reverse on rollback.
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    applied: Vec<Op<E>>,
+    committed: bool,
+}
+
+impl<E: Hash + Eq + Clone, K, C, const CHECK_CAPACITY: usize>
+    ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
+    /// Starts a batch of updates that is rolled back unless committed.
+    pub fn begin_update(&mut self) -> UpdateGuard<'_, E, K, C, CHECK_CAPACITY> {
+        UpdateGuard {
+            hash: self.hash,
+            #[cfg(feature = "count_guard")]
+            len_delta: self.len_delta,
+            #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+            applied: Vec::new(),
+            committed: false,
+            set: self,
+        }
+    }
+}
+
+impl<E: Hash + Eq + Clone, K, C, const CHECK_CAPACITY: usize>
+    UpdateGuard<'_, E, K, C, CHECK_CAPACITY>
+{
+    /// Returns the hash value including the updates made so far.
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Keeps the updates.
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    /// Discards the updates; the same as dropping the guard.
+    pub fn rollback(self) {}
+}
+
+impl<E, K, C, const CHECK_CAPACITY: usize> UpdateGuard<'_, E, K, C, CHECK_CAPACITY>
+where
+    E: Hash + Eq + Clone + Debug,
+    K: KeySource<E>,
+    C: Combiner,
+{
+    /// Adds `key` like [`ZobristHashSet::add`].
+    pub fn add(&mut self, key: &E) -> u64 {
+        let element_key = self.set.add(key);
+        self.applied(|| Op::Add(key.clone()));
+        element_key
+    }
+
+    /// Removes `key` like [`ZobristHashSet::remove`].
+    pub fn remove(&mut self, key: &E) -> u64 {
+        let element_key = self.set.remove(key);
+        self.applied(|| Op::Remove(key.clone()));
+        element_key
+    }
+
+    /// Adds `key` like [`ZobristHashSet::try_add`].
+    pub fn try_add(&mut self, key: &E) -> Result<u64, ZobristError> {
+        let element_key = self.set.try_add(key)?;
+        self.applied(|| Op::Add(key.clone()));
+        Ok(element_key)
+    }
+
+    /// Removes `key` like [`ZobristHashSet::try_remove`].
+    pub fn try_remove(&mut self, key: &E) -> Result<u64, ZobristError> {
+        let element_key = self.set.try_remove(key)?;
+        self.applied(|| Op::Remove(key.clone()));
+        Ok(element_key)
+    }
+
+    /// Remembers an update for rollback if the set is checked.
+    fn applied(&mut self, op: impl FnOnce() -> Op<E>) {
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        if self.set.checker.is_some() {
+            self.applied.push(op());
+        }
+        #[cfg(not(all(debug_assertions, feature = "check_set_behavior")))]
+        let _ = op;
+    }
+}
+
+impl<E, K, C, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, C, CHECK_CAPACITY>
+where
+    E: Hash + Eq + Clone + Debug,
+    K: KeySource<E>,
+    C: Combiner,
+{
+    /// Removes every element of `removed` and then adds every element of `added`, all or
+    /// nothing.
+    ///
+    /// In checked builds every removal is validated before any addition, and if the checker
+    /// rejects one element the set, checker included, is left exactly as it was. Without the
+    /// checker it always succeeds.
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// let mut board = ZobristHashSet::empty();
+    /// board.add(&("king", 4));
+    /// board.add(&("rook", 7));
+    /// board
+    ///     .apply_diff(&[("king", 6), ("rook", 5)], &[("king", 4), ("rook", 7)])
+    ///     .unwrap();
+    ///
+    /// let mut castled = ZobristHashSet::empty();
+    /// castled.add(&("king", 6));
+    /// castled.add(&("rook", 5));
+    /// assert_eq!(board.hash(), castled.hash());
+    /// ```
+    pub fn apply_diff(&mut self, added: &[E], removed: &[E]) -> Result<(), ZobristError> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::trace_span!("apply_diff", added = added.len(), removed = removed.len())
+                .entered();
+        let mut update = self.begin_update();
+        for key in removed {
+            update.try_remove(key)?;
+        }
+        for key in added {
+            update.try_add(key)?;
+        }
+        update.commit();
+        Ok(())
+    }
+}
+
+impl<E: Hash + Eq + Clone, K, C, const CHECK_CAPACITY: usize> Drop
+    for UpdateGuard<'_, E, K, C, CHECK_CAPACITY>
+{
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        self.set.hash = self.hash;
+        #[cfg(feature = "count_guard")]
+        {
+            self.set.len_delta = self.len_delta;
+        }
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        if let Some(checker) = self.set.checker.as_mut() {
+            for op in self.applied.drain(..).rev() {
+                match op {
+                    Op::Add(key) => {
+                        checker.remove(&key);
+                    }
+                    // Adding back a removed element never trips the capacity check.
+                    Op::Remove(key) => checker.insert_repeated(key),
+                    Op::Clear | Op::Toggle(_) => unreachable!("the guard records adds and removes"),
+                }
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(op = "rollback", hash = self.set.hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    use crate::ZobristError;
+    use crate::ZobristHashSet;
+
+    #[test]
+    fn dropped_updates_roll_back() {
+        let mut set = ZobristHashSet::empty();
+        set.add(&1u32);
+        let before = set.hash();
+
+        let mut update = set.begin_update();
+        update.add(&2);
+        update.remove(&1);
+        assert_ne!(update.hash(), before);
+        update.rollback();
+        assert_eq!(set.hash(), before);
+
+        let mut update = set.begin_update();
+        update.add(&2);
+        update.commit();
+        set.remove(&2);
+        assert_eq!(set.hash(), before);
+
+        // The checker took the rolled-back removal back too.
+        set.remove(&1);
+        assert_eq!(set.hash(), 0);
+    }
+
+    #[test]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn failed_updates_leave_the_set_intact() {
+        let mut set = ZobristHashSet::empty();
+        set.add(&1u32);
+        let before = set.hash();
+
+        let result = (|| {
+            let mut update = set.begin_update();
+            update.try_add(&2)?;
+            update.try_remove(&3)?;
+            update.commit();
+            Ok::<_, ZobristError>(())
+        })();
+        assert_eq!(result, Err(ZobristError::MissingRemove));
+        assert_eq!(set.hash(), before);
+        // The checker was rolled back too, so 2 is not tracked.
+        assert_eq!(set.try_remove(&2), Err(ZobristError::MissingRemove));
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut update = set.begin_update();
+            update.add(&2);
+            update.add(&1);
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(set.hash(), before);
+    }
+
+    #[test]
+    fn apply_diff_matches_single_updates() {
+        let mut set = ZobristHashSet::empty();
+        let mut expected = ZobristHashSet::empty();
+        for i in 0..10u32 {
+            set.add(&i);
+            expected.add(&i);
+        }
+        set.apply_diff(&[10, 11, 3], &[3, 4, 5]).unwrap();
+        for i in [4, 5] {
+            expected.remove(&i);
+        }
+        for i in [10, 11] {
+            expected.add(&i);
+        }
+        assert_eq!(set.hash(), expected.hash());
+    }
+
+    #[test]
+    #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+    fn rejected_diffs_change_nothing() {
+        let mut set = ZobristHashSet::empty();
+        set.add(&1u32);
+        set.add(&2);
+        let before = set.hash();
+
+        assert_eq!(
+            set.apply_diff(&[3], &[1, 4]),
+            Err(ZobristError::MissingRemove)
+        );
+        assert_eq!(
+            set.apply_diff(&[3, 2], &[1]),
+            Err(ZobristError::DuplicateAdd)
+        );
+        assert_eq!(set.hash(), before);
+        set.remove(&1);
+        set.remove(&2);
+    }
+}
@@ -0,0 +1,514 @@
+//! A transposition table keyed by Zobrist hashes.
+
+mod concurrent;
+mod db;
+mod lru;
+#[cfg(feature = "net")]
+mod net;
+mod packed;
+mod persist;
+mod policy;
+mod sharded;
+
+pub use concurrent::ConcurrentTranspositionTable;
+pub use db::PositionDb;
+pub use lru::PositionCache;
+#[cfg(feature = "net")]
+pub use net::{
+    decode_frame, encode_frame, read_entries, write_entries, EntrySharer, SharedEntry,
+    MAX_FRAME_ENTRIES,
+};
+pub use packed::{PackedEntry, PackedTranspositionTable};
+pub use persist::Persist;
+pub use policy::{AlwaysReplace, DepthPreferred, ReplacementPolicy, TwoSlot};
+pub use sharded::ShardedPositionCache;
+#[cfg(feature = "tt-stats")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A fixed-size, hash-indexed cache of search results.
+///
+/// The table is an array of a power-of-two number of buckets, each holding
+/// [`P::WAYS`](ReplacementPolicy::WAYS) slots. The low bits of a hash select a bucket and the
+/// high 32 bits are stored next to the value, so a probe can tell a hit from a different position
+/// that happens to share the bucket. When a bucket has no slot for the stored hash yet, an empty
+/// slot or an entry left over from an earlier search (see [`new_search`](Self::new_search)) is
+/// evicted first, and otherwise the [`ReplacementPolicy`] decides which occupant to evict.
+///
+/// ```rust
+/// use zobristhash_set::transposition::TranspositionTable;
+/// use zobristhash_set::ZobristHashSet;
+///
+/// let mut position = ZobristHashSet::empty();
+/// position.add(&("king", 4));
+///
+/// let mut table = TranspositionTable::with_entries(1024);
+/// table.store(position.hash(), 42);
+/// assert_eq!(table.probe(position.hash()), Some(&42));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct TranspositionTable<V, P = AlwaysReplace> {
+    slots: Vec<Slot<V>>,
+    mask: usize,
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    policy: P,
+    generation: u8,
+    #[cfg(feature = "tt-stats")]
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    stats: Counters,
+}
+
+/// Usage counters of a [`TranspositionTable`].
+#[cfg(feature = "tt-stats")]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TtStats {
+    pub probes: u64,
+    pub hits: u64,
+    /// Probes that missed although their bucket was occupied, i.e. that were only told apart from
+    /// another position by the verification bits.
+    pub collisions: u64,
+    /// Stores that evicted an entry for a different position.
+    pub replacements: u64,
+}
+
+/// The counters behind [`TtStats`], atomic so that probing through `&self` keeps the table
+/// `Sync`.
+#[cfg(feature = "tt-stats")]
+#[derive(Default, Debug)]
+struct Counters {
+    probes: AtomicU64,
+    hits: AtomicU64,
+    collisions: AtomicU64,
+    replacements: AtomicU64,
+}
+
+#[cfg(feature = "tt-stats")]
+impl Counters {
+    fn count(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn load(&self) -> TtStats {
+        TtStats {
+            probes: self.probes.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            collisions: self.collisions.load(Ordering::Relaxed),
+            replacements: self.replacements.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "tt-stats")]
+impl Clone for Counters {
+    fn clone(&self) -> Self {
+        let stats = self.load();
+        Self {
+            probes: stats.probes.into(),
+            hits: stats.hits.into(),
+            collisions: stats.collisions.into(),
+            replacements: stats.replacements.into(),
+        }
+    }
+}
+
+/// A slot of a [`TranspositionTable`] bucket, as seen by a [`ReplacementPolicy`].
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct Slot<V> {
+    entry: Option<Entry<V>>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+struct Entry<V> {
+    verification: u32,
+    generation: u8,
+    value: V,
+}
+
+impl<V> Slot<V> {
+    /// Returns the stored value, or `None` if the slot is empty.
+    pub fn value(&self) -> Option<&V> {
+        self.entry.as_ref().map(|entry| &entry.value)
+    }
+
+    fn holds(&self, verification: u32) -> Option<&Entry<V>> {
+        self.entry
+            .as_ref()
+            .filter(|entry| entry.verification == verification)
+    }
+}
+
+impl<V> TranspositionTable<V> {
+    /// Creates a table with room for `entries` values, rounded down to a power of two.
+    pub fn with_entries(entries: usize) -> Self {
+        Self::with_policy(entries, AlwaysReplace)
+    }
+
+    /// Creates the largest table whose slots fit in `bytes`.
+    pub fn with_bytes(bytes: usize) -> Self {
+        Self::with_entries(bytes / std::mem::size_of::<Slot<V>>())
+    }
+}
+
+impl<V, P: ReplacementPolicy<V>> TranspositionTable<V, P> {
+    /// Creates a table with room for about `entries` values that evicts according to `policy`.
+    ///
+    /// The number of buckets is `entries / P::WAYS` rounded down to a power of two.
+    pub fn with_policy(entries: usize, policy: P) -> Self {
+        let buckets = Self::buckets_for(entries);
+        Self {
+            slots: empty_slots(buckets * P::WAYS),
+            mask: buckets - 1,
+            policy,
+            generation: 0,
+            #[cfg(feature = "tt-stats")]
+            stats: Default::default(),
+        }
+    }
+
+    /// Returns the number of slots.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the value stored for `hash`, if its bucket holds an entry for it.
+    pub fn probe(&self, hash: u64) -> Option<&V> {
+        self.probe_with_age(hash).map(|(value, _)| value)
+    }
+
+    /// Like [`probe`](Self::probe), but also returns how many searches ago the entry was stored.
+    ///
+    /// Ages wrap around after 256 searches.
+    pub fn probe_with_age(&self, hash: u64) -> Option<(&V, u8)> {
+        let verification = verification(hash);
+        let bucket = self.bucket(hash);
+        let found = bucket.iter().find_map(|slot| {
+            let entry = slot.holds(verification)?;
+            Some((&entry.value, self.age(entry)))
+        });
+        #[cfg(feature = "tt-stats")]
+        {
+            Counters::count(&self.stats.probes);
+            if found.is_some() {
+                Counters::count(&self.stats.hits);
+            } else if bucket.iter().any(|slot| slot.entry.is_some()) {
+                Counters::count(&self.stats.collisions);
+            }
+        }
+        #[cfg(feature = "metrics")]
+        if found.is_some() {
+            crate::telemetry::tt_hits().increment(1);
+        } else {
+            crate::telemetry::tt_misses().increment(1);
+        }
+        found
+    }
+
+    /// Starts a new search, making every stored entry stale.
+    ///
+    /// Stale entries can still be probed, but are replaced before any entry of the current search.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Stores `value` for `hash`.
+    ///
+    /// An existing entry for `hash` is always overwritten; otherwise the policy picks the slot, or
+    /// drops the value.
+    pub fn store(&mut self, hash: u64, value: V) {
+        let entry = Entry {
+            verification: verification(hash),
+            generation: self.generation,
+            value,
+        };
+        self.place(hash, entry);
+    }
+
+    /// Changes the table to hold about `entries` values, as in [`with_policy`](Self::with_policy).
+    ///
+    /// A slot only remembers part of its hash, so entries are kept when the table shrinks, folded
+    /// into the smaller table by the replacement rules of [`store`](Self::store), but discarded
+    /// when it grows.
+    pub fn resize(&mut self, entries: usize) {
+        let buckets = Self::buckets_for(entries);
+        let old_buckets = self.mask + 1;
+        let old = std::mem::replace(&mut self.slots, empty_slots(buckets * P::WAYS));
+        self.mask = buckets - 1;
+        if buckets > old_buckets {
+            return;
+        }
+        for (i, slot) in old.into_iter().enumerate() {
+            if let Some(entry) = slot.entry {
+                let hash = (entry.verification as u64) << 32 | (i / P::WAYS) as u64;
+                self.place(hash, entry);
+            }
+        }
+    }
+
+    fn place(&mut self, hash: u64, entry: Entry<V>) {
+        let verification = entry.verification;
+        let start = self.bucket_start(hash);
+        let bucket = &self.slots[start..start + P::WAYS];
+        let generation = self.generation;
+        let age = |slot: &Slot<V>| match &slot.entry {
+            Some(entry) => generation.wrapping_sub(entry.generation),
+            None => u8::MAX,
+        };
+        let way = if let Some(way) = bucket.iter().position(|s| s.holds(verification).is_some()) {
+            way
+        } else {
+            // Reversed so that ties go to the first slot.
+            let stalest = (0..P::WAYS)
+                .rev()
+                .max_by_key(|&way| age(&bucket[way]))
+                .unwrap();
+            if age(&bucket[stalest]) > 0 {
+                stalest
+            } else {
+                match self.policy.victim(bucket, &entry.value) {
+                    Some(way) => way,
+                    None => return,
+                }
+            }
+        };
+        #[cfg(feature = "tt-stats")]
+        if bucket[way]
+            .entry
+            .as_ref()
+            .is_some_and(|entry| entry.verification != verification)
+        {
+            Counters::count(&self.stats.replacements);
+        }
+        self.slots[start + way].entry = Some(entry);
+    }
+
+    /// Hints the CPU to start loading the bucket of `hash` into cache.
+    ///
+    /// Issue it as soon as a child position's hash is known, so the memory access overlaps with
+    /// the rest of the move generation and the later [`probe`](Self::probe) hits the cache.
+    pub fn prefetch(&self, hash: u64) {
+        prefetch(&self.slots[self.bucket_start(hash)]);
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| slot.entry = None);
+    }
+
+    /// Returns the counters accumulated since creation or the last
+    /// [`reset_stats`](Self::reset_stats).
+    #[cfg(feature = "tt-stats")]
+    pub fn stats(&self) -> TtStats {
+        self.stats.load()
+    }
+
+    #[cfg(feature = "tt-stats")]
+    pub fn reset_stats(&mut self) {
+        self.stats = Counters::default();
+    }
+
+    fn buckets_for(entries: usize) -> usize {
+        match entries / P::WAYS {
+            0 => 1,
+            n => 1 << n.ilog2(),
+        }
+    }
+
+    fn age(&self, entry: &Entry<V>) -> u8 {
+        self.generation.wrapping_sub(entry.generation)
+    }
+
+    fn bucket(&self, hash: u64) -> &[Slot<V>] {
+        let start = self.bucket_start(hash);
+        &self.slots[start..start + P::WAYS]
+    }
+
+    fn bucket_start(&self, hash: u64) -> usize {
+        (hash as usize & self.mask) * P::WAYS
+    }
+}
+
+/// A [`TranspositionTable`] as laid out by `rkyv`, which can be probed in place, e.g. from a
+/// memory-mapped file, without deserializing it first.
+///
+/// The replacement policy and usage counters are not archived; a deserialized table gets their
+/// default values.
+///
+/// ```rust
+/// use zobristhash_set::transposition::{ArchivedTranspositionTable, TranspositionTable};
+///
+/// let mut table = TranspositionTable::<u32>::with_entries(1024);
+/// table.store(0xfeed_0000_0000_1234, 42);
+///
+/// let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&table).unwrap();
+/// let archived =
+///     rkyv::access::<ArchivedTranspositionTable<u32>, rkyv::rancor::Error>(&bytes).unwrap();
+/// assert_eq!(archived.probe(0xfeed_0000_0000_1234).map(|v| v.to_native()), Some(42));
+/// assert_eq!(archived.probe(0xbeef_0000_0000_1234), None);
+/// ```
+#[cfg(feature = "rkyv")]
+impl<V: rkyv::Archive, P: ReplacementPolicy<V>> ArchivedTranspositionTable<V, P> {
+    /// Returns the archived value stored for `hash`, like [`TranspositionTable::probe`].
+    pub fn probe(&self, hash: u64) -> Option<&V::Archived> {
+        let verification = verification(hash);
+        let start = (hash as usize & self.mask.to_native() as usize) * P::WAYS;
+        self.slots[start..start + P::WAYS].iter().find_map(|slot| {
+            let entry = slot.entry.as_ref()?;
+            (entry.verification.to_native() == verification).then_some(&entry.value)
+        })
+    }
+}
+
+fn empty_slots<V>(len: usize) -> Vec<Slot<V>> {
+    let mut slots = Vec::with_capacity(len);
+    slots.resize_with(len, || Slot { entry: None });
+    slots
+}
+
+fn prefetch<T>(data: &T) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        // SAFETY: SSE is part of the x86_64 baseline, and prefetching never faults.
+        unsafe { _mm_prefetch::<_MM_HINT_T0>((data as *const T).cast()) };
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = data;
+}
+
+/// 32-bit FNV-1a, used to detect corrupted records and frames.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u32 {
+    bytes.into_iter().fold(0x811c_9dc5, |sum, byte| {
+        (sum ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn verification(hash: u64) -> u32 {
+    (hash >> 32) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizing() {
+        assert_eq!(TranspositionTable::<u8>::with_entries(0).capacity(), 1);
+        assert_eq!(TranspositionTable::<u8>::with_entries(1000).capacity(), 512);
+        let slot = std::mem::size_of::<Slot<u64>>();
+        let table = TranspositionTable::<u64>::with_bytes(1 << 20);
+        assert!(table.capacity() * slot <= 1 << 20);
+        assert!(table.capacity() * slot * 2 > 1 << 20);
+        let two_slot = TranspositionTable::with_policy(1000, TwoSlot::new(|&d: &i32| d));
+        assert_eq!(two_slot.capacity(), 512);
+    }
+
+    #[test]
+    fn verification_rejects_slot_collisions() {
+        let mut table = TranspositionTable::with_entries(16);
+        let a = 0x0000_0001_0000_0003;
+        let b = 0x0000_0002_0000_0003;
+        table.store(a, "a");
+        assert_eq!(table.probe(a), Some(&"a"));
+        assert_eq!(table.probe(b), None);
+        table.store(b, "b");
+        assert_eq!(table.probe(a), None);
+        assert_eq!(table.probe(b), Some(&"b"));
+        table.prefetch(b);
+        table.clear();
+        assert_eq!(table.probe(b), None);
+    }
+
+    #[test]
+    fn resize() {
+        let mut table = TranspositionTable::with_entries(16);
+        let hashes: Vec<u64> = (0..16).map(|i| (i + 1) << 32 | i).collect();
+        for &hash in &hashes {
+            table.store(hash, hash);
+        }
+        table.resize(8);
+        assert_eq!(table.capacity(), 8);
+        // Buckets `i` and `i + 8` merge; the later store wins.
+        for &hash in &hashes {
+            let expected = (hash & 8 != 0).then_some(&hash);
+            assert_eq!(table.probe(hash), expected);
+        }
+        table.resize(32);
+        assert_eq!(table.capacity(), 32);
+        assert!(hashes.iter().all(|&hash| table.probe(hash).is_none()));
+    }
+
+    #[test]
+    fn stale_entries_are_evicted_first() {
+        let depth = |&(_, depth): &(char, i32)| depth;
+        let mut table = TranspositionTable::with_policy(2, TwoSlot::new(depth));
+        table.store(1 << 32, ('a', 9));
+        table.new_search();
+        table.store(2 << 32, ('b', 1));
+        table.new_search();
+        assert_eq!(table.probe_with_age(1 << 32), Some((&('a', 9), 2)));
+        assert_eq!(table.probe_with_age(2 << 32), Some((&('b', 1), 1)));
+
+        // The deep but stale entry goes before the shallow one from the last search.
+        table.store(3 << 32, ('c', 1));
+        assert_eq!(table.probe(1 << 32), None);
+        assert_eq!(table.probe(2 << 32), Some(&('b', 1)));
+        assert_eq!(table.probe_with_age(3 << 32), Some((&('c', 1), 0)));
+    }
+
+    #[test]
+    #[cfg(feature = "tt-stats")]
+    fn stats() {
+        let mut table = TranspositionTable::with_entries(4);
+        table.probe(1 << 32);
+        table.store(1 << 32, 'a');
+        table.probe(1 << 32);
+        table.probe(2 << 32);
+        table.store(1 << 32, 'b');
+        table.store(2 << 32, 'c');
+        let expected = TtStats {
+            probes: 3,
+            hits: 1,
+            collisions: 1,
+            replacements: 1,
+        };
+        assert_eq!(table.stats(), expected);
+        table.reset_stats();
+        assert_eq!(table.stats(), TtStats::default());
+
+        fn assert_sync<T: Sync>(_: &T) {}
+        assert_sync(&table);
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn archived_table_round_trips() {
+        let mut table = TranspositionTable::with_policy(64, TwoSlot::new(|&depth: &i32| depth));
+        for hash in 0..100u64 {
+            table.store(hash << 32 | hash, hash as i32);
+        }
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&table).unwrap();
+        let archived = rkyv::access::<
+            ArchivedTranspositionTable<i32, TwoSlot<i32>>,
+            rkyv::rancor::Error,
+        >(&bytes)
+        .unwrap();
+        for hash in 0..100u64 {
+            let hash = hash << 32 | hash;
+            assert_eq!(
+                archived.probe(hash).map(|value| value.to_native()),
+                table.probe(hash).copied()
+            );
+        }
+    }
+}
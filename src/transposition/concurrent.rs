@@ -0,0 +1,251 @@
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A transposition table that threads can share without locks, for Lazy-SMP style search.
+///
+/// Each slot is a pair of atomic words holding `hash ^ data` and `data`, written and read with
+/// relaxed ordering. Two threads storing to the same slot at once can leave it with one thread's
+/// key word and the other's data word; such a torn slot no longer satisfies
+/// `key_word ^ data_word == hash` for either hash, so [`probe`](Self::probe) treats it as a miss
+/// instead of returning data that belongs to a different position. Payloads must be packed into a
+/// `u64` by the caller.
+///
+/// An empty slot reads as hash `0` with data `0`, so probing hash `0` can report a spurious hit.
+///
+/// With the `mmap` feature, [`open_mapped`](Self::open_mapped) places the slots in a memory-mapped
+/// file instead of the heap.
+///
+/// ```rust
+/// use zobristhash_set::transposition::ConcurrentTranspositionTable;
+///
+/// let table = ConcurrentTranspositionTable::with_entries(1024);
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| table.store(0x1234_5678_9abc_def0, 42));
+/// });
+/// assert_eq!(table.probe(0x1234_5678_9abc_def0), Some(42));
+/// ```
+#[derive(Debug)]
+pub struct ConcurrentTranspositionTable {
+    slots: Slots,
+    mask: usize,
+}
+
+#[derive(Debug)]
+enum Slots {
+    Heap(Box<[[AtomicU64; 2]]>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::MmapRaw),
+}
+
+impl Deref for Slots {
+    type Target = [[AtomicU64; 2]];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Slots::Heap(slots) => slots,
+            #[cfg(feature = "mmap")]
+            Slots::Mapped(map) => {
+                // SAFETY: the mapping is page-aligned, a whole number of slots long, and
+                // `[AtomicU64; 2]` has the layout of `[u64; 2]`, for which any bytes are valid.
+                // The pointer comes from a raw mapping and carries write permission, and every
+                // access goes through atomics, so concurrent writers, including other processes
+                // mapping the same file, cannot cause data races.
+                unsafe {
+                    std::slice::from_raw_parts(
+                        map.as_mut_ptr().cast_const().cast(),
+                        map.len() / std::mem::size_of::<[AtomicU64; 2]>(),
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl ConcurrentTranspositionTable {
+    /// Creates a table with room for `entries` values, rounded down to a power of two.
+    pub fn with_entries(entries: usize) -> Self {
+        let len = slot_count(entries);
+        Self {
+            slots: Slots::Heap((0..len).map(|_| Default::default()).collect()),
+            mask: len - 1,
+        }
+    }
+
+    /// Opens a table with room for `entries` values, rounded down to a power of two, stored in
+    /// the file at `path`.
+    ///
+    /// An existing file is reused with its contents, so entries survive restarts and are shared
+    /// with every other process that maps the same file. A missing or empty file is sized for
+    /// the table and starts out empty. A file of any other size is never resized, since that
+    /// would crash the processes mapping it; an error of kind
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) is returned instead.
+    #[cfg(feature = "mmap")]
+    pub fn open_mapped(path: impl AsRef<std::path::Path>, entries: usize) -> std::io::Result<Self> {
+        let len = slot_count(entries);
+        let bytes = (len * std::mem::size_of::<[AtomicU64; 2]>()) as u64;
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        match file.metadata()?.len() {
+            0 => file.set_len(bytes)?,
+            existing if existing != bytes => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("table file holds {existing} bytes, expected {bytes}"),
+                ))
+            }
+            _ => {}
+        }
+        let map = memmap2::MmapRaw::map_raw(&file)?;
+        Ok(Self {
+            slots: Slots::Mapped(map),
+            mask: len - 1,
+        })
+    }
+
+    /// Writes the entries of a memory-mapped table back to its file. Does nothing for a table
+    /// on the heap.
+    #[cfg(feature = "mmap")]
+    pub fn flush(&self) -> std::io::Result<()> {
+        match &self.slots {
+            Slots::Heap(_) => Ok(()),
+            Slots::Mapped(map) => map.flush(),
+        }
+    }
+
+    /// Creates the largest table whose slots fit in `bytes`.
+    pub fn with_bytes(bytes: usize) -> Self {
+        Self::with_entries(bytes / std::mem::size_of::<[AtomicU64; 2]>())
+    }
+
+    /// Returns the number of slots.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the data stored for `hash`, if its slot holds an intact entry for it.
+    pub fn probe(&self, hash: u64) -> Option<u64> {
+        let [key, data] = &self.slots[hash as usize & self.mask];
+        let key = key.load(Ordering::Relaxed);
+        let data = data.load(Ordering::Relaxed);
+        (key ^ data == hash).then_some(data)
+    }
+
+    /// Stores `data` for `hash`, replacing the previous occupant of its slot.
+    pub fn store(&self, hash: u64, data: u64) {
+        let [key_word, data_word] = &self.slots[hash as usize & self.mask];
+        key_word.store(hash ^ data, Ordering::Relaxed);
+        data_word.store(data, Ordering::Relaxed);
+    }
+
+    /// Changes the table to hold `entries` values, rounded down to a power of two.
+    ///
+    /// Slots keep their full hash, so every entry is moved to its new slot; where several land in
+    /// the same one, the last wins. The resized table always lives on the heap.
+    pub fn resize(&mut self, entries: usize) {
+        let old = std::mem::replace(self, Self::with_entries(entries));
+        for [key, data] in old.slots.iter() {
+            let (key, data) = (key.load(Ordering::Relaxed), data.load(Ordering::Relaxed));
+            if key != 0 || data != 0 {
+                self.store(key ^ data, data);
+            }
+        }
+    }
+
+    /// Hints the CPU to start loading the slot of `hash` into cache.
+    pub fn prefetch(&self, hash: u64) {
+        super::prefetch(&self.slots[hash as usize & self.mask]);
+    }
+
+    /// Removes every entry. Stores racing with the call may survive it.
+    pub fn clear(&self) {
+        for [key, data] in self.slots.iter() {
+            key.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+fn slot_count(entries: usize) -> usize {
+    match entries {
+        0 => 1,
+        n => 1 << n.ilog2(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn torn_entries_are_rejected() {
+        let table = ConcurrentTranspositionTable::with_entries(4);
+        let a = 1 << 40;
+        let b = 2 << 40;
+        table.store(a, 7);
+        // Simulate a store of `b` interrupted after its key word.
+        table.slots[0][0].store(b ^ 9, Ordering::Relaxed);
+        assert_eq!(table.probe(a), None);
+        assert_eq!(table.probe(b), None);
+        table.slots[0][1].store(9, Ordering::Relaxed);
+        assert_eq!(table.probe(b), Some(9));
+    }
+
+    #[test]
+    fn resize_keeps_entries() {
+        let mut table = ConcurrentTranspositionTable::with_entries(4);
+        table.store(0xabc0, 1);
+        table.store(0xdef1, 2);
+        table.resize(64);
+        assert_eq!(table.capacity(), 64);
+        assert_eq!(table.probe(0xabc0), Some(1));
+        assert_eq!(table.probe(0xdef1), Some(2));
+    }
+
+    #[test]
+    fn concurrent_stores_never_mix_entries() {
+        let table = ConcurrentTranspositionTable::with_entries(8);
+        // Every hash maps to data derived from it, so any hit can be checked.
+        let data = |hash: u64| hash.rotate_left(17) ^ 0x5555;
+        std::thread::scope(|scope| {
+            for thread in 0..4u64 {
+                let table = &table;
+                scope.spawn(move || {
+                    for i in 0..20_000u64 {
+                        let hash = (i % 64 + 1) * 0x1_0000_0001 + thread;
+                        table.store(hash, data(hash));
+                        let probed = (i * 7 % 64 + 1) * 0x1_0000_0001 + thread;
+                        table.prefetch(probed);
+                        if let Some(found) = table.probe(probed) {
+                            assert_eq!(found, data(probed));
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn mapped_tables_persist_and_share() {
+        let path = std::env::temp_dir().join(format!("zobrist-mmap-{}.bin", std::process::id()));
+        let table = ConcurrentTranspositionTable::open_mapped(&path, 64).unwrap();
+        let other = ConcurrentTranspositionTable::open_mapped(&path, 64).unwrap();
+        table.store(0x1234, 5);
+        assert_eq!(other.probe(0x1234), Some(5));
+        table.flush().unwrap();
+        drop((table, other));
+
+        let reopened = ConcurrentTranspositionTable::open_mapped(&path, 64).unwrap();
+        assert_eq!(reopened.probe(0x1234), Some(5));
+        // A different size is rejected rather than truncating the live mapping.
+        let error = ConcurrentTranspositionTable::open_mapped(&path, 128).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(reopened.probe(0x1234), Some(5));
+        drop(reopened);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,344 @@
+use super::fnv1a;
+use rustc_hash::FxHashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 8] = b"ZOBRDB\0\x01";
+const TOMBSTONE: u32 = u32::MAX;
+/// Hash, payload length, and checksum.
+const RECORD_OVERHEAD: u64 = 16;
+
+/// An append-only file mapping position hashes to byte payloads, for results that should outlive
+/// a single run.
+///
+/// Every [`put`](Self::put) appends a record holding the full 64-bit hash, so lookups are exact,
+/// and the latest record for a hash wins. Records carry a checksum: if the process dies halfway
+/// through an append, [`open`](Self::open) drops the torn record and keeps everything before it.
+/// Superseded records stay in the file until [`compact`](Self::compact) rewrites it.
+///
+/// ```rust
+/// use zobristhash_set::transposition::PositionDb;
+///
+/// let path = std::env::temp_dir().join("position-db-doctest.zdb");
+/// # let _ = std::fs::remove_file(&path);
+/// let mut db = PositionDb::open(&path)?;
+/// db.put(0x463b_9618_1691_fc9c, b"e4 +0.3")?;
+/// drop(db);
+///
+/// let mut db = PositionDb::open(&path)?;
+/// assert_eq!(db.get(0x463b_9618_1691_fc9c)?, Some(b"e4 +0.3".to_vec()));
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct PositionDb {
+    path: PathBuf,
+    file: File,
+    /// Payload offset and length of the latest record for each live hash.
+    index: FxHashMap<u64, (u64, u32)>,
+    /// Bytes taken by records that have been superseded or removed.
+    garbage: u64,
+}
+
+impl PositionDb {
+    /// Opens the database at `path`, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(MAGIC)?;
+            file.sync_all()?;
+        }
+        let mut db = Self {
+            path,
+            file,
+            index: FxHashMap::default(),
+            garbage: 0,
+        };
+        db.recover()?;
+        Ok(db)
+    }
+
+    /// Returns the number of hashes with a payload.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Returns the number of bytes that [`compact`](Self::compact) would reclaim.
+    pub fn garbage_bytes(&self) -> u64 {
+        self.garbage
+    }
+
+    pub fn contains(&self, hash: u64) -> bool {
+        self.index.contains_key(&hash)
+    }
+
+    /// Reads the payload stored for `hash`.
+    pub fn get(&mut self, hash: u64) -> io::Result<Option<Vec<u8>>> {
+        let Some(&(offset, len)) = self.index.get(&hash) else {
+            return Ok(None);
+        };
+        let mut payload = vec![0; len as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut payload)?;
+        Ok(Some(payload))
+    }
+
+    /// Stores `payload` for `hash`, superseding any earlier payload.
+    pub fn put(&mut self, hash: u64, payload: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(payload.len())
+            .ok()
+            .filter(|&len| len != TOMBSTONE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "payload too large"))?;
+        let offset = self.append(hash, len, payload)?;
+        self.supersede(hash, Some((offset, len)));
+        Ok(())
+    }
+
+    /// Removes the payload for `hash`, if any.
+    pub fn remove(&mut self, hash: u64) -> io::Result<()> {
+        if self.contains(hash) {
+            self.append(hash, TOMBSTONE, &[])?;
+            self.supersede(hash, None);
+            self.garbage += RECORD_OVERHEAD;
+        }
+        Ok(())
+    }
+
+    /// Forces appended records to disk.
+    pub fn sync(&self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
+    /// Rewrites the file with only the live records.
+    ///
+    /// The new contents are written to a temporary file that replaces the old one in a single
+    /// rename, so a crash during compaction leaves either the old or the new database.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".compact");
+        let temp_path = PathBuf::from(temp_path);
+
+        let mut entries: Vec<_> = self.index.iter().map(|(&h, &(o, l))| (o, l, h)).collect();
+        entries.sort_unstable();
+        {
+            let mut out = BufWriter::new(File::create(&temp_path)?);
+            out.write_all(MAGIC)?;
+            for (offset, len, hash) in entries {
+                let mut payload = vec![0; len as usize];
+                self.file.seek(SeekFrom::Start(offset))?;
+                self.file.read_exact(&mut payload)?;
+                write_record(&mut out, hash, len, &payload)?;
+            }
+            out.into_inner()?.sync_all()?;
+        }
+        fs::rename(&temp_path, &self.path)?;
+        *self = Self::open(&self.path)?;
+        Ok(())
+    }
+
+    fn append(&mut self, hash: u64, len: u32, payload: &[u8]) -> io::Result<u64> {
+        let start = self.file.seek(SeekFrom::End(0))?;
+        let mut record = Vec::with_capacity(payload.len() + RECORD_OVERHEAD as usize);
+        write_record(&mut record, hash, len, payload)?;
+        if let Err(error) = self.file.write_all(&record) {
+            // Cut off whatever part of the record made it to the file, so later appends do not
+            // land behind a torn record that recovery would stop at.
+            self.file.set_len(start)?;
+            return Err(error);
+        }
+        Ok(start + 12)
+    }
+
+    fn supersede(&mut self, hash: u64, location: Option<(u64, u32)>) {
+        let old = match location {
+            Some(location) => self.index.insert(hash, location),
+            None => self.index.remove(&hash),
+        };
+        if let Some((_, len)) = old {
+            self.garbage += RECORD_OVERHEAD + len as u64;
+        }
+    }
+
+    /// Rebuilds the index from the file, truncating it after the last intact record.
+    fn recover(&mut self) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut input = BufReader::new(&self.file);
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a position database",
+            ));
+        }
+
+        let file_len = self.file.metadata()?.len();
+        let mut records = Vec::new();
+        let mut end = MAGIC.len() as u64;
+        while let Some((hash, len)) = read_record(&mut input, file_len - end)? {
+            let payload_len = if len == TOMBSTONE { 0 } else { len as u64 };
+            records.push((hash, len, end + 12));
+            end += RECORD_OVERHEAD + payload_len;
+        }
+        drop(input);
+
+        for (hash, len, offset) in records {
+            if len == TOMBSTONE {
+                self.supersede(hash, None);
+                self.garbage += RECORD_OVERHEAD;
+            } else {
+                self.supersede(hash, Some((offset, len)));
+            }
+        }
+        if file_len > end {
+            self.file.set_len(end)?;
+            self.file.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+fn write_record(out: &mut impl Write, hash: u64, len: u32, payload: &[u8]) -> io::Result<()> {
+    out.write_all(&hash.to_le_bytes())?;
+    out.write_all(&len.to_le_bytes())?;
+    out.write_all(payload)?;
+    out.write_all(&checksum(hash, len, payload).to_le_bytes())
+}
+
+/// Reads the next record from the `remaining` bytes of the file, returning `None` at the end of
+/// the file or at a torn record.
+fn read_record(input: &mut impl Read, remaining: u64) -> io::Result<Option<(u64, u32)>> {
+    let mut header = [0; 12];
+    if !read_full(input, &mut header)? {
+        return Ok(None);
+    }
+    let hash = u64::from_le_bytes(header[..8].try_into().unwrap());
+    let len = u32::from_le_bytes(header[8..].try_into().unwrap());
+    let payload_len = if len == TOMBSTONE { 0 } else { len as u64 };
+    // A length running past the end of the file belongs to a torn record; checking it first
+    // keeps a corrupt header from allocating gigabytes.
+    if RECORD_OVERHEAD + payload_len > remaining {
+        return Ok(None);
+    }
+    let mut payload = vec![0; payload_len as usize];
+    let mut sum = [0; 4];
+    if !read_full(input, &mut payload)? || !read_full(input, &mut sum)? {
+        return Ok(None);
+    }
+    Ok((u32::from_le_bytes(sum) == checksum(hash, len, &payload)).then_some((hash, len)))
+}
+
+/// Like `read_exact`, but returns `false` instead of failing when the input ends early.
+fn read_full(input: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    match input.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn checksum(hash: u64, len: u32, payload: &[u8]) -> u32 {
+    fnv1a(
+        hash.to_le_bytes()
+            .into_iter()
+            .chain(len.to_le_bytes())
+            .chain(payload.iter().copied()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("zobrist-{name}-{}.zdb", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn torn_tail_is_dropped() {
+        let path = temp_path("torn");
+        let mut db = PositionDb::open(&path).unwrap();
+        db.put(1, b"one").unwrap();
+        db.put(2, b"two").unwrap();
+        drop(db);
+
+        // Simulate a crash in the middle of appending a third record.
+        let full = fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write_record(&mut file, 3, 5, b"three").unwrap();
+        file.set_len(full + 10).unwrap();
+        drop(file);
+
+        let mut db = PositionDb::open(&path).unwrap();
+        assert_eq!(db.len(), 2);
+        assert_eq!(db.get(2).unwrap(), Some(b"two".to_vec()));
+        assert_eq!(db.get(3).unwrap(), None);
+        assert_eq!(fs::metadata(&path).unwrap().len(), full);
+        db.put(3, b"three").unwrap();
+        drop(db);
+        assert_eq!(
+            PositionDb::open(&path).unwrap().get(3).unwrap(),
+            Some(b"three".to_vec())
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn oversized_lengths_are_torn_records() {
+        let path = temp_path("oversized");
+        let mut db = PositionDb::open(&path).unwrap();
+        db.put(1, b"one").unwrap();
+        drop(db);
+
+        let full = fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&2u64.to_le_bytes()).unwrap();
+        file.write_all(&(TOMBSTONE - 1).to_le_bytes()).unwrap();
+        file.write_all(&[0; 16]).unwrap();
+        drop(file);
+
+        let mut db = PositionDb::open(&path).unwrap();
+        assert_eq!(db.len(), 1);
+        assert_eq!(db.get(1).unwrap(), Some(b"one".to_vec()));
+        assert_eq!(fs::metadata(&path).unwrap().len(), full);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn compaction_keeps_latest_payloads() {
+        let path = temp_path("compact");
+        let mut db = PositionDb::open(&path).unwrap();
+        for round in 0..10u8 {
+            for hash in 0..10u64 {
+                db.put(hash, &[round; 32]).unwrap();
+            }
+        }
+        db.remove(9).unwrap();
+        assert!(db.garbage_bytes() > 0);
+        let before = fs::metadata(&path).unwrap().len();
+
+        db.compact().unwrap();
+        assert_eq!(db.garbage_bytes(), 0);
+        assert!(fs::metadata(&path).unwrap().len() < before / 5);
+        drop(db);
+
+        let mut db = PositionDb::open(&path).unwrap();
+        assert_eq!(db.len(), 9);
+        assert_eq!(db.get(4).unwrap(), Some(vec![9; 32]));
+        assert_eq!(db.get(9).unwrap(), None);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+use rustc_hash::FxHashMap;
+
+const NIL: usize = usize::MAX;
+
+/// A bounded cache of values keyed by position hash, evicting the least recently used entry.
+///
+/// Entries are indexed by the low 32 bits of the hash and store the high 32 bits for
+/// verification, so a hash that only shares the index with a cached position misses, and storing
+/// it replaces that position. Suited to evaluation caches, where recency matters more than search
+/// depth.
+///
+/// ```rust
+/// use zobristhash_set::transposition::PositionCache;
+///
+/// let mut cache = PositionCache::new(2);
+/// cache.put(1, "a");
+/// cache.put(2, "b");
+/// cache.get(1);
+/// cache.put(3, "c"); // evicts 2, the least recently used
+/// assert_eq!(cache.get(2), None);
+/// assert_eq!(cache.get(1), Some(&"a"));
+/// assert_eq!(cache.hit_rate(), 2.0 / 3.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PositionCache<V> {
+    index: FxHashMap<u32, usize>,
+    nodes: Vec<Node<V>>,
+    /// Most recently used node.
+    head: usize,
+    /// Least recently used node.
+    tail: usize,
+    capacity: usize,
+    lookups: u64,
+    hits: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Node<V> {
+    hash: u64,
+    value: V,
+    prev: usize,
+    next: usize,
+}
+
+impl<V> PositionCache<V> {
+    /// Creates a cache holding up to `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            index: FxHashMap::default(),
+            nodes: Vec::with_capacity(capacity),
+            head: NIL,
+            tail: NIL,
+            capacity,
+            lookups: 0,
+            hits: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the fraction of [`get`](Self::get) calls that found their entry, or `0.0` before
+    /// the first one.
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.lookups as f64
+        }
+    }
+
+    /// Returns the value cached for `hash` and marks it as most recently used.
+    pub fn get(&mut self, hash: u64) -> Option<&V> {
+        self.lookups += 1;
+        let node = *self.index.get(&index(hash))?;
+        if verification(self.nodes[node].hash) != verification(hash) {
+            return None;
+        }
+        self.hits += 1;
+        self.move_to_front(node);
+        Some(&self.nodes[node].value)
+    }
+
+    /// Caches `value` for `hash`, evicting the least recently used entry if the cache is full.
+    pub fn put(&mut self, hash: u64, value: V) {
+        let node = match self.index.get(&index(hash)) {
+            Some(&node) => node,
+            None if self.nodes.len() < self.capacity => {
+                self.nodes.push(Node {
+                    hash,
+                    value,
+                    prev: NIL,
+                    next: NIL,
+                });
+                let node = self.nodes.len() - 1;
+                self.index.insert(index(hash), node);
+                self.link_front(node);
+                return;
+            }
+            None => {
+                let node = self.tail;
+                self.index.remove(&index(self.nodes[node].hash));
+                self.index.insert(index(hash), node);
+                node
+            }
+        };
+        self.nodes[node].hash = hash;
+        self.nodes[node].value = value;
+        self.move_to_front(node);
+    }
+
+    fn move_to_front(&mut self, node: usize) {
+        if self.head == node {
+            return;
+        }
+        let Node { prev, next, .. } = self.nodes[node];
+        self.nodes[prev].next = next;
+        match next {
+            NIL => self.tail = prev,
+            next => self.nodes[next].prev = prev,
+        }
+        self.link_front(node);
+    }
+
+    fn link_front(&mut self, node: usize) {
+        self.nodes[node].prev = NIL;
+        self.nodes[node].next = self.head;
+        match self.head {
+            NIL => self.tail = node,
+            head => self.nodes[head].prev = node,
+        }
+        self.head = node;
+    }
+}
+
+fn index(hash: u64) -> u32 {
+    hash as u32
+}
+
+fn verification(hash: u64) -> u32 {
+    (hash >> 32) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_in_lru_order() {
+        let mut cache = PositionCache::new(3);
+        for hash in 0..3 {
+            cache.put(hash, hash);
+        }
+        cache.get(0);
+        cache.put(1, 10);
+        cache.put(3, 3);
+        cache.put(4, 4);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(0), None);
+        assert_eq!(cache.get(1), Some(&10));
+        assert_eq!(cache.get(3), Some(&3));
+        assert_eq!(cache.get(4), Some(&4));
+    }
+
+    #[test]
+    fn verification_bits_reject_index_collisions() {
+        let mut cache = PositionCache::new(4);
+        cache.put(1 << 32 | 7, 'a');
+        assert_eq!(cache.get(2 << 32 | 7), None);
+        cache.put(2 << 32 | 7, 'b');
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(1 << 32 | 7), None);
+        assert_eq!(cache.get(2 << 32 | 7), Some(&'b'));
+        assert_eq!(cache.hit_rate(), 1.0 / 3.0);
+    }
+}
@@ -0,0 +1,258 @@
+use super::{fnv1a, ConcurrentTranspositionTable};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+const MAGIC: &[u8; 2] = b"ZT";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 4;
+const ENTRY_LEN: usize = 16;
+/// Keeps a frame of 64 entries, 1032 bytes, below common MTUs.
+pub const MAX_FRAME_ENTRIES: usize = 64;
+
+/// A transposition table entry as exchanged between search workers.
+///
+/// It is sent as the two words of a [`ConcurrentTranspositionTable`] slot, `hash ^ data` and
+/// `data`, so the receiver can verify it as it would a slot read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct SharedEntry {
+    pub hash: u64,
+    pub data: u64,
+}
+
+/// Encodes up to [`MAX_FRAME_ENTRIES`] entries as one frame.
+///
+/// A frame is the magic `ZT`, a version byte, an entry count byte, 16 bytes per entry, and an
+/// FNV-1a checksum of everything before it, all little-endian.
+pub fn encode_frame(entries: &[SharedEntry]) -> Vec<u8> {
+    assert!(
+        entries.len() <= MAX_FRAME_ENTRIES,
+        "too many entries for one frame"
+    );
+    let mut frame = Vec::with_capacity(HEADER_LEN + ENTRY_LEN * entries.len() + 4);
+    frame.extend_from_slice(MAGIC);
+    frame.push(VERSION);
+    frame.push(entries.len() as u8);
+    for entry in entries {
+        frame.extend_from_slice(&(entry.hash ^ entry.data).to_le_bytes());
+        frame.extend_from_slice(&entry.data.to_le_bytes());
+    }
+    let sum = fnv1a(frame.iter().copied());
+    frame.extend_from_slice(&sum.to_le_bytes());
+    frame
+}
+
+/// Decodes a frame produced by [`encode_frame`].
+pub fn decode_frame(frame: &[u8]) -> io::Result<Vec<SharedEntry>> {
+    if frame.len() < HEADER_LEN + 4 || &frame[..2] != MAGIC || frame[2] != VERSION {
+        return Err(invalid("not a transposition entry frame"));
+    }
+    let count = frame[3] as usize;
+    let body_end = HEADER_LEN + ENTRY_LEN * count;
+    if frame.len() != body_end + 4 {
+        return Err(invalid("frame length does not match its entry count"));
+    }
+    let sum = u32::from_le_bytes(frame[body_end..].try_into().unwrap());
+    if sum != fnv1a(frame[..body_end].iter().copied()) {
+        return Err(invalid("frame checksum mismatch"));
+    }
+    let entries = frame[HEADER_LEN..body_end]
+        .chunks_exact(ENTRY_LEN)
+        .map(|chunk| {
+            let key = u64::from_le_bytes(chunk[..8].try_into().unwrap());
+            let data = u64::from_le_bytes(chunk[8..].try_into().unwrap());
+            SharedEntry {
+                hash: key ^ data,
+                data,
+            }
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Writes `entries` to a stream, such as a TCP connection, as a sequence of frames.
+pub fn write_entries(out: &mut impl Write, entries: &[SharedEntry]) -> io::Result<()> {
+    for chunk in entries.chunks(MAX_FRAME_ENTRIES) {
+        out.write_all(&encode_frame(chunk))?;
+    }
+    out.flush()
+}
+
+/// Reads the next frame from a stream, returning `None` if it ends cleanly before one.
+pub fn read_entries(input: &mut impl Read) -> io::Result<Option<Vec<SharedEntry>>> {
+    let mut header = [0; HEADER_LEN];
+    match input.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut frame = header.to_vec();
+    frame.resize(HEADER_LEN + ENTRY_LEN * header[3] as usize + 4, 0);
+    input.read_exact(&mut frame[HEADER_LEN..])?;
+    decode_frame(&frame).map(Some)
+}
+
+/// Broadcasts selected transposition table entries to peer workers over UDP and stores the
+/// entries they send.
+///
+/// Which entries are worth sharing, typically deep results, is up to the engine: it passes them
+/// to [`share`](Self::share) and calls [`flush`](Self::flush) periodically. Delivery is best
+/// effort; lost datagrams only cost the peers some re-search.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use zobristhash_set::transposition::{ConcurrentTranspositionTable, EntrySharer};
+///
+/// let mut a = EntrySharer::bind("127.0.0.1:0")?;
+/// let b = EntrySharer::bind("127.0.0.1:0")?;
+/// a.add_peer(b.local_addr()?)?;
+///
+/// a.share(0x1234_5678, 99);
+/// a.flush()?;
+///
+/// let table = ConcurrentTranspositionTable::with_entries(1024);
+/// b.wait(Duration::from_secs(5))?;
+/// assert_eq!(b.receive_into(&table)?, 1);
+/// assert_eq!(table.probe(0x1234_5678), Some(99));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct EntrySharer {
+    socket: UdpSocket,
+    peers: Vec<SocketAddr>,
+    pending: Vec<SharedEntry>,
+}
+
+impl EntrySharer {
+    /// Binds a non-blocking UDP socket to `addr`.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            peers: Vec::new(),
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Waits up to `timeout` for entries to arrive. Returns `false` if none did.
+    pub fn wait(&self, timeout: Duration) -> io::Result<bool> {
+        self.socket.set_nonblocking(false)?;
+        self.socket.set_read_timeout(Some(timeout))?;
+        let arrived = match self.socket.peek(&mut [0]) {
+            Ok(_) => Ok(true),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(false)
+            }
+            // Windows reports a datagram larger than the buffer as an error.
+            Err(e) if e.kind() == io::ErrorKind::InvalidInput => Ok(true),
+            Err(e) => Err(e),
+        };
+        self.socket.set_nonblocking(true)?;
+        arrived
+    }
+
+    /// Adds a worker that flushed entries are sent to.
+    pub fn add_peer(&mut self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        self.peers.extend(addr.to_socket_addrs()?);
+        Ok(())
+    }
+
+    /// Queues an entry for the next [`flush`](Self::flush).
+    pub fn share(&mut self, hash: u64, data: u64) {
+        self.pending.push(SharedEntry { hash, data });
+    }
+
+    /// Sends the queued entries to every peer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        for chunk in self.pending.chunks(MAX_FRAME_ENTRIES) {
+            let frame = encode_frame(chunk);
+            for peer in &self.peers {
+                match self.socket.send_to(&frame, peer) {
+                    Ok(_) => {}
+                    // A full send buffer drops the datagram, as the network could have.
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Stores every entry received so far in `table`, returning how many there were. Does not
+    /// wait for more.
+    ///
+    /// Malformed datagrams are skipped.
+    pub fn receive_into(&self, table: &ConcurrentTranspositionTable) -> io::Result<usize> {
+        let mut buf = [0; HEADER_LEN + ENTRY_LEN * MAX_FRAME_ENTRIES + 4];
+        let mut received = 0;
+        loop {
+            let len = match self.socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(received),
+                Err(e) => return Err(e),
+            };
+            for entry in decode_frame(&buf[..len]).unwrap_or_default() {
+                table.store(entry.hash, entry.data);
+                received += 1;
+            }
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(n: u64) -> Vec<SharedEntry> {
+        (0..n)
+            .map(|i| SharedEntry {
+                hash: i.wrapping_mul(0x9E37_79B9_7F4A_7C15),
+                data: i,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frames_round_trip_and_reject_corruption() {
+        let sent = entries(3);
+        let mut frame = encode_frame(&sent);
+        assert_eq!(frame.len(), 4 + 3 * 16 + 4);
+        assert_eq!(decode_frame(&frame).unwrap(), sent);
+        frame[10] ^= 1;
+        assert!(decode_frame(&frame).is_err());
+        assert!(decode_frame(&frame[..20]).is_err());
+    }
+
+    #[test]
+    fn stream_round_trip() {
+        let sent = entries(150);
+        let mut stream = Vec::new();
+        write_entries(&mut stream, &sent).unwrap();
+        let mut input = stream.as_slice();
+        let mut received = Vec::new();
+        while let Some(frame) = read_entries(&mut input).unwrap() {
+            assert!(frame.len() <= MAX_FRAME_ENTRIES);
+            received.extend(frame);
+        }
+        assert_eq!(received, sent);
+    }
+}
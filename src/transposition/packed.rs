@@ -0,0 +1,200 @@
+use std::mem::size_of;
+
+/// A user-defined transposition table entry with a compact layout, stored in a
+/// [`PackedTranspositionTable`].
+///
+/// The entry carries 16 verification bits of the hash next to the payload (score, depth, bound,
+/// move, ...), packed however the engine likes.
+pub trait PackedEntry: Copy + Default {
+    fn verification(&self) -> u16;
+
+    fn set_verification(&mut self, verification: u16);
+
+    /// Returns `true` for a slot that holds no entry. [`Default::default`] must be empty.
+    fn is_empty(&self) -> bool;
+
+    /// Entries with lower priority, typically the search depth, are evicted first.
+    fn priority(&self) -> i32;
+}
+
+#[derive(Clone, Copy, Debug)]
+#[repr(C, align(64))]
+struct Bucket<T, const N: usize>([T; N]);
+
+/// A transposition table of [`PackedEntry`]s, `N` to a bucket, with every bucket occupying one
+/// aligned cache line.
+///
+/// A probe therefore touches exactly one line of memory. Instantiating the table with entries
+/// too large for `N` of them to fit in 64 bytes fails to compile.
+///
+/// ```rust
+/// use zobristhash_set::transposition::{PackedEntry, PackedTranspositionTable};
+///
+/// #[derive(Clone, Copy, Default, Debug, PartialEq)]
+/// struct Entry {
+///     key: u16,
+///     best_move: u16,
+///     score: i16,
+///     depth: i8,
+///     bound: u8, // 0 for an empty slot
+/// }
+///
+/// impl PackedEntry for Entry {
+///     fn verification(&self) -> u16 {
+///         self.key
+///     }
+///     fn set_verification(&mut self, verification: u16) {
+///         self.key = verification;
+///     }
+///     fn is_empty(&self) -> bool {
+///         self.bound == 0
+///     }
+///     fn priority(&self) -> i32 {
+///         self.depth.into()
+///     }
+/// }
+///
+/// let mut table = PackedTranspositionTable::<Entry, 8>::with_entries(1 << 16);
+/// let entry = Entry { best_move: 12, score: -35, depth: 9, bound: 1, ..Entry::default() };
+/// table.store(0xfeed_0000_0000_1234, entry);
+/// assert_eq!(table.probe(0xfeed_0000_0000_1234).map(|e| e.score), Some(-35));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PackedTranspositionTable<T, const N: usize> {
+    buckets: Vec<Bucket<T, N>>,
+    mask: usize,
+}
+
+impl<T: PackedEntry, const N: usize> PackedTranspositionTable<T, N> {
+    const FITS_CACHE_LINE: () = assert!(
+        N > 0 && size_of::<[T; N]>() <= 64,
+        "a bucket of packed entries must fit in one cache line"
+    );
+
+    /// Creates a table with room for about `entries` values; the number of buckets is
+    /// `entries / N` rounded down to a power of two.
+    pub fn with_entries(entries: usize) -> Self {
+        let () = Self::FITS_CACHE_LINE;
+        let buckets = match entries / N {
+            0 => 1,
+            n => 1 << n.ilog2(),
+        };
+        Self {
+            buckets: vec![Bucket([T::default(); N]); buckets],
+            mask: buckets - 1,
+        }
+    }
+
+    /// Creates the largest table that fits in `bytes`.
+    pub fn with_bytes(bytes: usize) -> Self {
+        Self::with_entries(bytes / size_of::<Bucket<T, N>>() * N)
+    }
+
+    /// Returns the number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * N
+    }
+
+    /// Returns the entry stored for `hash`, if any.
+    pub fn probe(&self, hash: u64) -> Option<T> {
+        let verification = verification(hash);
+        self.buckets[hash as usize & self.mask]
+            .0
+            .iter()
+            .find(|entry| !entry.is_empty() && entry.verification() == verification)
+            .copied()
+    }
+
+    /// Stores `entry` for `hash`, setting its verification bits.
+    ///
+    /// An existing entry for `hash` is overwritten; otherwise the entry takes an empty slot or
+    /// evicts the one with the lowest priority.
+    pub fn store(&mut self, hash: u64, mut entry: T) {
+        let verification = verification(hash);
+        entry.set_verification(verification);
+        let bucket = &mut self.buckets[hash as usize & self.mask].0;
+        let slot = match bucket
+            .iter()
+            .position(|e| e.is_empty() || e.verification() == verification)
+        {
+            Some(slot) => slot,
+            None => (0..N).rev().min_by_key(|&i| bucket[i].priority()).unwrap(),
+        };
+        bucket[slot] = entry;
+    }
+
+    /// Hints the CPU to start loading the bucket of `hash` into cache.
+    pub fn prefetch(&self, hash: u64) {
+        super::prefetch(&self.buckets[hash as usize & self.mask]);
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        self.buckets.fill(Bucket([T::default(); N]));
+    }
+}
+
+fn verification(hash: u64) -> u16 {
+    (hash >> 48) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Default, Debug, PartialEq)]
+    struct Entry {
+        key: u16,
+        depth: i16,
+        value: u32,
+    }
+
+    impl PackedEntry for Entry {
+        fn verification(&self) -> u16 {
+            self.key
+        }
+
+        fn set_verification(&mut self, verification: u16) {
+            self.key = verification;
+        }
+
+        fn is_empty(&self) -> bool {
+            self.depth == 0
+        }
+
+        fn priority(&self) -> i32 {
+            self.depth.into()
+        }
+    }
+
+    fn entry(depth: i16, value: u32) -> Entry {
+        Entry {
+            key: 0,
+            depth,
+            value,
+        }
+    }
+
+    #[test]
+    fn buckets_are_cache_lines() {
+        let table = PackedTranspositionTable::<Entry, 8>::with_bytes(1 << 12);
+        assert_eq!(table.capacity(), 512);
+        assert_eq!(size_of::<Bucket<Entry, 8>>(), 64);
+        assert_eq!(table.buckets.as_ptr() as usize % 64, 0);
+    }
+
+    #[test]
+    fn shallowest_entry_is_evicted() {
+        let mut table = PackedTranspositionTable::<Entry, 2>::with_entries(2);
+        table.store(1 << 48, entry(5, 1));
+        table.store(2 << 48, entry(3, 2));
+        table.store(3 << 48, entry(4, 3));
+        assert_eq!(table.probe(1 << 48).map(|e| e.value), Some(1));
+        assert_eq!(table.probe(2 << 48), None);
+        assert_eq!(table.probe(3 << 48).map(|e| e.value), Some(3));
+        table.store(1 << 48, entry(1, 4));
+        assert_eq!(table.probe(1 << 48).map(|e| e.value), Some(4));
+        table.clear();
+        assert_eq!(table.probe(1 << 48), None);
+    }
+}
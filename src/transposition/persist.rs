@@ -0,0 +1,222 @@
+use super::{Entry, ReplacementPolicy, TranspositionTable};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 6] = b"ZOBRTT";
+const FORMAT_VERSION: u16 = 1;
+/// Identifies how hashes map to buckets and verification bits. A file written under another
+/// scheme would be silently misread, so it is rejected.
+const HASH_SCHEME_VERSION: u16 = 1;
+/// The bytes before the first slot: magic, versions, bucket and value sizes, length and
+/// generation.
+const HEADER_BYTES: u64 = MAGIC.len() as u64 + 2 + 2 + 4 + 4 + 8 + 1;
+
+/// A value with a fixed-size binary encoding, so it can be saved with a [`TranspositionTable`].
+pub trait Persist: Sized {
+    const SIZE: usize;
+
+    /// Writes exactly [`SIZE`](Self::SIZE) bytes to `out`.
+    fn write(&self, out: &mut [u8]);
+
+    /// Decodes a value from exactly [`SIZE`](Self::SIZE) bytes.
+    fn read(bytes: &[u8]) -> Self;
+}
+
+macro_rules! persist_int {
+    ($($int:ty),*) => {$(
+        impl Persist for $int {
+            const SIZE: usize = std::mem::size_of::<$int>();
+
+            fn write(&self, out: &mut [u8]) {
+                out.copy_from_slice(&self.to_le_bytes());
+            }
+
+            fn read(bytes: &[u8]) -> Self {
+                <$int>::from_le_bytes(bytes.try_into().unwrap())
+            }
+        }
+    )*};
+}
+
+persist_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl<A: Persist, B: Persist> Persist for (A, B) {
+    const SIZE: usize = A::SIZE + B::SIZE;
+
+    fn write(&self, out: &mut [u8]) {
+        let (a, b) = out.split_at_mut(A::SIZE);
+        self.0.write(a);
+        self.1.write(b);
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let (a, b) = bytes.split_at(A::SIZE);
+        (A::read(a), B::read(b))
+    }
+}
+
+impl<V: Persist, P: ReplacementPolicy<V>> TranspositionTable<V, P> {
+    /// Writes the table, including its current generation, to the file at `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        out.write_all(&HASH_SCHEME_VERSION.to_le_bytes())?;
+        out.write_all(&(P::WAYS as u32).to_le_bytes())?;
+        out.write_all(&(V::SIZE as u32).to_le_bytes())?;
+        out.write_all(&(self.slots.len() as u64).to_le_bytes())?;
+        out.write_all(&[self.generation])?;
+        let mut value = vec![0; V::SIZE];
+        for slot in &self.slots {
+            match &slot.entry {
+                Some(entry) => {
+                    out.write_all(&[1])?;
+                    out.write_all(&entry.verification.to_le_bytes())?;
+                    out.write_all(&[entry.generation])?;
+                    entry.value.write(&mut value);
+                    out.write_all(&value)?;
+                }
+                None => out.write_all(&[0])?,
+            }
+        }
+        out.flush()
+    }
+
+    /// Reads a table written by [`save`](Self::save), evicting according to `policy`.
+    pub fn load_with_policy(path: impl AsRef<Path>, policy: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut input = BufReader::new(file);
+        let mut magic = [0; 6];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a transposition table file"));
+        }
+        if read_u16(&mut input)? != FORMAT_VERSION {
+            return Err(invalid("unsupported format version"));
+        }
+        if read_u16(&mut input)? != HASH_SCHEME_VERSION {
+            return Err(invalid("unsupported hash scheme version"));
+        }
+        if read_u32(&mut input)? != P::WAYS as u32 {
+            return Err(invalid("bucket size does not match the replacement policy"));
+        }
+        if read_u32(&mut input)? != V::SIZE as u32 {
+            return Err(invalid("value size does not match the value type"));
+        }
+        let len = read_u64(&mut input)?;
+        let buckets = len / P::WAYS as u64;
+        if !buckets.is_power_of_two() || buckets * P::WAYS as u64 != len {
+            return Err(invalid("invalid table size"));
+        }
+        // Every slot takes at least its tag byte, so a length beyond the rest of the file is
+        // corrupt, and is rejected before the table is allocated.
+        let len = match usize::try_from(len) {
+            Ok(len) if len as u64 <= file_len.saturating_sub(HEADER_BYTES) => len,
+            _ => return Err(invalid("table size exceeds the file")),
+        };
+
+        let mut table = Self::with_policy(len, policy);
+        table.generation = read_u8(&mut input)?;
+        let mut value = vec![0; V::SIZE];
+        for slot in &mut table.slots {
+            match read_u8(&mut input)? {
+                0 => {}
+                1 => {
+                    let verification = read_u32(&mut input)?;
+                    let generation = read_u8(&mut input)?;
+                    input.read_exact(&mut value)?;
+                    slot.entry = Some(Entry {
+                        verification,
+                        generation,
+                        value: V::read(&value),
+                    });
+                }
+                _ => return Err(invalid("invalid slot tag")),
+            }
+        }
+        Ok(table)
+    }
+}
+
+impl<V: Persist, P: ReplacementPolicy<V> + Default> TranspositionTable<V, P> {
+    /// Reads a table written by [`save`](Self::save).
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::load_with_policy(path, P::default())
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u8(input: &mut impl Read) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u16(input: &mut impl Read) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    input.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transposition::TwoSlot;
+
+    #[test]
+    fn round_trip() {
+        let path = std::env::temp_dir().join(format!("zobrist-tt-{}.bin", std::process::id()));
+        let depth = |&(_, depth): &(u32, i8)| i32::from(depth);
+        let mut table = TranspositionTable::with_policy(64, TwoSlot::new(depth));
+        table.store(1 << 32 | 3, (10, 4));
+        table.new_search();
+        table.store(2 << 32 | 3, (20, 2));
+        table.store(3 << 32 | 5, (30, -1));
+        table.save(&path).unwrap();
+
+        let loaded = TranspositionTable::load_with_policy(&path, TwoSlot::new(depth)).unwrap();
+        assert_eq!(loaded.capacity(), 64);
+        assert_eq!(loaded.probe_with_age(1 << 32 | 3), Some((&(10, 4), 1)));
+        assert_eq!(loaded.probe_with_age(2 << 32 | 3), Some((&(20, 2), 0)));
+        assert_eq!(loaded.probe(3 << 32 | 5), Some(&(30, -1)));
+
+        // The value type is part of the format.
+        let error = TranspositionTable::<u64>::load(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn oversized_lengths_are_rejected() {
+        let path = std::env::temp_dir().join(format!("zobrist-tt-len-{}.bin", std::process::id()));
+        TranspositionTable::<u64>::with_entries(64)
+            .save(&path)
+            .unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        let len = HEADER_BYTES as usize - 9;
+        bytes[len..len + 8].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        std::fs::write(&path, bytes).unwrap();
+
+        let error = TranspositionTable::<u64>::load(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "table size exceeds the file");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+use super::Slot;
+
+/// Decides which entry of a full [`TranspositionTable`](super::TranspositionTable) bucket a new
+/// value evicts.
+pub trait ReplacementPolicy<V> {
+    /// The number of slots in each bucket.
+    const WAYS: usize;
+
+    /// Returns the slot of `bucket` that `new` should be written to, or `None` to discard it.
+    ///
+    /// Only called when no slot of `bucket` holds an entry for the same hash.
+    fn victim(&self, bucket: &[Slot<V>], new: &V) -> Option<usize>;
+}
+
+/// One slot per bucket, always overwritten.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct AlwaysReplace;
+
+impl<V> ReplacementPolicy<V> for AlwaysReplace {
+    const WAYS: usize = 1;
+
+    fn victim(&self, _bucket: &[Slot<V>], _new: &V) -> Option<usize> {
+        Some(0)
+    }
+}
+
+/// One slot per bucket, overwritten only by results searched at least as deep.
+#[derive(Clone, Copy, Debug)]
+pub struct DepthPreferred<V> {
+    depth: fn(&V) -> i32,
+}
+
+impl<V> DepthPreferred<V> {
+    /// Creates the policy, reading the search depth of a value with `depth`.
+    pub fn new(depth: fn(&V) -> i32) -> Self {
+        Self { depth }
+    }
+}
+
+impl<V> ReplacementPolicy<V> for DepthPreferred<V> {
+    const WAYS: usize = 1;
+
+    fn victim(&self, bucket: &[Slot<V>], new: &V) -> Option<usize> {
+        match bucket[0].value() {
+            Some(old) if (self.depth)(old) > (self.depth)(new) => None,
+            _ => Some(0),
+        }
+    }
+}
+
+/// Two slots per bucket: a depth-preferred one, and one that takes everything the first rejects.
+#[derive(Clone, Copy, Debug)]
+pub struct TwoSlot<V> {
+    depth: fn(&V) -> i32,
+}
+
+impl<V> TwoSlot<V> {
+    /// Creates the policy, reading the search depth of a value with `depth`.
+    pub fn new(depth: fn(&V) -> i32) -> Self {
+        Self { depth }
+    }
+}
+
+impl<V> ReplacementPolicy<V> for TwoSlot<V> {
+    const WAYS: usize = 2;
+
+    fn victim(&self, bucket: &[Slot<V>], new: &V) -> Option<usize> {
+        match bucket[0].value() {
+            Some(old) if (self.depth)(old) > (self.depth)(new) => Some(1),
+            _ => Some(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transposition::TranspositionTable;
+
+    // Hashes sharing bucket 0 of a small table.
+    const A: u64 = 1 << 32;
+    const B: u64 = 2 << 32;
+    const C: u64 = 3 << 32;
+
+    fn depth(&(_, depth): &(char, i32)) -> i32 {
+        depth
+    }
+
+    #[test]
+    fn depth_preferred_keeps_deeper_results() {
+        let mut table = TranspositionTable::with_policy(8, DepthPreferred::new(depth));
+        table.store(A, ('a', 5));
+        table.store(B, ('b', 3));
+        assert_eq!(table.probe(A), Some(&('a', 5)));
+        assert_eq!(table.probe(B), None);
+        table.store(B, ('b', 5));
+        assert_eq!(table.probe(B), Some(&('b', 5)));
+        // The same position is always updated, even with a shallower result.
+        table.store(B, ('b', 1));
+        assert_eq!(table.probe(B), Some(&('b', 1)));
+    }
+
+    #[test]
+    fn two_slot_falls_back_to_always_slot() {
+        let mut table = TranspositionTable::with_policy(8, TwoSlot::new(depth));
+        table.store(A, ('a', 5));
+        table.store(B, ('b', 3));
+        table.store(C, ('c', 2));
+        assert_eq!(table.probe(A), Some(&('a', 5)));
+        assert_eq!(table.probe(B), None);
+        assert_eq!(table.probe(C), Some(&('c', 2)));
+        table.store(B, ('b', 7));
+        assert_eq!(table.probe(A), None);
+        assert_eq!(table.probe(B), Some(&('b', 7)));
+        assert_eq!(table.probe(C), Some(&('c', 2)));
+    }
+}
@@ -0,0 +1,152 @@
+use rustc_hash::FxHashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// A map from position hashes to values that many threads can update at once, such as the node
+/// table of a parallel Monte Carlo tree search.
+///
+/// Entries are spread over a power-of-two number of independently locked shards, chosen by the
+/// high bits of the hash, so threads only contend when they touch the same shard.
+///
+/// ```rust
+/// use zobristhash_set::transposition::ShardedPositionCache;
+///
+/// let cache = ShardedPositionCache::new(16);
+/// std::thread::scope(|scope| {
+///     for thread in 0..4u64 {
+///         let cache = &cache;
+///         scope.spawn(move || cache.insert(thread << 60, thread));
+///     }
+/// });
+/// assert_eq!(cache.len(), 4);
+/// assert_eq!(cache.get(3 << 60), Some(3));
+/// ```
+#[derive(Debug)]
+pub struct ShardedPositionCache<V> {
+    shards: Box<[Mutex<FxHashMap<u64, V>>]>,
+    shift: u32,
+}
+
+impl<V> ShardedPositionCache<V> {
+    /// Creates an empty cache with `shards` shards, rounded up to a power of two.
+    pub fn new(shards: usize) -> Self {
+        let shards = shards.max(1).next_power_of_two();
+        Self {
+            shards: (0..shards).map(|_| Default::default()).collect(),
+            shift: 64 - shards.ilog2(),
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Reserves room for `additional` more entries in every shard.
+    pub fn reserve_per_shard(&self, additional: usize) {
+        for shard in self.shards.iter() {
+            lock(shard).reserve(additional);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a copy of the value stored for `hash`.
+    pub fn get(&self, hash: u64) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.shard(hash).get(&hash).cloned()
+    }
+
+    /// Calls `f` with the value for `hash`, inserting `default()` first if there is none.
+    ///
+    /// The shard stays locked while `f` runs.
+    pub fn update<R>(
+        &self,
+        hash: u64,
+        default: impl FnOnce() -> V,
+        f: impl FnOnce(&mut V) -> R,
+    ) -> R {
+        f(self.shard(hash).entry(hash).or_insert_with(default))
+    }
+
+    /// Stores `value` for `hash`, returning the previous value.
+    pub fn insert(&self, hash: u64, value: V) -> Option<V> {
+        self.shard(hash).insert(hash, value)
+    }
+
+    pub fn remove(&self, hash: u64) -> Option<V> {
+        self.shard(hash).remove(&hash)
+    }
+
+    /// Calls `f` on every entry, one shard at a time.
+    pub fn for_each(&self, mut f: impl FnMut(u64, &V)) {
+        for shard in self.shards.iter() {
+            for (&hash, value) in lock(shard).iter() {
+                f(hash, value);
+            }
+        }
+    }
+
+    /// Removes the entries for which `keep` returns `false`, one shard at a time, so other
+    /// threads can keep using the rest of the cache during garbage collection.
+    pub fn retain(&self, mut keep: impl FnMut(u64, &mut V) -> bool) {
+        for shard in self.shards.iter() {
+            lock(shard).retain(|&hash, value| keep(hash, value));
+        }
+    }
+
+    fn shard(&self, hash: u64) -> MutexGuard<'_, FxHashMap<u64, V>> {
+        // `checked_shr` covers the single-shard case, where the shift is 64.
+        let index = hash.checked_shr(self.shift).unwrap_or(0) as usize;
+        lock(&self.shards[index])
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_updates_and_gc() {
+        let cache = ShardedPositionCache::new(6);
+        assert_eq!(cache.shard_count(), 8);
+        cache.reserve_per_shard(16);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for i in 0..1000u64 {
+                        let hash = i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                        cache.update(hash, || 0u32, |visits| *visits += 1);
+                    }
+                });
+            }
+        });
+        assert_eq!(cache.len(), 1000);
+        let mut total = 0;
+        cache.for_each(|_, &visits| total += visits);
+        assert_eq!(total, 4000);
+
+        cache.retain(|hash, _| hash % 2 == 0);
+        cache.for_each(|hash, _| assert_eq!(hash % 2, 0));
+        assert!(cache.len() < 1000);
+    }
+
+    #[test]
+    fn single_shard() {
+        let cache = ShardedPositionCache::new(0);
+        assert_eq!(cache.shard_count(), 1);
+        cache.insert(u64::MAX, 'a');
+        assert_eq!(cache.remove(u64::MAX), Some('a'));
+        assert!(cache.is_empty());
+    }
+}
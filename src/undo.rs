@@ -0,0 +1,160 @@
+use crate::{FxKeys, KeySource, ZobristHashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A Zobrist hash that records every update, so updates can be undone and redone.
+///
+/// [`undo`](Self::undo) reverts the most recent update that has not been undone yet, and
+/// [`redo`](Self::redo) reapplies the most recently undone one. As in an editor, a new update
+/// discards the updates that could still be redone. The hash and the debug checker follow
+/// every step, so make/unmake code only has to record its moves once.
+///
+/// ```rust
+/// use zobristhash_set::UndoLog;
+///
+/// let mut position = UndoLog::empty();
+/// position.add(&("knight", 1));
+/// let start = position.hash();
+///
+/// position.remove(&("knight", 1));
+/// position.add(&("knight", 18));
+/// assert!(position.undo() && position.undo());
+/// assert_eq!(position.hash(), start);
+///
+/// assert!(position.redo());
+/// assert_eq!(position.undo_len(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct UndoLog<E, K = FxKeys> {
+    set: ZobristHashSet<E, K>,
+    done: Vec<Change<E>>,
+    undone: Vec<Change<E>>,
+}
+
+#[derive(Clone, Debug)]
+enum Change<E> {
+    Added(E),
+    Removed(E),
+}
+
+impl<E> UndoLog<E> {
+    pub fn empty() -> Self {
+        Self::with_keys(FxKeys)
+    }
+}
+
+impl<E> Default for UndoLog<E> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<E, K> UndoLog<E, K> {
+    /// Creates an empty set with an empty history that derives element keys from `keys`.
+    pub fn with_keys(keys: K) -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(keys),
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.set.hash
+    }
+
+    /// Returns the number of updates that can be undone.
+    pub fn undo_len(&self) -> usize {
+        self.done.len()
+    }
+
+    /// Returns the number of updates that can be redone.
+    pub fn redo_len(&self) -> usize {
+        self.undone.len()
+    }
+
+    /// Forgets the history, keeping the current hash.
+    pub fn clear_history(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
+
+impl<E, K> From<UndoLog<E, K>> for u64 {
+    fn from(log: UndoLog<E, K>) -> u64 {
+        log.set.hash
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug, K: KeySource<E>> UndoLog<E, K> {
+    pub fn add(&mut self, key: &E) {
+        self.set.add(key);
+        self.done.push(Change::Added(key.clone()));
+        self.undone.clear();
+    }
+
+    pub fn remove(&mut self, key: &E) {
+        self.set.remove(key);
+        self.done.push(Change::Removed(key.clone()));
+        self.undone.clear();
+    }
+
+    /// Reverts the most recent update, returning `false` if there is none.
+    pub fn undo(&mut self) -> bool {
+        let Some(change) = self.done.pop() else {
+            return false;
+        };
+        match &change {
+            Change::Added(key) => self.set.remove(key),
+            Change::Removed(key) => self.set.add(key),
+        };
+        self.undone.push(change);
+        true
+    }
+
+    /// Reapplies the most recently undone update, returning `false` if there is none.
+    pub fn redo(&mut self) -> bool {
+        let Some(change) = self.undone.pop() else {
+            return false;
+        };
+        match &change {
+            Change::Added(key) => self.set.add(key),
+            Change::Removed(key) => self.set.remove(key),
+        };
+        self.done.push(change);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_walk_the_history() {
+        let mut log = UndoLog::empty();
+        let mut hashes = vec![log.hash()];
+        for i in 0..10u32 {
+            log.add(&i);
+            hashes.push(log.hash());
+        }
+        log.remove(&3);
+        hashes.push(log.hash());
+
+        for expected in hashes.iter().rev().skip(1) {
+            assert!(log.undo());
+            assert_eq!(log.hash(), *expected);
+        }
+        assert!(!log.undo());
+        for expected in &hashes[1..] {
+            assert!(log.redo());
+            assert_eq!(log.hash(), *expected);
+        }
+        assert!(!log.redo());
+
+        log.undo();
+        log.add(&100);
+        assert_eq!(log.redo_len(), 0);
+        assert_eq!(log.undo_len(), 11);
+    }
+}
@@ -0,0 +1,131 @@
+#[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+use crate::copiable_hash::CopiableHash;
+use crate::{Combiner, KeySource, VerifyError, ZobristHashSet};
+use core::fmt::Debug;
+use core::hash::Hash;
+
+impl<E, K: KeySource<E>, C: Combiner, const CHECK_CAPACITY: usize>
+    ZobristHashSet<E, K, C, CHECK_CAPACITY>
+{
+    /// Checks the hash against the one computed from scratch for `elements`, the full state the
+    /// set is supposed to describe.
+    ///
+    /// Incremental updates that were missed or applied twice make the two differ. Calling this
+    /// every few moves in tests catches such drift close to where it happened.
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// let board = vec![("king", 4), ("rook", 0)];
+    /// let mut hash = ZobristHashSet::empty();
+    /// hash.add(&("king", 4));
+    /// assert!(hash.verify(&board).is_err());
+    ///
+    /// hash.add(&("rook", 0));
+    /// assert_eq!(hash.verify(&board), Ok(()));
+    /// ```
+    pub fn verify<'a, I>(&self, elements: I) -> Result<(), VerifyError>
+    where
+        I: IntoIterator<Item = &'a E>,
+        E: 'a,
+    {
+        let recomputed = elements.into_iter().fold(C::IDENTITY, |hash, element| {
+            C::combine(hash, self.keys.key(element))
+        });
+        if recomputed == self.hash {
+            Ok(())
+        } else {
+            Err(VerifyError {
+                incremental: self.hash,
+                recomputed,
+            })
+        }
+    }
+}
+
+impl<E: Hash + Eq + Clone + Debug> ZobristHashSet<E> {
+    /// Creates a set holding `elements`, hashed from scratch with the default key source.
+    ///
+    /// ```rust
+    /// use zobristhash_set::ZobristHashSet;
+    ///
+    /// let board = [("king", 4), ("rook", 0)];
+    /// let hash = ZobristHashSet::rebuild_from(&board);
+    /// assert_eq!(hash.verify(&board), Ok(()));
+    /// ```
+    pub fn rebuild_from<'a, I>(elements: I) -> Self
+    where
+        I: IntoIterator<Item = &'a E>,
+        E: 'a,
+    {
+        let mut set = Self::empty();
+        set.rebuild(elements);
+        set
+    }
+}
+
+impl<E, K, C, const CHECK_CAPACITY: usize> ZobristHashSet<E, K, C, CHECK_CAPACITY>
+where
+    E: Hash + Eq + Clone + Debug,
+    K: KeySource<E>,
+    C: Combiner,
+{
+    /// Discards the current hash and recomputes it from `elements`, keeping the key source.
+    ///
+    /// This repairs a set whose hash has drifted, for example after [`verify`](Self::verify)
+    /// failed. In checked builds the checker is reset to track exactly `elements`.
+    pub fn rebuild<'a, I>(&mut self, elements: I)
+    where
+        I: IntoIterator<Item = &'a E>,
+        E: 'a,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("rebuild").entered();
+        self.hash = C::IDENTITY;
+        #[cfg(all(debug_assertions, feature = "check_set_behavior"))]
+        {
+            self.checker = Some(CopiableHash::empty());
+        }
+        #[cfg(feature = "count_guard")]
+        {
+            self.len_delta = 0;
+        }
+        for element in elements {
+            self.add(element);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxKeys, KeySource, VerifyError, WrappingAdd, ZobristHashSet};
+
+    #[test]
+    fn rebuild_repairs_drift() {
+        let board = [3u8, 5, 8];
+        let mut hash = ZobristHashSet::rebuild_from(&board[..2]);
+        hash.add(&13);
+        assert!(hash.verify(&board).is_err());
+
+        hash.rebuild(&board);
+        assert_eq!(hash.verify(&board), Ok(()));
+        // The checker follows the rebuilt state.
+        hash.remove(&8);
+        hash.add(&13);
+    }
+
+    #[test]
+    fn reports_both_hashes() {
+        let mut hash = ZobristHashSet::with_combiner(FxKeys, WrappingAdd);
+        hash.add(&1u32);
+        hash.add(&1u32);
+        assert_eq!(hash.verify(&[1, 1]), Ok(()));
+        assert_eq!(
+            hash.verify(&[1]),
+            Err(VerifyError {
+                incremental: FxKeys.key(&1u32).wrapping_mul(2),
+                recomputed: FxKeys.key(&1u32),
+            })
+        );
+    }
+}
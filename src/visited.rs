@@ -0,0 +1,273 @@
+mod open;
+#[cfg(feature = "roaring")]
+mod roaring;
+mod sorted;
+
+pub use open::OpenAddressing;
+#[cfg(feature = "roaring")]
+pub use roaring::FoldedRoaring;
+pub use sorted::SortedBlocks;
+
+use rustc_hash::FxHashSet;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::marker::PhantomData;
+
+/// Storage for the hashes recorded by a [`VisitedSet`].
+pub trait VisitedBackend {
+    /// The number of hash bits the backend actually tells apart.
+    const HASH_BITS: u32 = 64;
+
+    /// Creates an empty backend prepared to hold about `len` hashes.
+    fn with_expected_len(len: usize) -> Self
+    where
+        Self: Sized;
+
+    /// Records `hash`, returning `true` if it was not present yet.
+    fn insert(&mut self, hash: u64) -> bool;
+
+    fn contains(&self, hash: u64) -> bool;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an estimate of the heap memory used, in bytes.
+    fn memory_bytes(&self) -> usize;
+}
+
+impl<S: BuildHasher + Default> VisitedBackend for HashSet<u64, S> {
+    fn with_expected_len(len: usize) -> Self {
+        HashSet::with_capacity_and_hasher(len, S::default())
+    }
+
+    fn insert(&mut self, hash: u64) -> bool {
+        HashSet::insert(self, hash)
+    }
+
+    fn contains(&self, hash: u64) -> bool {
+        HashSet::contains(self, &hash)
+    }
+
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        // Each bucket holds the value and a control byte; the table keeps 1/8 of them free.
+        self.capacity() * 8 / 7 * (std::mem::size_of::<u64>() + 1)
+    }
+}
+
+/// The set of states seen so far by a search, identified by their 64-bit hashes.
+///
+/// Two distinct states with the same hash are indistinguishable, so [`insert`](Self::insert)
+/// can wrongly report a new state as already visited and the search would skip it. With
+/// uniformly distributed hashes the chance that this happens at all among `n` states is about
+/// `n² / 2^65` (see [`false_positive_probability`](Self::false_positive_probability)): negligible
+/// for millions of states, but around 1% for half a billion. Searches that must be exact should
+/// confirm hits against the full state.
+///
+/// The hashes are kept in an [`FxHashSet`] unless another [`VisitedBackend`] is picked with
+/// [`builder`](Self::builder).
+///
+/// ```rust
+/// use zobristhash_set::VisitedSet;
+///
+/// let mut visited = VisitedSet::new();
+/// assert!(visited.insert(0xdead_beef));
+/// assert!(!visited.insert(0xdead_beef));
+/// assert_eq!(visited.len(), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct VisitedSet<B = FxHashSet<u64>> {
+    backend: B,
+}
+
+impl VisitedSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts configuring a set, with the default backend.
+    ///
+    /// ```rust
+    /// use zobristhash_set::VisitedSet;
+    ///
+    /// let mut visited = VisitedSet::builder()
+    ///     .expected_len(1 << 20)
+    ///     .open_addressing()
+    ///     .build();
+    /// assert!(visited.insert(7));
+    /// ```
+    pub fn builder() -> VisitedSetBuilder {
+        VisitedSetBuilder {
+            expected_len: 0,
+            _backend: PhantomData,
+        }
+    }
+}
+
+/// Builder for [`VisitedSet`], selecting its backend.
+#[derive(Clone, Copy, Debug)]
+pub struct VisitedSetBuilder<B = FxHashSet<u64>> {
+    expected_len: usize,
+    _backend: PhantomData<B>,
+}
+
+impl<B> VisitedSetBuilder<B> {
+    /// Sets the number of states the set should be prepared for.
+    pub fn expected_len(mut self, len: usize) -> Self {
+        self.expected_len = len;
+        self
+    }
+
+    /// Uses an [`FxHashSet`], the default.
+    pub fn hash_set(self) -> VisitedSetBuilder<FxHashSet<u64>> {
+        self.backend()
+    }
+
+    /// Uses [`SortedBlocks`].
+    pub fn sorted_blocks(self) -> VisitedSetBuilder<SortedBlocks> {
+        self.backend()
+    }
+
+    /// Uses [`OpenAddressing`].
+    pub fn open_addressing(self) -> VisitedSetBuilder<OpenAddressing> {
+        self.backend()
+    }
+
+    /// Uses [`FoldedRoaring`].
+    #[cfg(feature = "roaring")]
+    pub fn roaring(self) -> VisitedSetBuilder<FoldedRoaring> {
+        self.backend()
+    }
+
+    /// Uses any other backend.
+    pub fn backend<C>(self) -> VisitedSetBuilder<C> {
+        VisitedSetBuilder {
+            expected_len: self.expected_len,
+            _backend: PhantomData,
+        }
+    }
+}
+
+impl<B: VisitedBackend> VisitedSetBuilder<B> {
+    pub fn build(self) -> VisitedSet<B> {
+        VisitedSet::with_backend(B::with_expected_len(self.expected_len))
+    }
+}
+
+impl<B: VisitedBackend> VisitedSet<B> {
+    /// Creates a set that stores its hashes in `backend`.
+    pub fn with_backend(backend: B) -> Self {
+        Self { backend }
+    }
+
+    /// Records `hash`, returning `true` if it was not visited before.
+    pub fn insert(&mut self, hash: u64) -> bool {
+        self.backend.insert(hash)
+    }
+
+    pub fn contains(&self, hash: u64) -> bool {
+        self.backend.contains(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.backend.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backend.is_empty()
+    }
+
+    /// Returns an estimate of the heap memory used, in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.backend.memory_bytes()
+    }
+
+    /// Returns the probability that any two of the recorded states share a hash, assuming
+    /// uniformly distributed hashes.
+    ///
+    /// Backends that keep fewer than 64 bits of each hash are accounted for.
+    pub fn false_positive_probability(&self) -> f64 {
+        let n = self.len() as f64;
+        -(-n * (n - 1.0) / 2f64.powi(B::HASH_BITS as i32 + 1)).exp_m1()
+    }
+
+    /// Returns the backend.
+    pub fn into_backend(self) -> B {
+        self.backend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_and_risk_grow_with_size() {
+        let mut visited = VisitedSet::new();
+        assert_eq!(visited.false_positive_probability(), 0.0);
+        for hash in 0..10_000 {
+            assert!(visited.insert(hash));
+        }
+        assert!(visited.contains(42));
+        assert!(visited.memory_bytes() >= 10_000 * 9);
+        let risk = visited.false_positive_probability();
+        assert!(risk > 0.0 && risk < 1e-11, "{risk}");
+
+        let mut big = VisitedSet::with_backend(HashSet::<u64>::new());
+        big.insert(1);
+        assert_eq!(big.into_backend().len(), 1);
+    }
+
+    fn check_backend<B: VisitedBackend>(mut visited: VisitedSet<B>) {
+        // Mixed and structured hashes, including the extremes.
+        let mut hashes: Vec<u64> = (0..10_000u64)
+            .map(|i| i.wrapping_mul(0x2545_F491_4F6C_DD1D))
+            .chain((0..10_000u64).map(|i| i << 40))
+            .chain([u64::MAX])
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        for &hash in &hashes {
+            assert!(visited.insert(hash), "{hash}");
+        }
+        for &hash in &hashes {
+            assert!(visited.contains(hash));
+            assert!(!visited.insert(hash));
+        }
+        assert_eq!(visited.len(), hashes.len());
+        assert!(visited.memory_bytes() > 0);
+    }
+
+    #[test]
+    fn backends() {
+        check_backend(VisitedSet::builder().build());
+        check_backend(VisitedSet::builder().sorted_blocks().build());
+        check_backend(VisitedSet::builder().open_addressing().build());
+        check_backend(
+            VisitedSet::builder()
+                .expected_len(100)
+                .open_addressing()
+                .build(),
+        );
+        let sorted = VisitedSet::builder().sorted_blocks().build();
+        assert!(!sorted.contains(3));
+    }
+
+    #[test]
+    #[cfg(feature = "roaring")]
+    fn roaring_backend() {
+        let mut visited = VisitedSet::builder().roaring().build();
+        for hash in 0..1000u64 {
+            assert!(visited.insert(hash << 40));
+        }
+        assert!(visited.contains(5 << 40));
+        assert_eq!(visited.len(), 1000);
+        assert!(visited.false_positive_probability() > 1e-4);
+    }
+}
@@ -0,0 +1,86 @@
+use super::VisitedBackend;
+
+/// A [`VisitedBackend`] storing hashes in a flat, linearly probed table.
+///
+/// Slots are bare `u64`s with `0` marking an empty one (the hash `0` itself is tracked
+/// separately), so the table costs 8 bytes per slot, at most 80% of which are in use before it
+/// doubles.
+#[derive(Clone, Debug)]
+pub struct OpenAddressing {
+    slots: Vec<u64>,
+    len: usize,
+    has_zero: bool,
+}
+
+impl Default for OpenAddressing {
+    fn default() -> Self {
+        Self::with_expected_len(0)
+    }
+}
+
+impl OpenAddressing {
+    fn shift(&self) -> u32 {
+        64 - self.slots.len().ilog2()
+    }
+
+    /// Returns the slot holding `hash`, or the empty slot where it belongs.
+    fn find(&self, hash: u64) -> usize {
+        let mask = self.slots.len() - 1;
+        // The multiplication spreads hashes whose low bits are weak over the whole table.
+        let mut i = (hash.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> self.shift()) as usize;
+        while self.slots[i] != 0 && self.slots[i] != hash {
+            i = (i + 1) & mask;
+        }
+        i
+    }
+
+    fn grow(&mut self) {
+        let doubled = vec![0; self.slots.len() * 2];
+        let old = std::mem::replace(&mut self.slots, doubled);
+        for hash in old.into_iter().filter(|&hash| hash != 0) {
+            let i = self.find(hash);
+            self.slots[i] = hash;
+        }
+    }
+}
+
+impl VisitedBackend for OpenAddressing {
+    fn with_expected_len(len: usize) -> Self {
+        Self {
+            slots: vec![0; (len * 5 / 4 + 1).next_power_of_two().max(16)],
+            len: 0,
+            has_zero: false,
+        }
+    }
+
+    fn insert(&mut self, hash: u64) -> bool {
+        if hash == 0 {
+            return !std::mem::replace(&mut self.has_zero, true);
+        }
+        let i = self.find(hash);
+        if self.slots[i] == hash {
+            return false;
+        }
+        self.slots[i] = hash;
+        self.len += 1;
+        if self.len * 5 > self.slots.len() * 4 {
+            self.grow();
+        }
+        true
+    }
+
+    fn contains(&self, hash: u64) -> bool {
+        match hash {
+            0 => self.has_zero,
+            hash => self.slots[self.find(hash)] == hash,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len + usize::from(self.has_zero)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.slots.capacity() * std::mem::size_of::<u64>()
+    }
+}
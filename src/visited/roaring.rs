@@ -0,0 +1,41 @@
+use super::VisitedBackend;
+use roaring::RoaringBitmap;
+
+/// A [`VisitedBackend`] storing hashes folded to 32 bits in a compressed
+/// [roaring bitmap](https://roaringbitmap.org/).
+///
+/// Dense sets take close to one bit per possible value, far less than 8 bytes per hash once
+/// hundreds of millions of states are recorded. Folding makes distinct states collide much
+/// sooner, though: already at 100 000 states a false "visited" is likely.
+#[derive(Clone, Debug, Default)]
+pub struct FoldedRoaring {
+    bitmap: RoaringBitmap,
+}
+
+fn fold(hash: u64) -> u32 {
+    (hash.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) as u32
+}
+
+impl VisitedBackend for FoldedRoaring {
+    const HASH_BITS: u32 = 32;
+
+    fn with_expected_len(_len: usize) -> Self {
+        Self::default()
+    }
+
+    fn insert(&mut self, hash: u64) -> bool {
+        self.bitmap.insert(fold(hash))
+    }
+
+    fn contains(&self, hash: u64) -> bool {
+        self.bitmap.contains(fold(hash))
+    }
+
+    fn len(&self) -> usize {
+        self.bitmap.len() as usize
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.bitmap.serialized_size()
+    }
+}
@@ -0,0 +1,87 @@
+use super::VisitedBackend;
+
+/// A [`VisitedBackend`] storing hashes in sorted vectors, at 8 bytes per hash with no slack.
+///
+/// New hashes go to a small sorted buffer that is merged, in place, into the main vector once it
+/// holds about `sqrt(len)` hashes. The main vector grows to exactly the merged length, beyond
+/// the expected length it was created with, instead of doubling. Lookups are two binary
+/// searches; inserts cost `O(sqrt(len))` amortized, so it trades speed for memory when holding
+/// hundreds of millions of states.
+#[derive(Clone, Debug, Default)]
+pub struct SortedBlocks {
+    sorted: Vec<u64>,
+    recent: Vec<u64>,
+}
+
+impl SortedBlocks {
+    fn merge(&mut self) {
+        let (mut i, mut j) = (self.sorted.len(), self.recent.len());
+        self.sorted.reserve_exact(j);
+        self.sorted.resize(i + j, 0);
+        // Fill from the back so no element is overwritten before it is moved.
+        for k in (0..i + j).rev() {
+            if j == 0 {
+                break;
+            }
+            if i > 0 && self.sorted[i - 1] > self.recent[j - 1] {
+                self.sorted[k] = self.sorted[i - 1];
+                i -= 1;
+            } else {
+                self.sorted[k] = self.recent[j - 1];
+                j -= 1;
+            }
+        }
+        self.recent.clear();
+    }
+}
+
+impl VisitedBackend for SortedBlocks {
+    fn with_expected_len(len: usize) -> Self {
+        Self {
+            sorted: Vec::with_capacity(len),
+            recent: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, hash: u64) -> bool {
+        if self.sorted.binary_search(&hash).is_ok() {
+            return false;
+        }
+        let Err(position) = self.recent.binary_search(&hash) else {
+            return false;
+        };
+        self.recent.insert(position, hash);
+        if self.recent.len() > self.sorted.len().isqrt().max(1024) {
+            self.merge();
+        }
+        true
+    }
+
+    fn contains(&self, hash: u64) -> bool {
+        self.sorted.binary_search(&hash).is_ok() || self.recent.binary_search(&hash).is_ok()
+    }
+
+    fn len(&self) -> usize {
+        self.sorted.len() + self.recent.len()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        (self.sorted.capacity() + self.recent.capacity()) * std::mem::size_of::<u64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_leave_no_slack() {
+        let mut blocks = SortedBlocks::with_expected_len(0);
+        for hash in 0..5000u64 {
+            blocks.insert(hash.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        }
+        assert!(!blocks.sorted.is_empty());
+        assert_eq!(blocks.sorted.capacity(), blocks.sorted.len());
+        assert!(blocks.sorted.is_sorted());
+    }
+}
@@ -0,0 +1,147 @@
+//! JavaScript bindings through `wasm-bindgen`, built with the `wasm` feature.
+//!
+//! Elements are byte arrays (`Uint8Array`) keyed with [`BytesKeys`], which derives the same keys
+//! on 32-bit WebAssembly as on 64-bit hosts. Hashes are `BigInt`s holding the full 64 bits, so a
+//! browser GUI computes the same hashes as a native engine using
+//! `ZobristHashSet<Vec<u8>, BytesKeys>`. Misuse that the debug checker catches throws an `Error`.
+//!
+//! ```js
+//! import { RepetitionTracker, ZobristHashSet } from "zobristhash_set";
+//!
+//! const position = new ZobristHashSet();
+//! position.add(new TextEncoder().encode("Ke1"));
+//! const history = new RepetitionTracker();
+//! history.push(position.hash);
+//! ```
+
+use crate::{BytesKeys, RepetitionTracker, ZobristHashSet};
+use wasm_bindgen::prelude::*;
+
+/// A Zobrist hash of a set of byte strings.
+#[wasm_bindgen(js_name = ZobristHashSet)]
+pub struct JsZobristHashSet {
+    set: ZobristHashSet<Vec<u8>, BytesKeys>,
+}
+
+#[wasm_bindgen(js_class = ZobristHashSet)]
+impl JsZobristHashSet {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            set: ZobristHashSet::with_keys(BytesKeys),
+        }
+    }
+
+    pub fn add(&mut self, element: &[u8]) -> Result<(), JsError> {
+        self.set.try_add(&element.to_vec())?;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, element: &[u8]) -> Result<(), JsError> {
+        self.set.try_remove(&element.to_vec())?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hash(&self) -> u64 {
+        self.set.hash()
+    }
+
+    #[wasm_bindgen(js_name = clone)]
+    pub fn duplicate(&self) -> Self {
+        Self {
+            set: self.set.clone(),
+        }
+    }
+}
+
+impl Default for JsZobristHashSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tracks the position hashes of a game to detect repetitions, as [`RepetitionTracker`] does.
+#[wasm_bindgen(js_name = RepetitionTracker)]
+#[derive(Default)]
+pub struct JsRepetitionTracker {
+    tracker: RepetitionTracker,
+}
+
+#[wasm_bindgen(js_class = RepetitionTracker)]
+impl JsRepetitionTracker {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.tracker.len()
+    }
+
+    pub fn push(&mut self, hash: u64) {
+        self.tracker.push(hash);
+    }
+
+    #[wasm_bindgen(js_name = pushReset)]
+    pub fn push_reset(&mut self, hash: u64) {
+        self.tracker.push_reset(hash);
+    }
+
+    pub fn pop(&mut self) -> Option<u64> {
+        self.tracker.pop()
+    }
+
+    pub fn count(&self, hash: u64) -> u32 {
+        self.tracker.count(hash)
+    }
+
+    #[wasm_bindgen(js_name = isThreefold)]
+    pub fn is_threefold(&self) -> bool {
+        self.tracker.is_threefold()
+    }
+
+    #[wasm_bindgen(js_name = setRoot)]
+    pub fn set_root(&mut self) {
+        self.tracker.set_root();
+    }
+
+    #[wasm_bindgen(js_name = searchCount)]
+    pub fn search_count(&self, hash: u64) -> u32 {
+        self.tracker.search_count(hash)
+    }
+
+    #[wasm_bindgen(js_name = isSearchDraw)]
+    pub fn is_search_draw(&self) -> bool {
+        self.tracker.is_search_draw()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_match_rust_hashes() {
+        let mut position = JsZobristHashSet::new();
+        position.add(b"Ke1").unwrap();
+        position.add(b"Ke8").unwrap();
+        let mut expected = ZobristHashSet::with_keys(BytesKeys);
+        expected.add(&b"Ke8".to_vec());
+        expected.add(&b"Ke1".to_vec());
+        assert_eq!(position.hash(), expected.hash());
+        // Pinned, so the value computed on wasm32 is the one computed here.
+        assert_eq!(position.hash(), 0x2552_b32a_b567_7a56);
+
+        let mut history = JsRepetitionTracker::new();
+        for _ in 0..3 {
+            history.push(position.hash());
+            history.push(!position.hash());
+        }
+        history.pop();
+        assert_eq!(history.count(position.hash()), 3);
+        assert!(history.is_threefold());
+        assert_eq!(history.length(), 5);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::keys::mix64;
+use crate::KeySource;
+
+/// A key source for `u64` and `u32` elements whose batch updates derive four keys at a time.
+///
+/// The key of a word is the SplitMix64 output for it, so keys are close to uniform like those
+/// of [`SplitMixKeys`](crate::SplitMixKeys) but do not go through `FxHash`. That keeps them
+/// cheap to compute in vector registers: [`add_batch`](crate::ZobristHashSet::add_batch) and
+/// [`remove_batch`](crate::ZobristHashSet::remove_batch) hash four words per iteration with AVX2
+/// on x86-64 processors that support it, detected at runtime with the `std` feature and at
+/// compile time without it, and one word at a time elsewhere. The keys are the same either way.
+///
+/// ```rust
+/// use zobristhash_set::{KeySource, WordKeys, ZobristHashSet};
+///
+/// let squares: Vec<u64> = (0..64).collect();
+/// let mut batched = ZobristHashSet::with_keys(WordKeys);
+/// batched.add_batch(&squares);
+///
+/// let looped = squares.iter().fold(0, |hash, square| hash ^ WordKeys.key(square));
+/// assert_eq!(batched.hash(), looped);
+/// ```
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct WordKeys;
+
+const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+fn word_key(word: u64) -> u64 {
+    mix64(word.wrapping_add(GAMMA))
+}
+
+impl KeySource<u64> for WordKeys {
+    fn key(&self, element: &u64) -> u64 {
+        word_key(*element)
+    }
+
+    fn xor_keys(&self, elements: &[u64]) -> u64 {
+        xor_word_keys(elements)
+    }
+}
+
+impl KeySource<u32> for WordKeys {
+    fn key(&self, element: &u32) -> u64 {
+        word_key(u64::from(*element))
+    }
+
+    fn xor_keys(&self, elements: &[u32]) -> u64 {
+        xor_word_keys(elements)
+    }
+}
+
+/// An element type [`WordKeys`] hashes as one 64-bit word.
+trait Word: Copy {
+    fn widen(self) -> u64;
+
+    /// Loads four consecutive words into the lanes of a vector.
+    ///
+    /// # Safety
+    ///
+    /// `words` must point to four readable words, and the processor must support AVX2.
+    #[cfg(target_arch = "x86_64")]
+    unsafe fn load4(words: *const Self) -> core::arch::x86_64::__m256i;
+}
+
+impl Word for u64 {
+    fn widen(self) -> u64 {
+        self
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn load4(words: *const u64) -> core::arch::x86_64::__m256i {
+        core::arch::x86_64::_mm256_loadu_si256(words.cast())
+    }
+}
+
+impl Word for u32 {
+    fn widen(self) -> u64 {
+        u64::from(self)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn load4(words: *const u32) -> core::arch::x86_64::__m256i {
+        use core::arch::x86_64::{_mm256_cvtepu32_epi64, _mm_loadu_si128};
+
+        _mm256_cvtepu32_epi64(_mm_loadu_si128(words.cast()))
+    }
+}
+
+fn xor_word_keys<W: Word>(words: &[W]) -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    if avx2::available() {
+        // SAFETY: the processor supports AVX2.
+        return unsafe { avx2::xor_word_keys(words) };
+    }
+    xor_word_keys_scalar(words)
+}
+
+fn xor_word_keys_scalar<W: Word>(words: &[W]) -> u64 {
+    words
+        .iter()
+        .fold(0, |hash, word| hash ^ word_key(word.widen()))
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use super::{xor_word_keys_scalar, Word, GAMMA};
+    use core::arch::x86_64::*;
+
+    pub(super) fn available() -> bool {
+        #[cfg(feature = "std")]
+        {
+            std::is_x86_feature_detected!("avx2")
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            cfg!(target_feature = "avx2")
+        }
+    }
+
+    /// Computes the XOR of the keys of `words`, four lanes per iteration.
+    ///
+    /// # Safety
+    ///
+    /// The processor must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn xor_word_keys<W: Word>(words: &[W]) -> u64 {
+        let gamma = _mm256_set1_epi64x(GAMMA as i64);
+        let mut hash = _mm256_setzero_si256();
+        let mut chunks = words.chunks_exact(4);
+        for chunk in &mut chunks {
+            // The SplitMix64 finalizer, as in `mix64`.
+            let z = _mm256_add_epi64(W::load4(chunk.as_ptr()), gamma);
+            let z = mul(
+                _mm256_xor_si256(z, _mm256_srli_epi64::<30>(z)),
+                0xBF58_476D_1CE4_E5B9,
+            );
+            let z = mul(
+                _mm256_xor_si256(z, _mm256_srli_epi64::<27>(z)),
+                0x94D0_49BB_1331_11EB,
+            );
+            hash = _mm256_xor_si256(hash, _mm256_xor_si256(z, _mm256_srli_epi64::<31>(z)));
+        }
+        let mut lanes = [0u64; 4];
+        _mm256_storeu_si256(lanes.as_mut_ptr().cast(), hash);
+        lanes
+            .iter()
+            .fold(xor_word_keys_scalar(chunks.remainder()), |a, b| a ^ b)
+    }
+
+    /// Multiplies each 64-bit lane of `a` by `m`, wrapping like [`u64::wrapping_mul`].
+    ///
+    /// AVX2 only multiplies 32-bit halves, so the product is assembled from the low product and
+    /// the two cross products; the high product only affects bits past 64.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn mul(a: __m256i, m: u64) -> __m256i {
+        let low = _mm256_set1_epi64x(m as i64);
+        let high = _mm256_set1_epi64x((m >> 32) as i64);
+        let cross = _mm256_add_epi64(
+            _mm256_mul_epu32(_mm256_srli_epi64::<32>(a), low),
+            _mm256_mul_epu32(a, high),
+        );
+        _mm256_add_epi64(_mm256_mul_epu32(a, low), _mm256_slli_epi64::<32>(cross))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZobristHashSet;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn vector_keys_match_scalar_keys() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let wide: Vec<u64> = (0..103).map(|_| rng.gen()).collect();
+        let narrow: Vec<u32> = (0..103).map(|_| rng.gen()).collect();
+        for len in 0..wide.len() {
+            assert_eq!(
+                WordKeys.xor_keys(&wide[..len]),
+                xor_word_keys_scalar(&wide[..len])
+            );
+            assert_eq!(
+                WordKeys.xor_keys(&narrow[..len]),
+                xor_word_keys_scalar(&narrow[..len])
+            );
+        }
+        assert_eq!(WordKeys.key(&7u32), WordKeys.key(&7u64));
+        assert_ne!(WordKeys.key(&0u64), 0);
+    }
+
+    #[test]
+    fn batches_match_single_updates() {
+        let squares: Vec<u64> = (0..64).collect();
+        let mut batched = ZobristHashSet::with_keys(WordKeys);
+        batched.add_batch(&squares);
+        let mut looped = ZobristHashSet::with_keys(WordKeys);
+        for square in &squares {
+            looped.add(square);
+        }
+        assert_eq!(batched.hash(), looped.hash());
+        batched.remove_batch(&squares[..37]);
+        for square in &squares[..37] {
+            looped.remove(square);
+        }
+        assert_eq!(batched.hash(), looped.hash());
+    }
+}